@@ -0,0 +1,303 @@
+use crate::config::Config;
+use crate::error::{Result, RuptureError};
+use crate::io::InputFormat;
+use crate::model::{contagion_index, AssetPoint, ContagionRow, EngineRun};
+use crate::parallel::parallel_map;
+use crate::pipeline::{run_file, write_outputs};
+use crate::types::RuptureEvent;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Component, Path, PathBuf};
+
+/// One asset in a batch run.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchItem {
+    /// Asset name, used for the output subdirectory and summary rows.
+    pub name: String,
+    pub input: PathBuf,
+    /// Full config file for this asset, replacing the base config.
+    #[serde(default)]
+    pub config: Option<PathBuf>,
+    /// Partial config merged on top of the base (or per-asset) config.
+    #[serde(default)]
+    pub overrides: Option<toml::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    #[serde(rename = "asset", default)]
+    assets: Vec<BatchItem>,
+}
+
+/// Result of running one asset.
+#[derive(Debug, Clone)]
+pub struct AssetOutcome {
+    pub name: String,
+    pub result: std::result::Result<AssetSummary, String>,
+}
+
+/// Summary of a successful asset run.
+#[derive(Debug, Clone)]
+pub struct AssetSummary {
+    pub bars: usize,
+    pub events: Vec<RuptureEvent>,
+    pub output_dir: PathBuf,
+    /// Per-bar rho and state, kept only when `[contagion]` is enabled.
+    pub points: Vec<AssetPoint>,
+}
+
+/// Build the batch item list from a directory of CSV files or a TOML
+/// manifest of `[[asset]]` entries.
+pub fn batch_items(path: &Path) -> Result<Vec<BatchItem>> {
+    let items = if path.is_dir() {
+        discover_inputs(path)?
+    } else {
+        load_manifest(path)?
+    };
+
+    let mut seen = HashSet::new();
+    for item in &items {
+        // The name is the asset's output subdirectory, so it must be one
+        // plain path component.
+        let mut components = Path::new(&item.name).components();
+        let plain = matches!(components.next(), Some(Component::Normal(_)))
+            && components.next().is_none()
+            && !item.name.contains(['/', '\\']);
+        if !plain {
+            return Err(RuptureError::Config(format!(
+                "asset name '{}' must be a non-empty file name without path separators or '..'",
+                item.name
+            )));
+        }
+        if !seen.insert(item.name.as_str()) {
+            return Err(RuptureError::Config(format!(
+                "duplicate asset name '{}' in batch",
+                item.name
+            )));
+        }
+    }
+    Ok(items)
+}
+
+/// List every `.csv` file in a directory (and Parquet and Arrow IPC files,
+/// with the `parquet` feature) as a batch item named after its file stem, in
+/// file name order.
+pub fn discover_inputs(dir: &Path) -> Result<Vec<BatchItem>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            let format = InputFormat::from_path(p);
+            p.is_file()
+                && format.is_supported()
+                && (format != InputFormat::Csv
+                    || p.extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv")))
+        })
+        .collect();
+    paths.sort();
+
+    Ok(paths
+        .into_iter()
+        .map(|input| BatchItem {
+            name: input
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            input,
+            config: None,
+            overrides: None,
+        })
+        .collect())
+}
+
+/// Load a TOML manifest. Relative paths are resolved against the manifest's
+/// directory.
+///
+/// ```toml
+/// [[asset]]
+/// name = "SPY"
+/// input = "spy_daily.csv"
+///
+/// [[asset]]
+/// name = "TLT"
+/// input = "tlt_daily.csv"
+/// overrides = { residuals = { theta_vol = 2.5 } }
+/// ```
+pub fn load_manifest(path: &Path) -> Result<Vec<BatchItem>> {
+    let content = fs::read_to_string(path)?;
+    let manifest: Manifest = toml::from_str(&content)
+        .map_err(|e| RuptureError::Config(format!("batch manifest {}: {}", path.display(), e)))?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+
+    Ok(manifest
+        .assets
+        .into_iter()
+        .map(|mut item| {
+            item.input = base.join(&item.input);
+            item.config = item.config.map(|c| base.join(c));
+            item
+        })
+        .collect())
+}
+
+/// Run the pipeline for every item on up to `jobs` threads, writing each
+/// asset's outputs to `output_dir/<name>/`. A failure on one asset (including
+/// a panic) is recorded in its outcome and does not stop the others.
+pub fn run_batch(
+    items: &[BatchItem],
+    base_config: &Config,
+    output_dir: &Path,
+    jobs: usize,
+) -> Vec<AssetOutcome> {
+    parallel_map(items, jobs, |item| {
+        let result =
+            match catch_unwind(AssertUnwindSafe(|| run_item(item, base_config, output_dir))) {
+                Ok(result) => result.map_err(|e| e.to_string()),
+                Err(panic) => Err(panic
+                    .downcast_ref::<&str>()
+                    .map(|s| format!("panic: {}", s))
+                    .or_else(|| {
+                        panic
+                            .downcast_ref::<String>()
+                            .map(|s| format!("panic: {}", s))
+                    })
+                    .unwrap_or_else(|| "panic".into())),
+            };
+        AssetOutcome {
+            name: item.name.clone(),
+            result,
+        }
+    })
+}
+
+fn run_item(item: &BatchItem, base_config: &Config, output_dir: &Path) -> Result<AssetSummary> {
+    let config = match &item.config {
+        Some(path) => Config::from_file(path)?,
+        None => base_config.clone(),
+    };
+    let config = match &item.overrides {
+        Some(overrides) => config.with_overrides(overrides)?,
+        None => config,
+    };
+
+    let run = run_file(&item.input, &config)?;
+    let asset_dir = output_dir.join(&item.name);
+    fs::create_dir_all(&asset_dir)?;
+    write_outputs(&run, &config, &asset_dir)?;
+    let EngineRun { rows, events, .. } = run;
+
+    let points = if base_config.contagion.enabled {
+        rows.iter().map(AssetPoint::from).collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(AssetSummary {
+        bars: rows.len(),
+        events,
+        output_dir: asset_dir,
+        points,
+    })
+}
+
+/// Aggregate the successful assets into the contagion index, using the base
+/// config's `[contagion]` and `[state_machine]` sections.
+pub fn batch_contagion(
+    outcomes: &[AssetOutcome],
+    base_config: &Config,
+) -> Result<(Vec<ContagionRow>, Vec<RuptureEvent>)> {
+    let series: Vec<Vec<AssetPoint>> = outcomes
+        .iter()
+        .filter_map(|o| o.result.as_ref().ok())
+        .map(|s| s.points.clone())
+        .collect();
+    contagion_index(&series, &base_config.contagion, &base_config.state_machine)
+}
+
+/// Write one row per asset: status, bar count, event counts, and the error
+/// message for failed assets.
+pub fn write_batch_summary(outcomes: &[AssetOutcome], path: &Path) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record([
+        "asset",
+        "status",
+        "bars",
+        "candidates",
+        "confirmed",
+        "error",
+    ])?;
+
+    for outcome in outcomes {
+        match &outcome.result {
+            Ok(summary) => {
+                let confirmed = summary
+                    .events
+                    .iter()
+                    .filter(|e| e.confirmed_index.is_some())
+                    .count();
+                writer.write_record([
+                    outcome.name.clone(),
+                    "ok".into(),
+                    summary.bars.to_string(),
+                    summary.events.len().to_string(),
+                    confirmed.to_string(),
+                    String::new(),
+                ])?;
+            }
+            Err(message) => {
+                writer.write_record([
+                    outcome.name.clone(),
+                    "failed".into(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    message.clone(),
+                ])?;
+            }
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write every event from every successful asset into one table.
+pub fn write_batch_events(outcomes: &[AssetOutcome], path: &Path) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record([
+        "asset",
+        "candidate_index",
+        "candidate_timestamp",
+        "confirmed_index",
+        "confirmed_timestamp",
+        "peak_rho",
+    ])?;
+
+    for outcome in outcomes {
+        let Ok(summary) = &outcome.result else {
+            continue;
+        };
+        for event in &summary.events {
+            writer.write_record([
+                outcome.name.clone(),
+                event.candidate_index.to_string(),
+                event.candidate_timestamp.to_string(),
+                event
+                    .confirmed_index
+                    .map(|i| i.to_string())
+                    .unwrap_or_default(),
+                event
+                    .confirmed_timestamp
+                    .as_ref()
+                    .map(|t| t.to_string())
+                    .unwrap_or_default(),
+                format!("{:.8}", event.peak_rho),
+            ])?;
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
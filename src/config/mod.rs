@@ -0,0 +1,1089 @@
+use crate::error::{Result, RuptureError};
+use crate::types::RuptureState;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+mod validate;
+
+pub use validate::{Severity, ValidationIssue, ValidationReport};
+
+/// The commented default configuration, `configs/default.toml`. Parses to
+/// `Config::default()`.
+pub const DEFAULT_CONFIG_TOML: &str = include_str!("../../configs/default.toml");
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub io: IoConfig,
+    pub preprocess: PreprocessConfig,
+    pub numerics: NumericsConfig,
+    pub windows: WindowsConfig,
+    pub features: FeaturesConfig,
+    pub robust: RobustConfig,
+    pub residuals: ResidualsConfig,
+    pub combine: CombineConfig,
+    pub memory: MemoryConfig,
+    pub capacity: CapacityConfig,
+    pub state_machine: StateMachineConfig,
+    pub outputs: OutputsConfig,
+    pub gaps: GapsConfig,
+    pub contagion: ContagionConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IoConfig {
+    pub timestamp_column: String,
+    pub open_column: String,
+    pub high_column: String,
+    pub low_column: String,
+    pub close_column: String,
+    pub volume_column: String,
+    pub parse_timestamps: bool,
+    /// Timestamp format used when `parse_timestamps` is set: "auto", "rfc3339",
+    /// "iso8601", "unix_s", "unix_ms", or a chrono format string.
+    pub timestamp_format: String,
+    /// Time zone for timestamps without an offset: "UTC", a fixed offset such
+    /// as "+05:30", or an IANA name such as "America/New_York".
+    pub timezone: String,
+    pub drop_invalid_rows: bool,
+    pub sort_by_timestamp: bool,
+}
+
+impl Default for IoConfig {
+    fn default() -> Self {
+        Self {
+            timestamp_column: "timestamp".into(),
+            open_column: "open".into(),
+            high_column: "high".into(),
+            low_column: "low".into(),
+            close_column: "close".into(),
+            volume_column: "volume".into(),
+            parse_timestamps: false,
+            timestamp_format: default_timestamp_format(),
+            timezone: default_timezone(),
+            drop_invalid_rows: true,
+            sort_by_timestamp: false,
+        }
+    }
+}
+
+fn default_timestamp_format() -> String {
+    "auto".into()
+}
+
+fn default_timezone() -> String {
+    "UTC".into()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreprocessConfig {
+    pub require_positive_close: bool,
+    pub allow_zero_volume: bool,
+    pub min_rows: usize,
+}
+
+impl Default for PreprocessConfig {
+    fn default() -> Self {
+        Self {
+            require_positive_close: true,
+            allow_zero_volume: true,
+            min_rows: 600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NumericsConfig {
+    pub eps: f64,
+    pub clip_residuals: bool,
+    pub residual_clip_max: f64,
+}
+
+impl Default for NumericsConfig {
+    fn default() -> Self {
+        Self {
+            eps: 1.0e-12,
+            clip_residuals: true,
+            residual_clip_max: 25.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowsConfig {
+    pub robust_scale_n: usize,
+    pub memory_k: usize,
+    pub capacity_l: usize,
+}
+
+impl Default for WindowsConfig {
+    fn default() -> Self {
+        Self {
+            robust_scale_n: 20,
+            memory_k: 200,
+            capacity_l: 500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeaturesConfig {
+    pub use_log_returns: bool,
+    /// Per-bar volatility estimator used for the range feature `g`.
+    pub volatility_estimator: VolatilityEstimator,
+    /// Feed a range-based estimator into its own residual channel (`r_rng`)
+    /// instead of replacing the close-to-close `u` feature.
+    pub range_channel: bool,
+}
+
+impl Default for FeaturesConfig {
+    fn default() -> Self {
+        Self {
+            use_log_returns: true,
+            volatility_estimator: VolatilityEstimator::default(),
+            range_channel: false,
+        }
+    }
+}
+
+/// Per-bar volatility estimator. The range-based estimators use open, high,
+/// low and close, and pick up intraday stress that close-to-close returns miss.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VolatilityEstimator {
+    /// Absolute close-to-close return only; no range feature.
+    #[default]
+    CloseToClose,
+    Parkinson,
+    GarmanKlass,
+    RogersSatchell,
+    YangZhang,
+}
+
+impl VolatilityEstimator {
+    pub fn is_range_based(self) -> bool {
+        self != VolatilityEstimator::CloseToClose
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RobustConfig {
+    pub mad_scale_factor: f64,
+    pub use_volume_median: bool,
+}
+
+impl Default for RobustConfig {
+    fn default() -> Self {
+        Self {
+            mad_scale_factor: 1.4826,
+            use_volume_median: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResidualsConfig {
+    pub theta_vol: f64,
+    pub theta_liq: f64,
+    pub theta_acc: f64,
+    /// Threshold for the range channel: r_rng = max(0, g - theta_rng).
+    pub theta_rng: f64,
+    /// Residual channels in combination order. When empty, the engine uses
+    /// `vol`, `liq` and `acc` with the thresholds above, plus `rng` when
+    /// `features.range_channel` is set.
+    pub channels: Vec<ChannelConfig>,
+}
+
+impl Default for ResidualsConfig {
+    fn default() -> Self {
+        Self {
+            theta_vol: 1.25,
+            theta_liq: 1.10,
+            theta_acc: 1.25,
+            theta_rng: default_theta_rng(),
+            channels: Vec::new(),
+        }
+    }
+}
+
+/// One residual channel, e.g. `{ kind = "acc", theta = 1.5 }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelConfig {
+    /// Channel kind from the registry: vol, liq, acc, rng or gap.
+    pub kind: String,
+    /// Column name; defaults to the kind. Needed to use a kind twice.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub theta: f64,
+}
+
+impl ChannelConfig {
+    pub fn new(kind: &str, theta: f64) -> Self {
+        Self {
+            kind: kind.into(),
+            name: None,
+            theta,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.kind)
+    }
+}
+
+fn default_theta_rng() -> f64 {
+    1.25
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CombineConfig {
+    pub tau: f64,
+    pub method: CombineMethod,
+    /// Exponent for `p_norm`.
+    pub p: f64,
+    /// Per-channel weights by channel name; missing channels get 1. Used by
+    /// every method except plain `soft_max`.
+    pub weights: BTreeMap<String, f64>,
+}
+
+impl Default for CombineConfig {
+    fn default() -> Self {
+        Self {
+            tau: 0.35,
+            method: CombineMethod::default(),
+            p: default_p(),
+            weights: BTreeMap::new(),
+        }
+    }
+}
+
+fn default_p() -> f64 {
+    2.0
+}
+
+/// How residual channels are combined into `r`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CombineMethod {
+    /// r = tau * log( sum_i exp(r_i/tau) )
+    #[default]
+    SoftMax,
+    /// r = tau * log( sum_i w_i exp(r_i/tau) )
+    WeightedSoftMax,
+    /// r = sum_i w_i r_i
+    WeightedSum,
+    /// r = max_i w_i r_i
+    HardMax,
+    /// r = ( sum_i (w_i r_i)^p )^(1/p)
+    PNorm,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemoryConfig {
+    pub alpha: f64,
+    pub kernel: KernelFamily,
+    /// Decay rate for the `exponential` and `tempered_power_law` kernels.
+    pub lambda: f64,
+    /// Weights for the `file` kernel, lag 1 first. Filled from
+    /// `weights_file` when the config is loaded.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub weights: Vec<f64>,
+    /// File of weights for the `file` kernel, relative to the config file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weights_file: Option<PathBuf>,
+    pub method: MemoryMethod,
+    /// Number of exponential terms for `sum_of_exponentials`.
+    pub terms: usize,
+}
+
+impl MemoryConfig {
+    /// Read `weights_file` into `weights`, resolving a relative path against
+    /// `base_dir`. The resolved path is stored back so that reloading from
+    /// a snapshot or merged config finds the same file.
+    pub fn load_weights_file(&mut self, base_dir: &Path) -> Result<()> {
+        let Some(file) = &self.weights_file else {
+            return Ok(());
+        };
+        let path = base_dir.join(file);
+        let content = fs::read_to_string(&path).map_err(|e| {
+            RuptureError::Config(format!("memory.weights_file '{}': {}", path.display(), e))
+        })?;
+        self.weights = parse_weights(&content).map_err(|e| {
+            RuptureError::Config(format!("memory.weights_file '{}': {}", path.display(), e))
+        })?;
+        self.weights_file = Some(path);
+        Ok(())
+    }
+}
+
+/// Parse a weight vector: numbers separated by commas, whitespace or
+/// newlines, with `#` starting a comment.
+fn parse_weights(content: &str) -> std::result::Result<Vec<f64>, String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or(""))
+        .flat_map(|line| line.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            token
+                .parse::<f64>()
+                .map_err(|_| format!("invalid weight '{}'", token))
+        })
+        .collect()
+}
+
+/// Shape of the memory weights w_k over lags k = 1..K. Every family is
+/// normalised to sum to 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KernelFamily {
+    /// w_k ~ k^(-alpha)
+    #[default]
+    PowerLaw,
+    /// w_k ~ exp(-lambda (k - 1)), an EWMA truncated at K.
+    Exponential,
+    /// Grünwald–Letnikov weights of the fractional integral of order
+    /// 1 - alpha: w_1 = 1, w_{k+1} = w_k (k - alpha) / k.
+    Fractional,
+    /// w_k ~ k^(-alpha) exp(-lambda k)
+    TemperedPowerLaw,
+    /// `memory.weights`, or the contents of `memory.weights_file`.
+    File,
+}
+
+impl KernelFamily {
+    /// Whether the weights depend on `memory.alpha`.
+    pub fn uses_alpha(self) -> bool {
+        matches!(
+            self,
+            KernelFamily::PowerLaw | KernelFamily::Fractional | KernelFamily::TemperedPowerLaw
+        )
+    }
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            alpha: 0.65,
+            kernel: KernelFamily::default(),
+            lambda: 0.0,
+            weights: Vec::new(),
+            weights_file: None,
+            method: MemoryMethod::default(),
+            terms: default_memory_terms(),
+        }
+    }
+}
+
+fn default_memory_terms() -> usize {
+    64
+}
+
+/// How the memory kernel convolution is evaluated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryMethod {
+    /// O(K) dot product per bar with the exact weights.
+    #[default]
+    Exact,
+    /// O(terms) recursion per bar with approximate weights.
+    SumOfExponentials,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CapacityConfig {
+    pub q: f64,
+    pub smooth: bool,
+    pub lambda: f64,
+    pub e_min: f64,
+}
+
+impl Default for CapacityConfig {
+    fn default() -> Self {
+        Self {
+            q: 0.95,
+            smooth: true,
+            lambda: 0.05,
+            e_min: 1.0e-6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StateMachineConfig {
+    pub rho_stressed: f64,
+    pub rho_critical: f64,
+    pub rho_rupture: f64,
+    /// Stressed (or above) is left only when rho falls below this; defaults
+    /// to `rho_stressed`, i.e. no hysteresis.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rho_stressed_exit: Option<f64>,
+    /// Critical is left only when rho falls below this; defaults to
+    /// `rho_critical`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rho_critical_exit: Option<f64>,
+    /// Minimum bars spent in Stable, Stressed or Critical before moving to
+    /// another of these states. Candidate crossings are never delayed.
+    pub min_dwell_bars: usize,
+    pub confirm_k: usize,
+    pub confirm_m: usize,
+    /// How a candidate is confirmed: m-of-k bars, or time above threshold.
+    pub confirm_mode: ConfirmMode,
+    /// Time mode: how long after the crossing the confirmation window runs,
+    /// e.g. "30m".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm_window: Option<String>,
+    /// Time mode: how long rho must be at or above `rho_rupture` within the
+    /// window, e.g. "10m".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm_duration: Option<String>,
+    /// Confirm as soon as the rule is met instead of at the end of the window.
+    pub confirm_early: bool,
+    /// Enter a Recovery phase after a confirmed rupture; otherwise the
+    /// episode ends as soon as the recovery rule is met.
+    pub enable_recovery: bool,
+    /// Bars (consecutive) with rho below `rho_critical` that end
+    /// ConfirmedRupture.
+    pub recovery_hold: usize,
+    /// Recovery ends once rho is below this for `recovery_exit_bars`
+    /// consecutive bars; defaults to `rho_critical`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_exit_rho: Option<f64>,
+    pub recovery_exit_bars: usize,
+    /// Declared states, lowest level first. When empty, the machine is built
+    /// from the thresholds above: Stable, Stressed, Critical, Candidate
+    /// rupture, Confirmed rupture and (with `enable_recovery`) Recovery.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub states: Vec<StateConfig>,
+    /// Declared transitions between `states`; given together with them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transitions: Vec<TransitionConfig>,
+}
+
+impl Default for StateMachineConfig {
+    fn default() -> Self {
+        Self {
+            rho_stressed: 0.60,
+            rho_critical: 0.85,
+            rho_rupture: 1.00,
+            rho_stressed_exit: None,
+            rho_critical_exit: None,
+            min_dwell_bars: 0,
+            confirm_k: 3,
+            confirm_m: 2,
+            confirm_mode: ConfirmMode::Bars,
+            confirm_window: None,
+            confirm_duration: None,
+            confirm_early: false,
+            enable_recovery: true,
+            recovery_hold: 2,
+            recovery_exit_rho: None,
+            recovery_exit_bars: 1,
+            states: Vec::new(),
+            transitions: Vec::new(),
+        }
+    }
+}
+
+impl StateMachineConfig {
+    /// Exit thresholds for Stressed and Critical.
+    pub fn exit_thresholds(&self) -> (f64, f64) {
+        (
+            self.rho_stressed_exit.unwrap_or(self.rho_stressed),
+            self.rho_critical_exit.unwrap_or(self.rho_critical),
+        )
+    }
+
+    /// rho below which Recovery counts towards its end.
+    pub fn recovery_exit_threshold(&self) -> f64 {
+        self.recovery_exit_rho.unwrap_or(self.rho_critical)
+    }
+
+    /// The time-mode window and required duration above threshold, when
+    /// both are set and valid.
+    pub fn confirm_time_rule(&self) -> Option<(Duration, Duration)> {
+        let window = parse_duration(self.confirm_window.as_deref()?).ok()?;
+        let duration = parse_duration(self.confirm_duration.as_deref()?).ok()?;
+        Some((window, duration))
+    }
+
+    /// The states to run: `states`, or the built-in ones when that list is
+    /// empty.
+    pub fn state_configs(&self) -> Vec<StateConfig> {
+        if !self.states.is_empty() {
+            return self.states.clone();
+        }
+        let (stressed_exit, critical_exit) = self.exit_thresholds();
+        let mut states = vec![
+            StateConfig::level(RuptureState::Stable, None, None),
+            StateConfig::level(
+                RuptureState::Stressed,
+                Some(self.rho_stressed),
+                Some(stressed_exit),
+            ),
+            StateConfig::level(
+                RuptureState::Critical,
+                Some(self.rho_critical),
+                Some(critical_exit),
+            ),
+            StateConfig::new(RuptureState::CandidateRupture),
+            StateConfig::new(RuptureState::ConfirmedRupture),
+        ];
+        if self.enable_recovery {
+            states.push(StateConfig::new(RuptureState::Recovery));
+        }
+        states
+    }
+
+    /// The transitions to run: `transitions`, or the built-in ones matching
+    /// [`state_configs`](Self::state_configs) when `states` is empty.
+    pub fn transition_configs(&self) -> Vec<TransitionConfig> {
+        if !self.states.is_empty() {
+            return self.transitions.clone();
+        }
+        let [stable, stressed, critical, candidate, confirmed, recovery] = [
+            RuptureState::Stable,
+            RuptureState::Stressed,
+            RuptureState::Critical,
+            RuptureState::CandidateRupture,
+            RuptureState::ConfirmedRupture,
+            RuptureState::Recovery,
+        ]
+        .map(RuptureState::label);
+        let crossing = |from: &str| TransitionConfig {
+            rho: Some(self.rho_rupture),
+            ..TransitionConfig::new(from, Some(candidate), TransitionGuard::Crossing)
+        };
+        let below = |from: &str, to: Option<&str>, rho: f64, bars: usize| TransitionConfig {
+            rho: Some(rho),
+            bars: Some(bars),
+            ..TransitionConfig::new(from, to, TransitionGuard::Below)
+        };
+        let mut transitions = vec![
+            crossing(stable),
+            crossing(stressed),
+            crossing(critical),
+            TransitionConfig::new(candidate, Some(confirmed), TransitionGuard::Confirmed),
+            TransitionConfig::new(candidate, None, TransitionGuard::Rejected),
+        ];
+        if self.enable_recovery {
+            transitions.extend([
+                below(
+                    confirmed,
+                    Some(recovery),
+                    self.rho_critical,
+                    self.recovery_hold,
+                ),
+                crossing(recovery),
+                below(
+                    recovery,
+                    None,
+                    self.recovery_exit_threshold(),
+                    self.recovery_exit_bars,
+                ),
+            ]);
+        } else {
+            transitions.push(below(
+                confirmed,
+                None,
+                self.rho_critical,
+                self.recovery_hold,
+            ));
+        }
+        transitions
+    }
+}
+
+/// One state of a declared state machine, e.g.
+/// `{ name = "Fragile", kind = "stressed", enter_rho = 0.45 }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateConfig {
+    pub name: String,
+    /// What the engine does in this state. Levels (`stable`, `stressed`,
+    /// `critical`) follow rho; `candidate`, `confirmed` and `recovery` are
+    /// the phases of a rupture.
+    pub kind: RuptureState,
+    /// Levels: entered when rho is at or above this. The lowest level has
+    /// none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enter_rho: Option<f64>,
+    /// Levels: left only when rho falls below this; defaults to `enter_rho`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_rho: Option<f64>,
+    /// Levels: minimum bars before moving to another level; defaults to
+    /// `min_dwell_bars`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_dwell_bars: Option<usize>,
+}
+
+impl StateConfig {
+    /// A state of `kind`, named after it.
+    pub fn new(kind: RuptureState) -> Self {
+        Self {
+            name: kind.to_string(),
+            kind,
+            enter_rho: None,
+            exit_rho: None,
+            min_dwell_bars: None,
+        }
+    }
+
+    fn level(kind: RuptureState, enter_rho: Option<f64>, exit_rho: Option<f64>) -> Self {
+        Self {
+            enter_rho,
+            exit_rho,
+            ..Self::new(kind)
+        }
+    }
+}
+
+/// A transition of a declared state machine, e.g.
+/// `{ from = "Confirmed", to = "Recovery", on = "below", rho = 0.85, bars = 2 }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransitionConfig {
+    pub from: String,
+    /// Target state; when omitted, the level matching rho.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    pub on: TransitionGuard,
+    /// `crossing`: the threshold rho crosses upwards, default `rho_rupture`.
+    /// `below`: the threshold rho stays under, default `rho_critical`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rho: Option<f64>,
+    /// `below`: consecutive bars under `rho`, default 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bars: Option<usize>,
+}
+
+impl TransitionConfig {
+    pub fn new(from: &str, to: Option<&str>, on: TransitionGuard) -> Self {
+        Self {
+            from: from.into(),
+            to: to.map(Into::into),
+            on,
+            rho: None,
+            bars: None,
+        }
+    }
+}
+
+/// When a declared transition fires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionGuard {
+    /// rho crosses `rho` upwards; leads to the candidate state.
+    Crossing,
+    /// The candidate meets the confirmation rule (`confirm_*`).
+    Confirmed,
+    /// The candidate fails the confirmation rule.
+    Rejected,
+    /// rho stays below `rho` for `bars` consecutive bars, from the confirmed
+    /// or recovery state.
+    Below,
+}
+
+/// Rule for confirming a candidate rupture.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmMode {
+    /// At least `confirm_m` of the `confirm_k` bars from the crossing are at
+    /// or above `rho_rupture`.
+    #[default]
+    Bars,
+    /// rho is at or above `rho_rupture` for at least `confirm_duration`
+    /// within `confirm_window` of the crossing. Needs parsed timestamps.
+    Time,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputsConfig {
+    pub write_csv_timeseries: bool,
+    pub csv_timeseries_name: String,
+    pub write_json_events: bool,
+    pub json_events_name: String,
+    /// Confirmed rupture episodes, from candidate to the end of Recovery.
+    pub write_json_episodes: bool,
+    pub json_episodes_name: String,
+    /// Every state change, one JSON object per line.
+    pub write_jsonl_transitions: bool,
+    pub jsonl_transitions_name: String,
+    /// The time series as typed Parquet columns. Needs the `parquet` feature.
+    pub write_parquet_timeseries: bool,
+    pub parquet_timeseries_name: String,
+    pub write_json_config_snapshot: bool,
+    pub json_config_snapshot_name: String,
+}
+
+impl Default for OutputsConfig {
+    fn default() -> Self {
+        Self {
+            write_csv_timeseries: true,
+            csv_timeseries_name: "rupture_timeseries.csv".into(),
+            write_json_events: true,
+            json_events_name: "rupture_events.json".into(),
+            write_json_episodes: true,
+            json_episodes_name: "rupture_episodes.json".into(),
+            write_jsonl_transitions: true,
+            jsonl_transitions_name: "rupture_transitions.jsonl".into(),
+            write_parquet_timeseries: false,
+            parquet_timeseries_name: "rupture_timeseries.parquet".into(),
+            write_json_config_snapshot: true,
+            json_config_snapshot_name: "config_used.json".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GapsConfig {
+    /// Detect gaps between consecutive bars. Requires `io.parse_timestamps`.
+    pub enabled: bool,
+    /// Expected spacing between bars, e.g. "1d", "5m", "30s".
+    pub expected_interval: String,
+    pub calendar: GapCalendar,
+    /// Non-trading dates (YYYY-MM-DD) for the weekdays calendar.
+    pub holidays: Vec<String>,
+    pub policy: GapPolicy,
+}
+
+impl Default for GapsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            expected_interval: "1d".into(),
+            calendar: GapCalendar::Weekdays,
+            holidays: Vec::new(),
+            policy: GapPolicy::Flag,
+        }
+    }
+}
+
+/// Which periods are expected to contain bars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapCalendar {
+    /// Every interval is expected (e.g. crypto, FX).
+    Continuous,
+    /// Monday to Friday, excluding listed holidays, in `io.timezone`.
+    Weekdays,
+}
+
+/// How the engine treats a detected gap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapPolicy {
+    /// Report gaps only.
+    Flag,
+    /// Insert synthetic bars carrying the previous close and volume.
+    ForwardFill,
+    /// Restart the return and robust-scale windows after a gap.
+    ResetWindows,
+    /// Push one zero residual into the memory kernel per missing bar.
+    Decay,
+}
+
+/// Cross-sectional aggregation over the assets of a batch run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContagionConfig {
+    /// Compute the contagion index after a batch run.
+    pub enabled: bool,
+    /// Quantile of the cross-sectional rho distribution reported per timestamp.
+    pub rho_quantile: f64,
+    /// Timestamps with fewer assets than this are skipped.
+    pub min_assets: usize,
+    /// Aggregate series fed to the systemic state machine.
+    pub signal: ContagionSignal,
+    /// Thresholds for the systemic state machine. Defaults to `[state_machine]`;
+    /// required for the breadth signal.
+    pub state_machine: Option<StateMachineConfig>,
+    pub csv_name: String,
+    pub json_events_name: String,
+}
+
+impl Default for ContagionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rho_quantile: 0.9,
+            min_assets: 1,
+            signal: ContagionSignal::MeanRho,
+            state_machine: None,
+            csv_name: "contagion.csv".into(),
+            json_events_name: "contagion_events.json".into(),
+        }
+    }
+}
+
+/// Which aggregate drives systemic rupture detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContagionSignal {
+    /// Cross-sectional mean of rho.
+    MeanRho,
+    /// Cross-sectional `rho_quantile` of rho.
+    QuantileRho,
+    /// Fraction of assets in Critical or ConfirmedRupture.
+    Breadth,
+}
+
+/// Parse a duration such as "30s", "5m", "1h", "1d" or "1w".
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (count, unit) = text.split_at(split);
+    let count: i64 = count
+        .parse()
+        .map_err(|_| RuptureError::Config(format!("invalid duration '{}'", text)))?;
+    let duration = match unit.trim() {
+        "s" => Duration::seconds(count),
+        "m" | "min" => Duration::minutes(count),
+        "h" => Duration::hours(count),
+        "d" => Duration::days(count),
+        "w" => Duration::weeks(count),
+        _ => {
+            return Err(RuptureError::Config(format!(
+                "invalid duration '{}': unit must be s, m, h, d or w",
+                text
+            )))
+        }
+    };
+    if duration <= Duration::zero() {
+        return Err(RuptureError::Config(format!(
+            "duration '{}' must be positive",
+            text
+        )));
+    }
+    Ok(duration)
+}
+
+/// Prefix of environment variables that override config keys, with `__`
+/// between key parts: `RUPTURE_STATE_MACHINE__CONFIRM_K=2` sets
+/// `state_machine.confirm_k`. Variables with the prefix but no `__`, such as
+/// `RUPTURE_HOME`, are not overrides and are ignored.
+pub const ENV_PREFIX: &str = "RUPTURE_";
+
+impl Config {
+    /// Load configuration from a TOML file. Missing keys take their
+    /// defaults; a top-level `extends = "base.toml"`, relative to the file,
+    /// loads that file first and merges this one over it.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        Self::from_value(read_layers(path, &mut Vec::new())?)
+    }
+
+    /// Load configuration the way the CLI does: the file at `path` (or the
+    /// defaults), then `RUPTURE_*` environment variables, then `overrides`
+    /// of the form `state_machine.confirm_k=2`, each layer winning over the
+    /// one before.
+    pub fn load(path: Option<&Path>, overrides: &[String]) -> Result<Self> {
+        Self::load_with_env(path, std::env::vars(), overrides)
+    }
+
+    /// [`Config::load`] with the environment passed in.
+    pub fn load_with_env<I>(path: Option<&Path>, env: I, overrides: &[String]) -> Result<Self>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut value = match path {
+            Some(path) => read_layers(path, &mut Vec::new())?,
+            None => toml::Value::Table(toml::value::Table::new()),
+        };
+
+        // (dotted key, where it came from), checked once the config is built.
+        let mut keys: Vec<(String, String)> = Vec::new();
+        let mut env: Vec<(String, String)> = env
+            .into_iter()
+            .filter(|(name, _)| {
+                name.strip_prefix(ENV_PREFIX)
+                    .is_some_and(|key| key.contains("__"))
+            })
+            .collect();
+        env.sort();
+        for (name, text) in env {
+            let key = name[ENV_PREFIX.len()..]
+                .split("__")
+                .map(str::to_lowercase)
+                .collect::<Vec<_>>()
+                .join(".");
+            set_dotted(&mut value, &key, parse_override_value(&text))
+                .map_err(|e| RuptureError::Config(format!("{}: {}", name, e)))?;
+            keys.push((key, name));
+        }
+        for arg in overrides {
+            let (key, text) = arg.split_once('=').ok_or_else(|| {
+                RuptureError::Config(format!("override '{}' must be key=value", arg))
+            })?;
+            let key = key.trim();
+            set_dotted(&mut value, key, parse_override_value(text.trim()))
+                .map_err(|e| RuptureError::Config(format!("--set {}: {}", arg, e)))?;
+            keys.push((key.to_string(), format!("--set {}", arg)));
+        }
+
+        let config = Self::from_value(value)?;
+        // Unknown keys are ignored when deserialising, so check that every
+        // override landed on a field.
+        let effective =
+            toml::Value::try_from(&config).map_err(|e| RuptureError::Config(e.to_string()))?;
+        for (key, source) in keys {
+            let found = key
+                .split('.')
+                .try_fold(&effective, |v, part| v.as_table().and_then(|t| t.get(part)));
+            if found.is_none() {
+                return Err(RuptureError::Config(format!(
+                    "{}: no such config key '{}'",
+                    source, key
+                )));
+            }
+        }
+        Ok(config)
+    }
+
+    fn from_value(value: toml::Value) -> Result<Self> {
+        let mut config: Config = value
+            .try_into()
+            .map_err(|e: toml::de::Error| RuptureError::Config(e.to_string()))?;
+        config.memory.load_weights_file(Path::new(""))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// The residual channels to run: `residuals.channels`, or the built-in
+    /// defaults when that list is empty.
+    pub fn residual_channels(&self) -> Vec<ChannelConfig> {
+        if !self.residuals.channels.is_empty() {
+            return self.residuals.channels.clone();
+        }
+        let mut channels = vec![
+            ChannelConfig::new("vol", self.residuals.theta_vol),
+            ChannelConfig::new("liq", self.residuals.theta_liq),
+            ChannelConfig::new("acc", self.residuals.theta_acc),
+        ];
+        if self.features.range_channel {
+            channels.push(ChannelConfig::new("rng", self.residuals.theta_rng));
+        }
+        channels
+    }
+
+    /// Names of the residual channels, in order.
+    pub fn residual_channel_names(&self) -> Vec<String> {
+        self.residual_channels()
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect()
+    }
+
+    /// Return a copy of this config with `overrides` deep-merged on top. The
+    /// overrides table mirrors the config layout, e.g. `{ residuals = { theta_vol = 2.5 } }`.
+    pub fn with_overrides(&self, overrides: &toml::Value) -> Result<Config> {
+        let mut value =
+            toml::Value::try_from(self).map_err(|e| RuptureError::Config(e.to_string()))?;
+        merge_toml(&mut value, overrides);
+        Self::from_value(value)
+    }
+}
+
+/// Read a config file as TOML, merged over the file it `extends`. Relative
+/// `extends` and `memory.weights_file` paths resolve against the directory
+/// of the file that names them. `chain` holds the files extending this one.
+fn read_layers(path: &Path, chain: &mut Vec<PathBuf>) -> Result<toml::Value> {
+    let content = fs::read_to_string(path).map_err(RuptureError::Io)?;
+    let mut table: toml::value::Table = toml::from_str(&content)
+        .map_err(|e| RuptureError::Config(format!("{}: {}", path.display(), e)))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    if let Some(file) = table
+        .get_mut("memory")
+        .and_then(|m| m.get_mut("weights_file"))
+    {
+        if let Some(relative) = file.as_str() {
+            *file = toml::Value::String(dir.join(relative).to_string_lossy().into_owned());
+        }
+    }
+
+    let Some(parent) = table.remove("extends") else {
+        return Ok(toml::Value::Table(table));
+    };
+    let parent = parent.as_str().ok_or_else(|| {
+        RuptureError::Config(format!("{}: extends must be a file path", path.display()))
+    })?;
+    let canonical = fs::canonicalize(path)?;
+    if chain.contains(&canonical) {
+        return Err(RuptureError::Config(format!(
+            "{}: extends cycle back to this file",
+            path.display()
+        )));
+    }
+    chain.push(canonical);
+    let mut value = read_layers(&dir.join(parent), chain)?;
+    merge_toml(&mut value, &toml::Value::Table(table));
+    Ok(value)
+}
+
+/// Set a dotted key such as `memory.alpha` in a TOML table, creating
+/// intermediate tables.
+pub fn set_dotted(root: &mut toml::Value, key: &str, value: toml::Value) -> Result<()> {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap_or_default();
+    if key.is_empty() || parts.iter().any(|p| p.is_empty()) || last.is_empty() {
+        return Err(RuptureError::Config(format!("invalid key '{}'", key)));
+    }
+    let not_table = || RuptureError::Config(format!("'{}' does not address a table", key));
+    let mut table = root;
+    for part in parts {
+        table = table
+            .as_table_mut()
+            .ok_or_else(not_table)?
+            .entry(part)
+            .or_insert_with(|| toml::Value::Table(toml::value::Table::new()));
+    }
+    table
+        .as_table_mut()
+        .ok_or_else(not_table)?
+        .insert(last.to_string(), value);
+    Ok(())
+}
+
+/// Parse an override value as TOML (`2`, `0.5`, `true`, `"x"`, `[1, 2]`),
+/// falling back to a bare string such as `UTC` or `2020-01-01`.
+fn parse_override_value(text: &str) -> toml::Value {
+    toml::from_str::<toml::value::Table>(&format!("value = {}", text))
+        .ok()
+        .and_then(|mut t| t.remove("value"))
+        .filter(|v| !v.is_datetime())
+        .unwrap_or_else(|| toml::Value::String(text.to_string()))
+}
+
+/// Recursively merge `overlay` into `base`. Tables are merged key by key;
+/// any other value in `overlay` replaces the one in `base`.
+fn merge_toml(base: &mut toml::Value, overlay: &toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
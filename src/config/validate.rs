@@ -0,0 +1,529 @@
+use super::{
+    parse_duration, CombineMethod, Config, ConfirmMode, ContagionSignal, KernelFamily,
+    MemoryMethod, StateMachineConfig,
+};
+use crate::error::{Result, RuptureError};
+use crate::io::timestamp::TimestampParser;
+use crate::model::residuals::ChannelRegistry;
+use crate::model::state_graph::StateGraph;
+use crate::preprocess::gaps::GapDetector;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The config cannot be run.
+    Error,
+    /// The config runs, but probably not as intended.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// One finding, tied to the dotted path of the field it concerns.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Every error and warning found in a config, in check order.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|i| i.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == Severity::Warning)
+    }
+
+    /// True when there are no errors; warnings are allowed.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// `Err` listing every error, or `Ok` when the config is valid.
+    pub fn into_result(self) -> Result<()> {
+        if self.is_valid() {
+            Ok(())
+        } else {
+            Err(RuptureError::InvalidConfig(self))
+        }
+    }
+
+    fn error(&mut self, field: &str, message: impl Into<String>) {
+        self.push(Severity::Error, field, message.into());
+    }
+
+    fn warn(&mut self, field: &str, message: impl Into<String>) {
+        self.push(Severity::Warning, field, message.into());
+    }
+
+    fn push(&mut self, severity: Severity, field: &str, message: String) {
+        self.issues.push(ValidationIssue {
+            severity,
+            field: field.to_string(),
+            message,
+        });
+    }
+}
+
+/// Lists the errors, separated by "; ".
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<String> = self.errors().map(|e| e.to_string()).collect();
+        write!(f, "{}", errors.join("; "))
+    }
+}
+
+impl Config {
+    /// Validate parameter ranges and consistency, failing with every error
+    /// found. Warnings do not fail; see [`Config::validation_report`].
+    pub fn validate(&self) -> Result<()> {
+        self.validation_report().into_result()
+    }
+
+    /// Check the whole config, collecting all errors and warnings.
+    pub fn validation_report(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        self.check_numerics(&mut report);
+        self.check_windows(&mut report);
+        self.check_robust(&mut report);
+        self.check_memory(&mut report);
+        self.check_residuals(&mut report);
+        self.check_combine(&mut report);
+        self.check_capacity(&mut report);
+        check_state_machine(
+            &mut report,
+            "state_machine",
+            &self.state_machine,
+            self.io.parse_timestamps,
+        );
+        self.check_io(&mut report);
+        self.check_contagion(&mut report);
+        self.check_outputs(&mut report);
+        report
+    }
+
+    fn check_numerics(&self, report: &mut ValidationReport) {
+        if !(self.numerics.eps > 0.0 && self.numerics.eps.is_finite()) {
+            report.error("numerics.eps", "must be positive");
+        }
+        if self.numerics.residual_clip_max.is_nan() || self.numerics.residual_clip_max <= 0.0 {
+            report.error("numerics.residual_clip_max", "must be positive");
+        }
+    }
+
+    fn check_windows(&self, report: &mut ValidationReport) {
+        if self.windows.robust_scale_n < 2 {
+            report.error("windows.robust_scale_n", "must be at least 2");
+        }
+        if self.windows.memory_k < 10 {
+            report.error("windows.memory_k", "must be at least 10");
+        }
+        if self.windows.capacity_l < 50 {
+            report.error("windows.capacity_l", "must be at least 50");
+        }
+        if self.preprocess.min_rows < self.windows.capacity_l {
+            report.warn(
+                "preprocess.min_rows",
+                format!(
+                    "{} is below windows.capacity_l ({}); a run this short never fills \
+                     the capacity window",
+                    self.preprocess.min_rows, self.windows.capacity_l
+                ),
+            );
+        }
+    }
+
+    fn check_robust(&self, report: &mut ValidationReport) {
+        let factor = self.robust.mad_scale_factor;
+        if !(factor > 0.0 && factor.is_finite()) {
+            report.error("robust.mad_scale_factor", "must be positive");
+        }
+    }
+
+    fn check_memory(&self, report: &mut ValidationReport) {
+        let memory = &self.memory;
+        if memory.alpha <= 0.0 || memory.alpha >= 1.0 || memory.alpha.is_nan() {
+            report.error("memory.alpha", "must be in the open interval (0, 1)");
+        } else if memory.kernel.uses_alpha() && memory.alpha < 0.3 {
+            report.warn(
+                "memory.alpha",
+                format!(
+                    "{:.2} is quite low, producing a very long memory tail; the model may \
+                     be slow to adapt to regime changes",
+                    memory.alpha
+                ),
+            );
+        } else if memory.kernel.uses_alpha() && memory.alpha > 0.9 {
+            report.warn(
+                "memory.alpha",
+                format!(
+                    "{:.2} is close to 1, producing very short memory; the model may behave \
+                     more like a simple moving average",
+                    memory.alpha
+                ),
+            );
+        }
+        if memory.method == MemoryMethod::SumOfExponentials && memory.terms < 8 {
+            report.error("memory.terms", "must be at least 8");
+        }
+
+        let lambda = memory.lambda;
+        match memory.kernel {
+            KernelFamily::Exponential if lambda.is_nan() || lambda <= 0.0 => {
+                report.error(
+                    "memory.lambda",
+                    "must be positive for the exponential kernel",
+                );
+            }
+            KernelFamily::TemperedPowerLaw if lambda.is_nan() || lambda < 0.0 => {
+                report.error(
+                    "memory.lambda",
+                    "must be non-negative for the tempered_power_law kernel",
+                );
+            }
+            KernelFamily::PowerLaw | KernelFamily::Fractional | KernelFamily::File
+                if lambda != 0.0 =>
+            {
+                report.warn(
+                    "memory.lambda",
+                    "is only used by the exponential and tempered_power_law kernels",
+                );
+            }
+            _ => {}
+        }
+        if memory.kernel == KernelFamily::File {
+            if memory.weights.len() != self.windows.memory_k {
+                report.error(
+                    "memory.weights",
+                    format!(
+                        "has {} entries but windows.memory_k is {}",
+                        memory.weights.len(),
+                        self.windows.memory_k
+                    ),
+                );
+            }
+            if memory.weights.iter().any(|w| !w.is_finite() || *w < 0.0)
+                || memory.weights.iter().sum::<f64>() <= 0.0
+            {
+                report.error(
+                    "memory.weights",
+                    "must be finite, non-negative and not all zero",
+                );
+            }
+        }
+        if memory.method == MemoryMethod::SumOfExponentials
+            && !matches!(
+                memory.kernel,
+                KernelFamily::PowerLaw | KernelFamily::TemperedPowerLaw
+            )
+        {
+            report.error(
+                "memory.method",
+                "sum_of_exponentials requires the power_law or tempered_power_law kernel",
+            );
+        }
+    }
+
+    fn check_residuals(&self, report: &mut ValidationReport) {
+        if self.features.range_channel && !self.features.volatility_estimator.is_range_based() {
+            report.error(
+                "features.range_channel",
+                "requires a range-based volatility_estimator",
+            );
+        }
+        let registry = ChannelRegistry::builtin();
+        let mut names = HashSet::new();
+        for channel in &self.residuals.channels {
+            if !registry.contains(&channel.kind) {
+                report.warn(
+                    "residuals.channels",
+                    format!(
+                        "'{}' is not a built-in channel kind; the engine needs a registry \
+                         that provides it",
+                        channel.kind
+                    ),
+                );
+            }
+            if !names.insert(channel.name()) {
+                report.error(
+                    "residuals.channels",
+                    format!("duplicate channel name '{}'", channel.name()),
+                );
+            }
+            if !channel.theta.is_finite() {
+                report.error(
+                    "residuals.channels",
+                    format!("theta for '{}' must be finite", channel.name()),
+                );
+            }
+        }
+    }
+
+    fn check_combine(&self, report: &mut ValidationReport) {
+        if self.combine.tau.is_nan() || self.combine.tau <= 0.0 {
+            report.error("combine.tau", "must be positive");
+        }
+        if self.combine.method == CombineMethod::PNorm
+            && (self.combine.p.is_nan() || self.combine.p < 1.0)
+        {
+            report.error("combine.p", "must be at least 1");
+        }
+        let channels = self.residual_channels();
+        for (name, weight) in &self.combine.weights {
+            if !channels.iter().any(|c| c.name() == name) {
+                report.error(
+                    "combine.weights",
+                    format!("no residual channel named '{}'", name),
+                );
+            }
+            if !weight.is_finite() || *weight < 0.0 {
+                report.error(
+                    "combine.weights",
+                    format!("weight for '{}' must be finite and non-negative", name),
+                );
+            }
+        }
+    }
+
+    fn check_capacity(&self, report: &mut ValidationReport) {
+        if !(self.capacity.q > 0.0 && self.capacity.q < 1.0) {
+            report.error("capacity.q", "must be in the open interval (0, 1)");
+        }
+        if !(self.capacity.lambda > 0.0 && self.capacity.lambda <= 1.0) {
+            report.error("capacity.lambda", "must be in (0, 1]");
+        }
+        if !(self.capacity.e_min >= 0.0 && self.capacity.e_min.is_finite()) {
+            report.error("capacity.e_min", "must be zero or positive");
+        }
+    }
+
+    fn check_io(&self, report: &mut ValidationReport) {
+        if self.io.parse_timestamps {
+            if let Err(e) = TimestampParser::from_config(&self.io) {
+                report.error("io", message(e));
+            }
+        }
+        if self.gaps.enabled {
+            if !self.io.parse_timestamps {
+                report.error("gaps.enabled", "requires io.parse_timestamps = true");
+            } else if let Err(e) = GapDetector::from_config(&self.gaps, &self.io) {
+                report.error("gaps", message(e));
+            }
+        }
+    }
+
+    fn check_contagion(&self, report: &mut ValidationReport) {
+        let rho_quantile = self.contagion.rho_quantile;
+        if !(0.0..=1.0).contains(&rho_quantile) {
+            report.error("contagion.rho_quantile", "must be in [0, 1]");
+        }
+        // Assets are aligned in timestamp order, which for raw text is
+        // string order (e.g. "1/10/2008" before "1/2/2008").
+        if self.contagion.enabled && !self.io.parse_timestamps {
+            report.error(
+                "contagion.enabled",
+                "requires io.parse_timestamps to align assets in time order",
+            );
+        }
+        if let Some(state_machine) = &self.contagion.state_machine {
+            check_state_machine(
+                report,
+                "contagion.state_machine",
+                state_machine,
+                self.io.parse_timestamps,
+            );
+        } else if self.contagion.signal == ContagionSignal::Breadth {
+            report.error(
+                "contagion.state_machine",
+                "required with signal = \"breadth\": the breadth fraction is in [0, 1], \
+                 while [state_machine] thresholds are on the rho scale",
+            );
+        }
+    }
+
+    fn check_outputs(&self, report: &mut ValidationReport) {
+        let outputs = &self.outputs;
+        let names = [
+            ("outputs.csv_timeseries_name", &outputs.csv_timeseries_name),
+            ("outputs.json_events_name", &outputs.json_events_name),
+            ("outputs.json_episodes_name", &outputs.json_episodes_name),
+            (
+                "outputs.jsonl_transitions_name",
+                &outputs.jsonl_transitions_name,
+            ),
+            (
+                "outputs.parquet_timeseries_name",
+                &outputs.parquet_timeseries_name,
+            ),
+            (
+                "outputs.json_config_snapshot_name",
+                &outputs.json_config_snapshot_name,
+            ),
+            ("contagion.csv_name", &self.contagion.csv_name),
+            (
+                "contagion.json_events_name",
+                &self.contagion.json_events_name,
+            ),
+        ];
+        for (i, (field, name)) in names.iter().enumerate() {
+            if name.trim().is_empty() {
+                report.error(field, "must not be empty");
+            } else if let Some((other, _)) = names[..i].iter().find(|(_, n)| n == name) {
+                report.error(field, format!("'{}' is also used by {}", name, other));
+            }
+        }
+        if !outputs.write_csv_timeseries
+            && !outputs.write_json_events
+            && !outputs.write_json_episodes
+            && !outputs.write_jsonl_transitions
+            && !outputs.write_parquet_timeseries
+            && !outputs.write_json_config_snapshot
+        {
+            report.warn("outputs", "every output is disabled; a run writes nothing");
+        }
+        if outputs.write_parquet_timeseries && !cfg!(feature = "parquet") {
+            report.error(
+                "outputs.write_parquet_timeseries",
+                "Parquet output needs a build with --features parquet",
+            );
+        }
+    }
+}
+
+fn check_state_machine(
+    report: &mut ValidationReport,
+    prefix: &str,
+    sm: &StateMachineConfig,
+    parse_timestamps: bool,
+) {
+    let field = |name: &str| format!("{}.{}", prefix, name);
+    let thresholds = [sm.rho_stressed, sm.rho_critical, sm.rho_rupture];
+    if thresholds.iter().any(|t| !t.is_finite()) {
+        report.error(&field("rho_stressed"), "rho thresholds must be finite");
+    } else if !(sm.rho_stressed < sm.rho_critical && sm.rho_critical < sm.rho_rupture) {
+        report.error(
+            &field("rho_critical"),
+            format!(
+                "thresholds must satisfy rho_stressed < rho_critical < rho_rupture, got \
+                 {} < {} < {}",
+                sm.rho_stressed, sm.rho_critical, sm.rho_rupture
+            ),
+        );
+    }
+    for (name, exit, entry) in [
+        ("rho_stressed_exit", sm.rho_stressed_exit, sm.rho_stressed),
+        ("rho_critical_exit", sm.rho_critical_exit, sm.rho_critical),
+    ] {
+        if exit.is_some_and(|exit| !exit.is_finite() || exit > entry) {
+            report.error(
+                &field(name),
+                format!("must be finite and at most the entry threshold {}", entry),
+            );
+        }
+    }
+    match sm.confirm_mode {
+        ConfirmMode::Bars => {
+            if sm.confirm_k < 1 {
+                report.error(&field("confirm_k"), "must be at least 1");
+            }
+            if sm.confirm_m < 1 || sm.confirm_m > sm.confirm_k {
+                report.error(&field("confirm_m"), "must satisfy 1 <= m <= k");
+            }
+        }
+        ConfirmMode::Time => {
+            if !parse_timestamps {
+                report.error(
+                    &field("confirm_mode"),
+                    "time confirmation requires io.parse_timestamps = true",
+                );
+            }
+            let mut parse = |name: &str, value: &Option<String>| match value {
+                None => {
+                    report.error(&field(name), "is required for time confirmation");
+                    None
+                }
+                Some(text) => parse_duration(text)
+                    .map_err(|e| report.error(&field(name), message(e)))
+                    .ok(),
+            };
+            let window = parse("confirm_window", &sm.confirm_window);
+            let duration = parse("confirm_duration", &sm.confirm_duration);
+            if let (Some(window), Some(duration)) = (window, duration) {
+                if duration > window {
+                    report.error(
+                        &field("confirm_duration"),
+                        "must not be longer than confirm_window",
+                    );
+                }
+            }
+        }
+    }
+    if sm.recovery_hold < 1 {
+        report.error(&field("recovery_hold"), "must be at least 1");
+    }
+    if sm.recovery_exit_bars < 1 {
+        report.error(&field("recovery_exit_bars"), "must be at least 1");
+    }
+    if sm
+        .recovery_exit_rho
+        .is_some_and(|exit| !exit.is_finite() || exit > sm.rho_rupture)
+    {
+        report.error(
+            &field("recovery_exit_rho"),
+            "must be finite and at most rho_rupture",
+        );
+    }
+    // The built-in definition follows from the keys checked above.
+    if !sm.states.is_empty() || !sm.transitions.is_empty() {
+        for problem in StateGraph::problems(sm) {
+            report.error(&field("states"), problem);
+        }
+        for (name, set) in [
+            ("rho_stressed_exit", sm.rho_stressed_exit.is_some()),
+            ("rho_critical_exit", sm.rho_critical_exit.is_some()),
+            ("recovery_exit_rho", sm.recovery_exit_rho.is_some()),
+        ] {
+            if set {
+                report.warn(
+                    &field(name),
+                    "is not used by declared states; set exit_rho or a below transition",
+                );
+            }
+        }
+    }
+}
+
+/// The message of a config error, without the "Config error" prefix.
+fn message(error: RuptureError) -> String {
+    match error {
+        RuptureError::Config(message) => message,
+        RuptureError::InvalidConfig(report) => report.to_string(),
+        other => other.to_string(),
+    }
+}
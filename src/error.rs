@@ -1,3 +1,4 @@
+use crate::config::ValidationReport;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -11,15 +12,30 @@ pub enum RuptureError {
     #[error("JSON serialisation error: {0}")]
     JsonSerialise(#[from] serde_json::Error),
 
+    #[cfg(feature = "parquet")]
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    #[cfg(feature = "parquet")]
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] arrow::error::ArrowError),
+
     #[error("Config error: {0}")]
     Config(String),
 
+    /// A config that parsed but failed validation, with every error found.
+    #[error("Config error: {0}")]
+    InvalidConfig(ValidationReport),
+
     #[error("Validation error: {0}")]
     Validation(String),
 
     #[error("Empty data: {0}")]
     EmptyData(String),
 
+    #[error("Checkpoint error: {0}")]
+    Checkpoint(String),
+
     #[error("Parse error at line {line}: {message}")]
     Parse { line: usize, message: String },
 }
@@ -0,0 +1,400 @@
+use crate::config::{parse_duration, IoConfig};
+use crate::error::{Result, RuptureError};
+use crate::io::TimestampParser;
+use crate::stats::quantile;
+use crate::types::{RuptureEvent, Timestamp};
+use chrono::Duration;
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Trading days per year, used to express false alarms per year when bar
+/// timestamps are not parsed.
+pub const DEFAULT_BARS_PER_YEAR: f64 = 252.0;
+
+/// A labelled regime change: the interval over which a rupture is expected.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LabelInterval {
+    pub name: String,
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+/// Read labelled intervals from a CSV file with columns `start`, `end` and
+/// an optional `name`. Timestamps are read like bar timestamps: parsed with
+/// `io.timestamp_format` when `io.parse_timestamps` is set, raw otherwise.
+pub fn read_labels(path: &Path, io_cfg: &IoConfig) -> Result<Vec<LabelInterval>> {
+    let parser = if io_cfg.parse_timestamps {
+        Some(TimestampParser::from_config(io_cfg)?)
+    } else {
+        None
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_path(path)?;
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let (start_idx, end_idx) = match (column("start"), column("end")) {
+        (Some(s), Some(e)) => (s, e),
+        _ => {
+            return Err(RuptureError::Config(format!(
+                "labels file {} needs 'start' and 'end' columns",
+                path.display()
+            )))
+        }
+    };
+    let name_idx = column("name");
+
+    let mut labels = Vec::new();
+    for (line_num, record) in reader.records().enumerate() {
+        let record = record?;
+        let line = line_num + 2;
+        let timestamp = |idx: usize| -> Result<Timestamp> {
+            let text = record.get(idx).unwrap_or("");
+            match &parser {
+                Some(parser) => parser.parse(text, line),
+                None => Ok(Timestamp::from(text)),
+            }
+        };
+        let label = LabelInterval {
+            name: name_idx
+                .and_then(|i| record.get(i))
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("label_{}", labels.len() + 1)),
+            start: timestamp(start_idx)?,
+            end: timestamp(end_idx)?,
+        };
+        if label.end < label.start {
+            return Err(RuptureError::Parse {
+                line,
+                message: format!("label '{}' ends before it starts", label.name),
+            });
+        }
+        labels.push(label);
+    }
+    Ok(labels)
+}
+
+/// How far outside a labelled interval a detection still counts as a hit:
+/// a number of bars (`"5"`), or a duration (`"10d"`, see `parse_duration`)
+/// when timestamps are parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tolerance {
+    Bars(usize),
+    Duration(Duration),
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance::Bars(0)
+    }
+}
+
+impl FromStr for Tolerance {
+    type Err = RuptureError;
+
+    fn from_str(text: &str) -> Result<Self> {
+        match text.trim().parse::<usize>() {
+            Ok(bars) => Ok(Tolerance::Bars(bars)),
+            Err(_) => parse_duration(text).map(Tolerance::Duration),
+        }
+    }
+}
+
+impl fmt::Display for Tolerance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tolerance::Bars(bars) => write!(f, "{} bars", bars),
+            Tolerance::Duration(d) => write!(f, "{}s", d.num_seconds()),
+        }
+    }
+}
+
+/// Labels are matched to bars by comparing timestamps, so the bar timestamps
+/// must be sorted. Raw text in a non-ISO format such as `1/2/2008` may not
+/// be; parsed timestamps always compare as instants.
+pub fn check_time_order(timestamps: &[Timestamp]) -> Result<()> {
+    match timestamps.windows(2).position(|w| w[1] < w[0]) {
+        Some(i) => Err(RuptureError::Config(format!(
+            "bar timestamps are not in time order ('{}' after '{}'); \
+             set io.parse_timestamps = true to compare them as instants",
+            timestamps[i + 1],
+            timestamps[i]
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Bar index range covered by a label: the first bar at or after `start`
+/// to the last bar at or before `end`. `None` when no bar falls inside.
+fn label_span(label: &LabelInterval, timestamps: &[Timestamp]) -> Option<(usize, usize)> {
+    let first = timestamps.partition_point(|ts| *ts < label.start);
+    let after = timestamps.partition_point(|ts| *ts <= label.end);
+    (first < after).then(|| (first, after - 1))
+}
+
+/// Bar index range within which a detection matches a label: its span
+/// widened by the tolerance.
+fn label_window(
+    label: &LabelInterval,
+    (first, last): (usize, usize),
+    timestamps: &[Timestamp],
+    tolerance: Tolerance,
+) -> Result<(usize, usize)> {
+    match tolerance {
+        Tolerance::Bars(bars) => Ok((first.saturating_sub(bars), last + bars)),
+        Tolerance::Duration(d) => match (label.start.as_datetime(), label.end.as_datetime()) {
+            (Some(start), Some(end)) => {
+                let lo = Timestamp::from(start - d);
+                let hi = Timestamp::from(end + d);
+                Ok((
+                    timestamps.partition_point(|ts| *ts < lo),
+                    timestamps.partition_point(|ts| *ts <= hi) - 1,
+                ))
+            }
+            _ => Err(RuptureError::Config(
+                "a duration tolerance needs io.parse_timestamps = true".into(),
+            )),
+        },
+    }
+}
+
+/// Detector score against labelled intervals.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Evaluation {
+    /// Labels with at least one bar in the data.
+    pub labels: usize,
+    /// Confirmed ruptures.
+    pub detections: usize,
+    /// Labels matched by at least one detection.
+    pub labels_hit: usize,
+    /// Detections falling within some label's window.
+    pub detections_matched: usize,
+}
+
+impl Evaluation {
+    /// Fraction of detections that match a label; NaN without detections.
+    pub fn precision(&self) -> f64 {
+        self.detections_matched as f64 / self.detections as f64
+    }
+
+    /// Fraction of labels that were detected (the hit rate); NaN without
+    /// labels.
+    pub fn recall(&self) -> f64 {
+        self.labels_hit as f64 / self.labels as f64
+    }
+
+    pub fn f1(&self) -> f64 {
+        let (p, r) = (self.precision(), self.recall());
+        if p + r > 0.0 {
+            2.0 * p * r / (p + r)
+        } else {
+            0.0
+        }
+    }
+
+    /// Detections that match no label.
+    pub fn false_alarms(&self) -> usize {
+        self.detections - self.detections_matched
+    }
+}
+
+/// How one label was scored.
+#[derive(Debug, Clone, Serialize)]
+pub struct LabelOutcome {
+    #[serde(flatten)]
+    pub label: LabelInterval,
+    /// The earliest matching detection; `None` for a missed label.
+    pub detection: Option<Detection>,
+}
+
+/// A confirmed rupture, with its timing relative to the matched label.
+/// Leads are positive when the rupture came before the label's start.
+#[derive(Debug, Clone, Serialize)]
+pub struct Detection {
+    pub candidate_index: usize,
+    pub candidate_timestamp: Timestamp,
+    pub confirmed_index: usize,
+    pub peak_rho: f64,
+    /// Bars from the candidate bar to the label's first bar.
+    pub lead_bars: i64,
+    /// Bars from the confirmation bar to the label's first bar.
+    pub confirmation_lead_bars: i64,
+    /// Days from the candidate bar to the label's start, when timestamps
+    /// are parsed.
+    pub lead_days: Option<f64>,
+}
+
+impl Detection {
+    fn new(event: &RuptureEvent, label: &LabelInterval, first_bar: usize) -> Self {
+        let confirmed_index = event.confirmed_index.unwrap_or(event.candidate_index);
+        let lead_days = match (
+            label.start.as_datetime(),
+            event.candidate_timestamp.as_datetime(),
+        ) {
+            (Some(start), Some(candidate)) => {
+                Some((start - candidate).num_seconds() as f64 / 86_400.0)
+            }
+            _ => None,
+        };
+        Self {
+            candidate_index: event.candidate_index,
+            candidate_timestamp: event.candidate_timestamp.clone(),
+            confirmed_index,
+            peak_rho: event.peak_rho,
+            lead_bars: first_bar as i64 - event.candidate_index as i64,
+            confirmation_lead_bars: first_bar as i64 - confirmed_index as i64,
+            lead_days,
+        }
+    }
+}
+
+/// Summary of a sample of leads or lags.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Distribution {
+    pub count: usize,
+    pub min: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub mean: f64,
+    pub p75: f64,
+    pub max: f64,
+}
+
+impl Distribution {
+    /// `None` for an empty sample.
+    pub fn of(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        Some(Self {
+            count: values.len(),
+            min: quantile(values, 0.0),
+            p25: quantile(values, 0.25),
+            median: quantile(values, 0.5),
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            p75: quantile(values, 0.75),
+            max: quantile(values, 1.0),
+        })
+    }
+}
+
+/// Full evaluation of a run against labels.
+#[derive(Debug, Clone, Serialize)]
+pub struct EvaluationReport {
+    pub tolerance: String,
+    pub counts: Evaluation,
+    pub hit_rate: f64,
+    pub precision: f64,
+    /// Length of the data in years: from timestamps when parsed, otherwise
+    /// bars divided by bars per year.
+    pub years: f64,
+    /// `None` when the data spans no time.
+    pub false_alarms_per_year: Option<f64>,
+    pub lead_bars: Option<Distribution>,
+    pub confirmation_lead_bars: Option<Distribution>,
+    pub lead_days: Option<Distribution>,
+    /// Labels with at least one bar in the data, in file order.
+    pub labels: Vec<LabelOutcome>,
+    pub missed: Vec<String>,
+    /// Confirmed ruptures that match no label.
+    pub false_alarms: Vec<RuptureEvent>,
+}
+
+/// Score confirmed ruptures against labels. A rupture matches a label when
+/// its candidate bar lies in the label's bar span widened by the tolerance.
+/// `timestamps` are the bar timestamps the events index into, in order (see
+/// [`check_time_order`]).
+pub fn evaluate(
+    events: &[RuptureEvent],
+    timestamps: &[Timestamp],
+    labels: &[LabelInterval],
+    tolerance: Tolerance,
+) -> Result<Evaluation> {
+    Ok(evaluation_report(events, timestamps, labels, tolerance, DEFAULT_BARS_PER_YEAR)?.counts)
+}
+
+/// Score confirmed ruptures against labels as `evaluate` does, and report
+/// per-label timing, missed labels and false alarms.
+pub fn evaluation_report(
+    events: &[RuptureEvent],
+    timestamps: &[Timestamp],
+    labels: &[LabelInterval],
+    tolerance: Tolerance,
+    bars_per_year: f64,
+) -> Result<EvaluationReport> {
+    check_time_order(timestamps)?;
+    let detections: Vec<&RuptureEvent> = events
+        .iter()
+        .filter(|e| e.confirmed_index.is_some())
+        .collect();
+    let mut matched = vec![false; detections.len()];
+    let mut outcomes = Vec::new();
+
+    for label in labels {
+        let Some(span) = label_span(label, timestamps) else {
+            continue;
+        };
+        let (lo, hi) = label_window(label, span, timestamps, tolerance)?;
+        let mut detection = None;
+        for (event, matched) in detections.iter().zip(matched.iter_mut()) {
+            if (lo..=hi).contains(&event.candidate_index) {
+                *matched = true;
+                detection.get_or_insert_with(|| Detection::new(event, label, span.0));
+            }
+        }
+        outcomes.push(LabelOutcome {
+            label: label.clone(),
+            detection,
+        });
+    }
+
+    let counts = Evaluation {
+        labels: outcomes.len(),
+        detections: detections.len(),
+        labels_hit: outcomes.iter().filter(|o| o.detection.is_some()).count(),
+        detections_matched: matched.iter().filter(|&&m| m).count(),
+    };
+    let years = match (
+        timestamps.first().and_then(Timestamp::as_datetime),
+        timestamps.last().and_then(Timestamp::as_datetime),
+    ) {
+        (Some(first), Some(last)) => (last - first).num_seconds() as f64 / (365.25 * 86_400.0),
+        _ => timestamps.len() as f64 / bars_per_year,
+    };
+    let hits: Vec<&Detection> = outcomes
+        .iter()
+        .filter_map(|o| o.detection.as_ref())
+        .collect();
+    let sample = |f: fn(&Detection) -> Option<f64>| {
+        Distribution::of(&hits.iter().filter_map(|d| f(d)).collect::<Vec<_>>())
+    };
+
+    Ok(EvaluationReport {
+        tolerance: tolerance.to_string(),
+        hit_rate: counts.recall(),
+        precision: counts.precision(),
+        years,
+        false_alarms_per_year: (years > 0.0).then(|| counts.false_alarms() as f64 / years),
+        lead_bars: sample(|d| Some(d.lead_bars as f64)),
+        confirmation_lead_bars: sample(|d| Some(d.confirmation_lead_bars as f64)),
+        lead_days: sample(|d| d.lead_days),
+        missed: outcomes
+            .iter()
+            .filter(|o| o.detection.is_none())
+            .map(|o| o.label.name.clone())
+            .collect(),
+        false_alarms: detections
+            .iter()
+            .zip(&matched)
+            .filter(|(_, &m)| !m)
+            .map(|(e, _)| (*e).clone())
+            .collect(),
+        labels: outcomes,
+        counts,
+    })
+}
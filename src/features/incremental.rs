@@ -0,0 +1,83 @@
+use crate::config::VolatilityEstimator;
+use crate::features::normalise::Normaliser;
+use crate::features::range::{range_variance, yang_zhang_k};
+use crate::types::{Bar, Normed};
+use serde::{Deserialize, Serialize};
+
+/// Incremental feature extractor. Produces the same returns, acceleration,
+/// range volatility and normalised features as `compute_returns`,
+/// `compute_acceleration`, `compute_range_vol` and `compute_normed`, but
+/// consumes one bar at a time.
+///
+/// With a range-based estimator and `replace_u`, the normalised range
+/// volatility `g` is also reported as `u`, so it drives `r_vol` and `r_liq`
+/// in place of the close-to-close return.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureState {
+    use_log_returns: bool,
+    prev_close: Option<f64>,
+    prev_return: Option<f64>,
+    estimator: VolatilityEstimator,
+    replace_u: bool,
+    yz_k: f64,
+    normaliser: Normaliser,
+}
+
+impl FeatureState {
+    pub fn new(window_n: usize, use_log_returns: bool, mad_scale_factor: f64, eps: f64) -> Self {
+        Self {
+            use_log_returns,
+            prev_close: None,
+            prev_return: None,
+            estimator: VolatilityEstimator::CloseToClose,
+            replace_u: false,
+            yz_k: yang_zhang_k(window_n),
+            normaliser: Normaliser::new(window_n, mad_scale_factor, eps),
+        }
+    }
+
+    /// Select the range volatility estimator and whether its normalised
+    /// value replaces `u`.
+    pub fn with_volatility_estimator(
+        mut self,
+        estimator: VolatilityEstimator,
+        replace_u: bool,
+    ) -> Self {
+        self.estimator = estimator;
+        self.replace_u = replace_u && estimator.is_range_based();
+        self
+    }
+
+    /// Push the next bar and return its normalised features.
+    pub fn update(&mut self, bar: &Bar) -> Normed {
+        let ret = match self.prev_close {
+            Some(prev) => {
+                let r = if self.use_log_returns {
+                    (bar.close / prev).ln()
+                } else {
+                    (bar.close - prev) / prev
+                };
+                if r.is_finite() {
+                    r
+                } else {
+                    0.0
+                }
+            }
+            None => 0.0,
+        };
+        let accel = match self.prev_return {
+            Some(prev) => ret - prev,
+            None => 0.0,
+        };
+        let range_vol = range_variance(self.estimator, bar, self.prev_close, self.yz_k).sqrt();
+
+        self.prev_close = Some(bar.close);
+        self.prev_return = Some(ret);
+
+        let mut normed = self.normaliser.update(ret, accel, bar.volume, range_vol);
+        if self.replace_u {
+            normed.u = normed.g;
+        }
+        normed
+    }
+}
@@ -1,7 +1,11 @@
 pub mod accel;
+pub mod incremental;
 pub mod normalise;
+pub mod range;
 pub mod returns;
 
 pub use accel::compute_acceleration;
-pub use normalise::compute_normed;
+pub use incremental::FeatureState;
+pub use normalise::{compute_normed, Normaliser};
+pub use range::{compute_range_vol, range_variance};
 pub use returns::compute_returns;
@@ -1,46 +1,69 @@
-use crate::stats::{mad, median, safe_div, RollingWindow};
+use crate::stats::{safe_div, OrderedWindow};
 use crate::types::{Bar, Normed};
+use serde::{Deserialize, Serialize};
+
+/// Rolling robust normaliser for returns, acceleration, volume and range
+/// volatility. Holds the windows used by `compute_normed` so that features
+/// can also be produced one bar at a time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Normaliser {
+    ret_win: OrderedWindow,
+    acc_win: OrderedWindow,
+    vol_win: OrderedWindow,
+    rng_win: OrderedWindow,
+    mad_scale_factor: f64,
+    eps: f64,
+}
+
+impl Normaliser {
+    pub fn new(window_n: usize, mad_scale_factor: f64, eps: f64) -> Self {
+        Self {
+            ret_win: OrderedWindow::new(window_n),
+            acc_win: OrderedWindow::new(window_n),
+            vol_win: OrderedWindow::new(window_n),
+            rng_win: OrderedWindow::new(window_n),
+            mad_scale_factor,
+            eps,
+        }
+    }
+
+    /// Push one bar's return, acceleration, volume and range volatility and
+    /// return the normalised features for that bar.
+    pub fn update(&mut self, ret: f64, accel: f64, volume: f64, range_vol: f64) -> Normed {
+        self.ret_win.push(ret);
+        self.acc_win.push(accel);
+        self.vol_win.push(volume);
+        self.rng_win.push(range_vol);
+
+        let sigma_ret = self.mad_scale_factor * self.ret_win.mad() + self.eps;
+        let sigma_acc = self.mad_scale_factor * self.acc_win.mad() + self.eps;
+        let vol_med = self.vol_win.median() + self.eps;
+        let rng_med = self.rng_win.median() + self.eps;
+
+        let u = safe_div(ret.abs(), sigma_ret, 0.0);
+        let v = safe_div(volume, vol_med, 0.0);
+        let a = safe_div(accel.abs(), sigma_acc, 0.0);
+        let g = safe_div(range_vol, rng_med, 0.0);
+
+        Normed { u, v, a, g }
+    }
+}
 
 /// Compute robustly normalised features for each bar using rolling MAD for returns
-/// and acceleration, and rolling median for volume. The first `window_n` bars will
+/// and acceleration, and rolling median for volume and range volatility. The first `window_n` bars will
 /// use partial windows (computing over whatever data is available so far).
 pub fn compute_normed(
     bars: &[Bar],
     returns: &[f64],
     accel: &[f64],
+    range_vol: &[f64],
     window_n: usize,
     mad_scale_factor: f64,
     eps: f64,
 ) -> Vec<Normed> {
-    let n = bars.len();
-    let mut normed = Vec::with_capacity(n);
-
-    let mut ret_win = RollingWindow::new(window_n);
-    let mut acc_win = RollingWindow::new(window_n);
-    let mut vol_win = RollingWindow::new(window_n);
-
-    for i in 0..n {
-        ret_win.push(returns[i]);
-        acc_win.push(accel[i]);
-        vol_win.push(bars[i].volume);
-
-        let ret_snapshot = ret_win.as_slice();
-        let acc_snapshot = acc_win.as_slice();
-        let vol_snapshot = vol_win.as_slice();
-
-        let sigma_ret = mad_scale_factor * mad(&ret_snapshot) + eps;
-        let sigma_acc = mad_scale_factor * mad(&acc_snapshot) + eps;
-        let vol_med = {
-            let mut v = vol_snapshot;
-            median(&mut v) + eps
-        };
-
-        let u = safe_div(returns[i].abs(), sigma_ret, 0.0);
-        let v = safe_div(bars[i].volume, vol_med, 0.0);
-        let a = safe_div(accel[i].abs(), sigma_acc, 0.0);
-
-        normed.push(Normed { u, v, a });
-    }
-
-    normed
+    let mut normaliser = Normaliser::new(window_n, mad_scale_factor, eps);
+    bars.iter()
+        .enumerate()
+        .map(|(i, bar)| normaliser.update(returns[i], accel[i], bar.volume, range_vol[i]))
+        .collect()
 }
@@ -0,0 +1,116 @@
+use crate::config::VolatilityEstimator;
+use crate::types::Bar;
+
+/// Yang–Zhang weight on the open-to-close variance for a window of `n` bars:
+///   k = 0.34 / (1.34 + (n + 1) / (n - 1))
+pub fn yang_zhang_k(n: usize) -> f64 {
+    let n = n.max(2) as f64;
+    0.34 / (1.34 + (n + 1.0) / (n - 1.0))
+}
+
+/// Single-bar variance estimate from OHLC prices.
+///   Parkinson:       ln(H/L)^2 / (4 ln 2)
+///   Garman–Klass:    0.5 ln(H/L)^2 - (2 ln 2 - 1) ln(C/O)^2
+///   Rogers–Satchell: ln(H/C) ln(H/O) + ln(L/C) ln(L/O)
+///   Yang–Zhang:      ln(O/C_prev)^2 + k ln(C/O)^2 + (1 - k) RS
+/// Yang–Zhang uses squared log returns around zero rather than sample
+/// variances so that it can be evaluated bar by bar. Invalid prices give 0.
+pub fn range_variance(
+    estimator: VolatilityEstimator,
+    bar: &Bar,
+    prev_close: Option<f64>,
+    yz_k: f64,
+) -> f64 {
+    let hl = (bar.high / bar.low).ln();
+    let co = (bar.close / bar.open).ln();
+    let rs = (bar.high / bar.close).ln() * (bar.high / bar.open).ln()
+        + (bar.low / bar.close).ln() * (bar.low / bar.open).ln();
+
+    let var = match estimator {
+        VolatilityEstimator::CloseToClose => 0.0,
+        VolatilityEstimator::Parkinson => hl * hl / (4.0 * std::f64::consts::LN_2),
+        VolatilityEstimator::GarmanKlass => {
+            0.5 * hl * hl - (2.0 * std::f64::consts::LN_2 - 1.0) * co * co
+        }
+        VolatilityEstimator::RogersSatchell => rs,
+        VolatilityEstimator::YangZhang => {
+            let overnight = prev_close.map_or(0.0, |prev| (bar.open / prev).ln());
+            overnight * overnight + yz_k * co * co + (1.0 - yz_k) * rs
+        }
+    };
+    if var.is_finite() {
+        var.max(0.0)
+    } else {
+        0.0
+    }
+}
+
+/// Compute the per-bar range volatility (square root of `range_variance`)
+/// for a sequence of bars. All zeros for `CloseToClose`.
+pub fn compute_range_vol(
+    bars: &[Bar],
+    estimator: VolatilityEstimator,
+    window_n: usize,
+) -> Vec<f64> {
+    let yz_k = yang_zhang_k(window_n);
+    bars.iter()
+        .enumerate()
+        .map(|(i, bar)| {
+            let prev_close = i.checked_sub(1).map(|j| bars[j].close);
+            range_variance(estimator, bar, prev_close, yz_k).sqrt()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(open: f64, high: f64, low: f64, close: f64) -> Bar {
+        Bar {
+            ts: "t".into(),
+            open,
+            high,
+            low,
+            close,
+            volume: 1.0,
+            filled: false,
+        }
+    }
+
+    #[test]
+    fn test_flat_bar_has_zero_range() {
+        let b = bar(100.0, 100.0, 100.0, 100.0);
+        for est in [
+            VolatilityEstimator::Parkinson,
+            VolatilityEstimator::GarmanKlass,
+            VolatilityEstimator::RogersSatchell,
+            VolatilityEstimator::YangZhang,
+        ] {
+            assert_eq!(range_variance(est, &b, Some(100.0), 0.3), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_parkinson_known_value() {
+        let b = bar(100.0, 110.0, 100.0, 105.0);
+        let expected = (1.1_f64).ln().powi(2) / (4.0 * 2.0_f64.ln());
+        let got = range_variance(VolatilityEstimator::Parkinson, &b, None, 0.0);
+        assert!((got - expected).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_rogers_satchell_is_drift_free() {
+        // Open at the low, close at the high: RS is zero whatever the range.
+        let b = bar(100.0, 110.0, 100.0, 110.0);
+        let got = range_variance(VolatilityEstimator::RogersSatchell, &b, None, 0.0);
+        assert!(got.abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_yang_zhang_includes_overnight_gap() {
+        let b = bar(110.0, 110.0, 110.0, 110.0);
+        let got = range_variance(VolatilityEstimator::YangZhang, &b, Some(100.0), 0.3);
+        assert!((got - (1.1_f64).ln().powi(2)).abs() < 1e-15);
+    }
+}
@@ -0,0 +1,19 @@
+use crate::error::{Result, RuptureError};
+use crate::model::Checkpoint;
+use std::fs;
+use std::path::Path;
+
+/// Write an engine checkpoint to a JSON file.
+pub fn write_checkpoint(checkpoint: &Checkpoint, path: &Path) -> Result<()> {
+    let json = serde_json::to_string(checkpoint)?;
+    fs::write(path, json)?;
+    Ok(())
+}
+
+/// Read an engine checkpoint previously written by `write_checkpoint`.
+pub fn read_checkpoint(path: &Path) -> Result<Checkpoint> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content).map_err(|e| {
+        RuptureError::Checkpoint(format!("cannot read checkpoint {}: {}", path.display(), e))
+    })
+}
@@ -1,7 +1,74 @@
+pub mod checkpoint;
 pub mod read_csv;
+#[cfg(feature = "parquet")]
+pub mod read_parquet;
+pub mod timestamp;
 pub mod write_csv;
 pub mod write_json;
+#[cfg(feature = "parquet")]
+pub mod write_parquet;
 
+pub use checkpoint::{read_checkpoint, write_checkpoint};
 pub use read_csv::read_bars_csv;
-pub use write_csv::write_timeseries_csv;
-pub use write_json::{write_config_snapshot, write_events_json};
+#[cfg(feature = "parquet")]
+pub use read_parquet::{read_bars_arrow_ipc, read_bars_parquet};
+pub use timestamp::TimestampParser;
+pub use write_csv::{write_contagion_csv, write_timeseries_csv};
+pub use write_json::{
+    write_config_snapshot, write_episodes_json, write_evaluation_json, write_events_json,
+    write_transitions_jsonl,
+};
+#[cfg(feature = "parquet")]
+pub use write_parquet::write_timeseries_parquet;
+
+use crate::config::IoConfig;
+use crate::error::Result;
+use crate::types::Bar;
+use std::path::Path;
+
+/// Input file format, chosen by file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    Csv,
+    Parquet,
+    ArrowIpc,
+}
+
+impl InputFormat {
+    /// `.parquet`/`.pq` is Parquet, `.arrow`/`.ipc`/`.feather` is Arrow IPC;
+    /// anything else is read as CSV.
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "parquet" | "pq" => InputFormat::Parquet,
+            "arrow" | "ipc" | "feather" => InputFormat::ArrowIpc,
+            _ => InputFormat::Csv,
+        }
+    }
+
+    /// Whether this build can read the format.
+    pub fn is_supported(self) -> bool {
+        self == InputFormat::Csv || cfg!(feature = "parquet")
+    }
+}
+
+/// Read bars from `path` in the format given by its extension.
+pub fn read_bars(path: &Path, io_cfg: &IoConfig) -> Result<Vec<Bar>> {
+    match InputFormat::from_path(path) {
+        InputFormat::Csv => read_bars_csv(path, io_cfg),
+        #[cfg(feature = "parquet")]
+        InputFormat::Parquet => read_bars_parquet(path, io_cfg),
+        #[cfg(feature = "parquet")]
+        InputFormat::ArrowIpc => read_bars_arrow_ipc(path, io_cfg),
+        #[cfg(not(feature = "parquet"))]
+        InputFormat::Parquet | InputFormat::ArrowIpc => {
+            Err(crate::error::RuptureError::Config(format!(
+                "cannot read '{}': Parquet and Arrow input need a build with --features parquet",
+                path.display()
+            )))
+        }
+    }
+}
@@ -1,12 +1,20 @@
 use crate::config::IoConfig;
 use crate::error::{Result, RuptureError};
+use crate::io::timestamp::TimestampParser;
 use crate::types::Bar;
 use std::path::Path;
 
 /// Read OHLCV bars from a CSV file. Column mapping is taken from the IO config.
 /// Rows with missing or unparseable numeric fields are either dropped or cause
-/// an error, depending on the drop_invalid_rows setting.
+/// an error, depending on the drop_invalid_rows setting. When parse_timestamps
+/// is set, every timestamp must parse; a failure is always an error.
 pub fn read_bars_csv(path: &Path, io_cfg: &IoConfig) -> Result<Vec<Bar>> {
+    let parser = if io_cfg.parse_timestamps {
+        Some(TimestampParser::from_config(io_cfg)?)
+    } else {
+        None
+    };
+
     let mut reader = csv::ReaderBuilder::new()
         .has_headers(true)
         .flexible(true)
@@ -28,7 +36,11 @@ pub fn read_bars_csv(path: &Path, io_cfg: &IoConfig) -> Result<Vec<Bar>> {
         let record = record.map_err(RuptureError::CsvParse)?;
         let line = line_num + 2; // 1-indexed, plus header row
 
-        let ts = record.get(ts_idx).unwrap_or("").to_string();
+        let ts_text = record.get(ts_idx).unwrap_or("");
+        let ts = match &parser {
+            Some(p) => p.parse(ts_text, line)?,
+            None => ts_text.into(),
+        };
 
         let open = parse_f64(record.get(open_idx), line, "open");
         let high = parse_f64(record.get(high_idx), line, "high");
@@ -45,6 +57,7 @@ pub fn read_bars_csv(path: &Path, io_cfg: &IoConfig) -> Result<Vec<Bar>> {
                     low: l,
                     close: c,
                     volume: v,
+                    filled: false,
                 });
             }
             _ if io_cfg.drop_invalid_rows => continue,
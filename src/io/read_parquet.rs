@@ -0,0 +1,158 @@
+use crate::config::IoConfig;
+use crate::error::{Result, RuptureError};
+use crate::io::timestamp::TimestampParser;
+use crate::types::{Bar, Timestamp};
+use arrow::array::{Array, AsArray, Float64Array, RecordBatch};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Float64Type, TimeUnit, TimestampNanosecondType};
+use arrow::error::ArrowError;
+use arrow::ipc::reader::FileReader;
+use chrono::DateTime;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use std::fs::File;
+use std::path::Path;
+
+/// Read OHLCV bars from a Parquet file. Column mapping, timestamp parsing and
+/// invalid-row handling follow the IO config as for CSV. Price and volume
+/// columns may be any numeric type; the timestamp column may be a string,
+/// integer, date or timestamp column.
+pub fn read_bars_parquet(path: &Path, io_cfg: &IoConfig) -> Result<Vec<Bar>> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?.build()?;
+    read_batches(reader, io_cfg)
+}
+
+/// Read OHLCV bars from an Arrow IPC file, like [`read_bars_parquet`].
+pub fn read_bars_arrow_ipc(path: &Path, io_cfg: &IoConfig) -> Result<Vec<Bar>> {
+    let reader = FileReader::try_new(File::open(path)?, None)?;
+    read_batches(reader, io_cfg)
+}
+
+fn read_batches(
+    batches: impl Iterator<Item = std::result::Result<RecordBatch, ArrowError>>,
+    io_cfg: &IoConfig,
+) -> Result<Vec<Bar>> {
+    let parser = if io_cfg.parse_timestamps {
+        Some(TimestampParser::from_config(io_cfg)?)
+    } else {
+        None
+    };
+
+    let mut bars = Vec::new();
+    let mut rows_read = 0;
+    for batch in batches {
+        let batch = batch?;
+        let ts = timestamps(
+            column(&batch, &io_cfg.timestamp_column)?,
+            parser.as_ref(),
+            rows_read,
+        )?;
+        let numeric = |name: &str| -> Result<Float64Array> {
+            let values = cast(column(&batch, name)?, &DataType::Float64)?;
+            Ok(values.as_primitive::<Float64Type>().clone())
+        };
+        let fields = [
+            ("open", numeric(&io_cfg.open_column)?),
+            ("high", numeric(&io_cfg.high_column)?),
+            ("low", numeric(&io_cfg.low_column)?),
+            ("close", numeric(&io_cfg.close_column)?),
+            ("volume", numeric(&io_cfg.volume_column)?),
+        ];
+
+        for (row, ts) in ts.into_iter().enumerate() {
+            let line = rows_read + row + 1;
+            let missing = fields.iter().find(|(_, values)| values.is_null(row));
+            if let Some((field, _)) = missing {
+                if io_cfg.drop_invalid_rows {
+                    continue;
+                }
+                return Err(RuptureError::Parse {
+                    line,
+                    message: format!("missing or non-numeric value in field '{}'", field),
+                });
+            }
+            let [open, high, low, close, volume] = fields.each_ref().map(|(_, v)| v.value(row));
+            bars.push(Bar {
+                ts,
+                open,
+                high,
+                low,
+                close,
+                volume,
+                filled: false,
+            });
+        }
+        rows_read += batch.num_rows();
+    }
+
+    Ok(bars)
+}
+
+fn column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a dyn Array> {
+    let schema = batch.schema_ref();
+    schema
+        .fields()
+        .iter()
+        .position(|f| f.name().trim().eq_ignore_ascii_case(name))
+        .map(|i| batch.column(i).as_ref())
+        .ok_or_else(|| RuptureError::Config(format!("Column '{}' not found in input", name)))
+}
+
+/// Timestamps of one batch. Date and timestamp columns are instants already
+/// (in `io.timezone` when they carry no zone); other columns are read as
+/// text and parsed like CSV.
+fn timestamps(
+    values: &dyn Array,
+    parser: Option<&TimestampParser>,
+    rows_read: usize,
+) -> Result<Vec<Timestamp>> {
+    let temporal = matches!(
+        values.data_type(),
+        DataType::Timestamp(..) | DataType::Date32 | DataType::Date64
+    );
+    let Some(parser) = parser.filter(|_| temporal) else {
+        let text = cast(values, &DataType::Utf8)?;
+        return (0..text.len())
+            .map(|row| {
+                let text = text.as_string::<i32>();
+                let value = if text.is_null(row) {
+                    ""
+                } else {
+                    text.value(row)
+                };
+                match parser {
+                    Some(p) => p.parse(value, rows_read + row + 1),
+                    None => Ok(value.into()),
+                }
+            })
+            .collect();
+    };
+
+    let zone = match values.data_type() {
+        DataType::Timestamp(_, zone) => zone.clone(),
+        _ => None,
+    };
+    let nanos = cast(
+        values,
+        &DataType::Timestamp(TimeUnit::Nanosecond, zone.clone()),
+    )?;
+    let nanos = nanos.as_primitive::<TimestampNanosecondType>();
+    (0..nanos.len())
+        .map(|row| {
+            let line = rows_read + row + 1;
+            let instant = nanos.is_valid(row).then(|| {
+                let dt = DateTime::from_timestamp_nanos(nanos.value(row));
+                match zone {
+                    Some(_) => Some(dt),
+                    None => parser.from_local(dt.naive_utc()),
+                }
+            });
+            instant
+                .flatten()
+                .map(Timestamp::Parsed)
+                .ok_or_else(|| RuptureError::Parse {
+                    line,
+                    message: "missing or invalid timestamp".into(),
+                })
+        })
+        .collect()
+}
@@ -0,0 +1,271 @@
+use crate::config::IoConfig;
+use crate::error::{Result, RuptureError};
+use crate::types::Timestamp;
+use chrono::{
+    DateTime, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+
+/// Formats tried in order by the "auto" and "iso8601" strategies for
+/// timestamps without an explicit offset.
+const NAIVE_DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// ISO 8601 formats with an explicit offset.
+const OFFSET_DATETIME_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S%.f%:z", "%Y-%m-%d %H:%M:%S%.f%:z"];
+
+#[derive(Debug, Clone)]
+enum Format {
+    Auto,
+    Rfc3339,
+    Iso8601,
+    UnixSeconds,
+    UnixMillis,
+    Custom(String),
+}
+
+#[derive(Debug, Clone)]
+enum Zone {
+    Fixed(FixedOffset),
+    Named(Tz),
+}
+
+/// Parses timestamp text into UTC instants according to the IO config.
+/// Timestamps without an offset are interpreted in the configured time zone.
+#[derive(Debug, Clone)]
+pub struct TimestampParser {
+    format: Format,
+    zone: Zone,
+}
+
+impl TimestampParser {
+    /// Build a parser from `io.timestamp_format` and `io.timezone`.
+    pub fn from_config(io_cfg: &IoConfig) -> Result<Self> {
+        let format = match io_cfg.timestamp_format.trim() {
+            "auto" => Format::Auto,
+            "rfc3339" => Format::Rfc3339,
+            "iso8601" => Format::Iso8601,
+            "unix_s" => Format::UnixSeconds,
+            "unix_ms" => Format::UnixMillis,
+            custom if custom.contains('%') => Format::Custom(custom.to_string()),
+            other => {
+                return Err(RuptureError::Config(format!(
+                    "io.timestamp_format '{}' is not a known format or a chrono format string",
+                    other
+                )))
+            }
+        };
+        Ok(Self {
+            format,
+            zone: parse_zone(&io_cfg.timezone)?,
+        })
+    }
+
+    /// Parse a timestamp, reporting failures against the given input line.
+    pub fn parse(&self, text: &str, line: usize) -> Result<Timestamp> {
+        let text = text.trim();
+        self.parse_instant(text)
+            .map(Timestamp::Parsed)
+            .ok_or_else(|| RuptureError::Parse {
+                line,
+                message: format!(
+                    "cannot parse '{}' as a timestamp using format '{}'",
+                    text,
+                    self.format_name()
+                ),
+            })
+    }
+
+    fn parse_instant(&self, text: &str) -> Option<DateTime<Utc>> {
+        match &self.format {
+            Format::Auto => parse_rfc3339(text).or_else(|| self.parse_iso8601(text)),
+            Format::Rfc3339 => parse_rfc3339(text),
+            Format::Iso8601 => self.parse_iso8601(text),
+            Format::UnixSeconds => {
+                let secs: f64 = text.parse().ok()?;
+                let millis = (secs * 1000.0).round();
+                DateTime::from_timestamp_millis(millis as i64).filter(|_| millis.is_finite())
+            }
+            Format::UnixMillis => DateTime::from_timestamp_millis(text.parse().ok()?),
+            Format::Custom(fmt) => {
+                if let Ok(dt) = DateTime::parse_from_str(text, fmt) {
+                    return Some(dt.with_timezone(&Utc));
+                }
+                if let Ok(naive) = NaiveDateTime::parse_from_str(text, fmt) {
+                    return self.from_local(naive);
+                }
+                let date = NaiveDate::parse_from_str(text, fmt).ok()?;
+                self.from_local(date.and_time(NaiveTime::MIN))
+            }
+        }
+    }
+
+    fn parse_iso8601(&self, text: &str) -> Option<DateTime<Utc>> {
+        for fmt in OFFSET_DATETIME_FORMATS {
+            if let Ok(dt) = DateTime::parse_from_str(text, fmt) {
+                return Some(dt.with_timezone(&Utc));
+            }
+        }
+        for fmt in NAIVE_DATETIME_FORMATS {
+            if let Ok(naive) = NaiveDateTime::parse_from_str(text, fmt) {
+                return self.from_local(naive);
+            }
+        }
+        let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+        self.from_local(date.and_time(NaiveTime::MIN))
+    }
+
+    /// Interpret a naive local time in the configured zone. Ambiguous local
+    /// times (DST fall-back) resolve to the earlier instant; local times that
+    /// do not exist (DST spring-forward) fail to parse.
+    pub fn from_local(&self, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match &self.zone {
+            Zone::Fixed(offset) => earliest(offset.from_local_datetime(&naive)),
+            Zone::Named(tz) => earliest(tz.from_local_datetime(&naive)),
+        }
+    }
+
+    /// Convert an instant to local time in the configured zone.
+    pub fn to_local(&self, dt: DateTime<Utc>) -> NaiveDateTime {
+        match &self.zone {
+            Zone::Fixed(offset) => dt.with_timezone(offset).naive_local(),
+            Zone::Named(tz) => dt.with_timezone(tz).naive_local(),
+        }
+    }
+
+    fn format_name(&self) -> &str {
+        match &self.format {
+            Format::Auto => "auto",
+            Format::Rfc3339 => "rfc3339",
+            Format::Iso8601 => "iso8601",
+            Format::UnixSeconds => "unix_s",
+            Format::UnixMillis => "unix_ms",
+            Format::Custom(fmt) => fmt,
+        }
+    }
+}
+
+fn parse_rfc3339(text: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn earliest<Tz2: TimeZone>(result: LocalResult<DateTime<Tz2>>) -> Option<DateTime<Utc>> {
+    result.earliest().map(|dt| dt.with_timezone(&Utc))
+}
+
+fn parse_zone(name: &str) -> Result<Zone> {
+    let name = name.trim();
+    if name.eq_ignore_ascii_case("utc") || name == "Z" {
+        return Ok(Zone::Fixed(
+            FixedOffset::east_opt(0).expect("zero offset is valid"),
+        ));
+    }
+    if name.starts_with('+') || name.starts_with('-') {
+        if let Ok(dt) = DateTime::parse_from_str(
+            &format!("2000-01-01T00:00:00{}", name),
+            "%Y-%m-%dT%H:%M:%S%:z",
+        ) {
+            return Ok(Zone::Fixed(*dt.offset()));
+        }
+    }
+    name.parse::<Tz>().map(Zone::Named).map_err(|_| {
+        RuptureError::Config(format!(
+            "io.timezone '{}' is not UTC, a fixed offset like +05:30, or an IANA zone name",
+            name
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser(format: &str, timezone: &str) -> TimestampParser {
+        let io_cfg = IoConfig {
+            timestamp_column: "timestamp".into(),
+            open_column: "open".into(),
+            high_column: "high".into(),
+            low_column: "low".into(),
+            close_column: "close".into(),
+            volume_column: "volume".into(),
+            parse_timestamps: true,
+            timestamp_format: format.into(),
+            timezone: timezone.into(),
+            drop_invalid_rows: true,
+            sort_by_timestamp: true,
+        };
+        TimestampParser::from_config(&io_cfg).unwrap()
+    }
+
+    fn utc(text: &str) -> Timestamp {
+        Timestamp::Parsed(
+            DateTime::parse_from_rfc3339(text)
+                .unwrap()
+                .with_timezone(&Utc),
+        )
+    }
+
+    #[test]
+    fn test_auto_formats() {
+        let p = parser("auto", "UTC");
+        assert_eq!(
+            p.parse("2008-01-02", 2).unwrap(),
+            utc("2008-01-02T00:00:00Z")
+        );
+        assert_eq!(
+            p.parse("2008-01-02 09:30:00", 2).unwrap(),
+            utc("2008-01-02T09:30:00Z")
+        );
+        assert_eq!(
+            p.parse("2008-01-02T09:30:00+01:00", 2).unwrap(),
+            utc("2008-01-02T08:30:00Z")
+        );
+    }
+
+    #[test]
+    fn test_custom_date_format_orders_chronologically() {
+        let p = parser("%m/%d/%Y", "UTC");
+        let a = p.parse("12/1/2007", 2).unwrap();
+        let b = p.parse("1/2/2008", 3).unwrap();
+        assert!(a < b);
+    }
+
+    #[test]
+    fn test_unix_epochs() {
+        assert_eq!(
+            parser("unix_s", "UTC").parse("1199232000", 2).unwrap(),
+            utc("2008-01-02T00:00:00Z")
+        );
+        assert_eq!(
+            parser("unix_ms", "UTC").parse("1199232000500", 2).unwrap(),
+            utc("2008-01-02T00:00:00.500Z")
+        );
+    }
+
+    #[test]
+    fn test_named_timezone() {
+        let p = parser("%Y-%m-%d %H:%M", "America/New_York");
+        assert_eq!(
+            p.parse("2008-01-02 09:30", 2).unwrap(),
+            utc("2008-01-02T14:30:00Z")
+        );
+        assert_eq!(
+            p.parse("2008-07-02 09:30", 2).unwrap(),
+            utc("2008-07-02T13:30:00Z")
+        );
+    }
+
+    #[test]
+    fn test_failure_reports_line() {
+        match parser("rfc3339", "UTC").parse("not a date", 7) {
+            Err(RuptureError::Parse { line, .. }) => assert_eq!(line, 7),
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+}
@@ -1,22 +1,78 @@
 use crate::error::Result;
+use crate::model::ContagionRow;
 use crate::types::EngineOutputRow;
 use std::path::Path;
 
-/// Write the per-bar time series output to a CSV file.
-pub fn write_timeseries_csv(rows: &[EngineOutputRow], path: &Path) -> Result<()> {
+/// Write the per-bar time series output to a CSV file. Each residual channel
+/// gets a column `r_<name>` and an attribution column `attr_<name>`, in the
+/// order of `channel_names`.
+pub fn write_timeseries_csv(
+    rows: &[EngineOutputRow],
+    channel_names: &[String],
+    path: &Path,
+) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+
+    let mut header = vec!["timestamp".to_string(), "close".into(), "volume".into()];
+    header.extend(channel_names.iter().map(|name| format!("r_{}", name)));
+    header.push("r_combined".into());
+    header.extend(channel_names.iter().map(|name| format!("attr_{}", name)));
+    header.extend(
+        [
+            "strain",
+            "capacity",
+            "rho",
+            "state_code",
+            "state",
+            "candidate",
+            "confirmed",
+            "gap",
+            "filled",
+        ]
+        .map(String::from),
+    );
+    writer.write_record(&header)?;
+
+    for row in rows {
+        let mut record = vec![
+            row.timestamp.to_string(),
+            format!("{:.6}", row.close),
+            format!("{:.2}", row.volume),
+        ];
+        record.extend(row.residuals.iter().map(|r| format!("{:.8}", r)));
+        record.push(format!("{:.8}", row.r_combined));
+        record.extend(row.attribution.iter().map(|a| format!("{:.8}", a)));
+        record.extend([
+            format!("{:.8}", row.strain),
+            format!("{:.8}", row.capacity),
+            format!("{:.8}", row.rho),
+            row.state.code().to_string(),
+            row.state_label.clone(),
+            if row.candidate_flag { "1" } else { "0" }.to_string(),
+            if row.confirmed_flag { "1" } else { "0" }.to_string(),
+            row.gap.to_string(),
+            if row.filled { "1" } else { "0" }.to_string(),
+        ]);
+        writer.write_record(&record)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write the cross-sectional contagion index to a CSV file.
+pub fn write_contagion_csv(rows: &[ContagionRow], path: &Path) -> Result<()> {
     let mut writer = csv::Writer::from_path(path)?;
 
     writer.write_record([
         "timestamp",
-        "close",
-        "volume",
-        "r_vol",
-        "r_liq",
-        "r_acc",
-        "r_combined",
-        "strain",
-        "capacity",
-        "rho",
+        "assets",
+        "frac_critical",
+        "mean_rho",
+        "quantile_rho",
+        "candidates",
+        "signal",
+        "state_code",
         "state",
         "candidate",
         "confirmed",
@@ -24,17 +80,15 @@ pub fn write_timeseries_csv(rows: &[EngineOutputRow], path: &Path) -> Result<()>
 
     for row in rows {
         writer.write_record(&[
-            row.timestamp.clone(),
-            format!("{:.6}", row.close),
-            format!("{:.2}", row.volume),
-            format!("{:.8}", row.r_vol),
-            format!("{:.8}", row.r_liq),
-            format!("{:.8}", row.r_acc),
-            format!("{:.8}", row.r_combined),
-            format!("{:.8}", row.strain),
-            format!("{:.8}", row.capacity),
-            format!("{:.8}", row.rho),
-            row.state.clone(),
+            row.timestamp.to_string(),
+            row.assets.to_string(),
+            format!("{:.6}", row.frac_critical),
+            format!("{:.8}", row.mean_rho),
+            format!("{:.8}", row.quantile_rho),
+            row.candidates.to_string(),
+            format!("{:.8}", row.signal),
+            row.state.code().to_string(),
+            row.state_label.clone(),
             if row.candidate_flag { "1" } else { "0" }.to_string(),
             if row.confirmed_flag { "1" } else { "0" }.to_string(),
         ])?;
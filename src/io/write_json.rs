@@ -1,7 +1,9 @@
 use crate::config::Config;
 use crate::error::Result;
-use crate::types::RuptureEvent;
-use std::fs;
+use crate::evaluate::EvaluationReport;
+use crate::types::{RuptureEpisode, RuptureEvent, StateTransition};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
 use std::path::Path;
 
 /// Write the list of detected rupture events to a JSON file.
@@ -11,9 +13,34 @@ pub fn write_events_json(events: &[RuptureEvent], path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Write the rupture episodes to a JSON file.
+pub fn write_episodes_json(episodes: &[RuptureEpisode], path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(episodes)?;
+    fs::write(path, json)?;
+    Ok(())
+}
+
+/// Write the state transition log as JSON Lines, one transition per line.
+pub fn write_transitions_jsonl(transitions: &[StateTransition], path: &Path) -> Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    for transition in transitions {
+        serde_json::to_writer(&mut out, transition)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
 /// Write a snapshot of the config used for this run to a JSON file.
 pub fn write_config_snapshot(config: &Config, path: &Path) -> Result<()> {
     let json = serde_json::to_string_pretty(config)?;
     fs::write(path, json)?;
     Ok(())
 }
+
+/// Write a labelled-event evaluation report to a JSON file.
+pub fn write_evaluation_json(report: &EvaluationReport, path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(report)?;
+    fs::write(path, json)?;
+    Ok(())
+}
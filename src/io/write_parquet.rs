@@ -0,0 +1,129 @@
+use crate::error::Result;
+use crate::types::{EngineOutputRow, Timestamp};
+use arrow::array::{
+    ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+    UInt64Array,
+};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Write the per-bar time series to a Parquet file, with the columns of the
+/// CSV output as typed columns: floats for prices and model values, booleans
+/// for flags, and a UTC timestamp column when every timestamp was parsed
+/// (text otherwise).
+pub fn write_timeseries_parquet(
+    rows: &[EngineOutputRow],
+    channel_names: &[String],
+    path: &Path,
+) -> Result<()> {
+    let mut fields = Vec::new();
+    let mut columns: Vec<ArrayRef> = Vec::new();
+    let mut push = |name: String, data_type: DataType, column: ArrayRef| {
+        fields.push(Field::new(name, data_type, false));
+        columns.push(column);
+    };
+    let floats = |value: &dyn Fn(&EngineOutputRow) -> f64| -> ArrayRef {
+        Arc::new(rows.iter().map(value).collect::<Float64Array>())
+    };
+    let flags = |value: &dyn Fn(&EngineOutputRow) -> bool| -> ArrayRef {
+        Arc::new(
+            rows.iter()
+                .map(|r| Some(value(r)))
+                .collect::<BooleanArray>(),
+        )
+    };
+
+    let parsed: Option<Vec<i64>> = rows
+        .iter()
+        .map(|r| r.timestamp.as_datetime().map(|dt| dt.timestamp_micros()))
+        .collect();
+    match parsed {
+        Some(micros) => push(
+            "timestamp".into(),
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            Arc::new(TimestampMicrosecondArray::from(micros).with_timezone("UTC")),
+        ),
+        None => push(
+            "timestamp".into(),
+            DataType::Utf8,
+            Arc::new(StringArray::from_iter_values(
+                rows.iter().map(|r| Timestamp::to_string(&r.timestamp)),
+            )),
+        ),
+    }
+    push("close".into(), DataType::Float64, floats(&|r| r.close));
+    push("volume".into(), DataType::Float64, floats(&|r| r.volume));
+    for (i, name) in channel_names.iter().enumerate() {
+        push(
+            format!("r_{}", name),
+            DataType::Float64,
+            floats(&|r| r.residuals[i]),
+        );
+    }
+    push(
+        "r_combined".into(),
+        DataType::Float64,
+        floats(&|r| r.r_combined),
+    );
+    for (i, name) in channel_names.iter().enumerate() {
+        push(
+            format!("attr_{}", name),
+            DataType::Float64,
+            floats(&|r| r.attribution[i]),
+        );
+    }
+    push("strain".into(), DataType::Float64, floats(&|r| r.strain));
+    push(
+        "capacity".into(),
+        DataType::Float64,
+        floats(&|r| r.capacity),
+    );
+    push("rho".into(), DataType::Float64, floats(&|r| r.rho));
+    push(
+        "state_code".into(),
+        DataType::Utf8,
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|r| r.state.code()),
+        )),
+    );
+    push(
+        "state".into(),
+        DataType::Utf8,
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|r| r.state_label.as_str()),
+        )),
+    );
+    push(
+        "candidate".into(),
+        DataType::Boolean,
+        flags(&|r| r.candidate_flag),
+    );
+    push(
+        "confirmed".into(),
+        DataType::Boolean,
+        flags(&|r| r.confirmed_flag),
+    );
+    push(
+        "gap".into(),
+        DataType::UInt64,
+        Arc::new(UInt64Array::from_iter_values(
+            rows.iter().map(|r| r.gap as u64),
+        )),
+    );
+    push("filled".into(), DataType::Boolean, flags(&|r| r.filled));
+
+    let schema = Arc::new(Schema::new(fields));
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(File::create(path)?, schema, Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
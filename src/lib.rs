@@ -1,9 +1,14 @@
+pub mod batch;
 pub mod config;
 pub mod error;
+pub mod evaluate;
 pub mod features;
 pub mod io;
 pub mod model;
+pub mod parallel;
+pub mod pipeline;
 pub mod preprocess;
 pub mod reporting;
 pub mod stats;
+pub mod sweep;
 pub mod types;
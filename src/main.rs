@@ -1,20 +1,143 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use rupture_engine::config::Config;
-use rupture_engine::io::{read_bars_csv, write_config_snapshot, write_events_json, write_timeseries_csv};
-use rupture_engine::model::run_engine;
-use rupture_engine::preprocess::{clean_bars, validate_bars};
+use rupture_engine::batch::{
+    batch_contagion, batch_items, run_batch, write_batch_events, write_batch_summary,
+};
+use rupture_engine::config::{Config, GapPolicy, DEFAULT_CONFIG_TOML};
+use rupture_engine::error::RuptureError;
+use rupture_engine::evaluate::{
+    check_time_order, evaluation_report, read_labels, Tolerance, DEFAULT_BARS_PER_YEAR,
+};
+use rupture_engine::io::{
+    read_checkpoint, write_checkpoint, write_contagion_csv, write_evaluation_json,
+    write_events_json,
+};
+use rupture_engine::model::checkpoint::unprocessed_bars;
+use rupture_engine::model::{Checkpoint, EngineRun, RuptureEngine};
+use rupture_engine::parallel::default_jobs;
+use rupture_engine::pipeline::{load_bars, run_file, write_outputs};
+use rupture_engine::preprocess::{
+    fill_between, validate_bars, validate_bars_with_history, GapDetector,
+};
 use rupture_engine::reporting::diagnostics::run_diagnostics;
-use rupture_engine::reporting::print_summary;
+use rupture_engine::reporting::{explain_config, print_evaluation, print_summary};
+use rupture_engine::sweep::{run_sweep, write_sweep_csv, SweepLabels, SweepSpec};
 
 #[derive(Parser, Debug)]
 #[command(
     name = "rupture-engine",
-    about = "Deterministic rupture detection engine for financial time series"
+    about = "Deterministic rupture detection engine for financial time series",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Without a subcommand, run the engine (same as `run`).
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the engine on one input or a batch.
+    Run(RunArgs),
+    /// Load and validate configuration files.
+    ValidateConfig(ValidateConfigArgs),
+    /// Check input data against a config without running the engine.
+    Diagnose(DiagnoseArgs),
+    /// Write the commented default configuration.
+    InitConfig(InitConfigArgs),
+    /// Print the effective configuration, with defaults filled in.
+    Explain(ExplainArgs),
+    /// Run every combination of a parameter grid over one input and write a
+    /// results table.
+    Sweep(SweepArgs),
+    /// Score a run against labelled regime-change intervals.
+    Evaluate(EvaluateArgs),
+}
+
+#[derive(Args, Debug)]
+struct RunArgs {
+    /// Path to the input OHLCV CSV file.
+    #[arg(short, long, required_unless_present = "batch")]
+    input: Option<PathBuf>,
+
+    /// Path to the TOML configuration file.
+    #[arg(short, long, required = true)]
+    config: Option<PathBuf>,
+
+    /// Override a config key, e.g. `--set state_machine.confirm_k=2`.
+    /// Repeatable; applied over the file and `RUPTURE_*` variables.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    /// Directory for output files.
+    #[arg(short, long, default_value = "output")]
+    output_dir: PathBuf,
+
+    /// Resume from an engine checkpoint. Bars up to and including the
+    /// checkpoint's last timestamp are skipped.
+    #[arg(long, conflicts_with = "batch")]
+    resume: Option<PathBuf>,
+
+    /// Write an engine checkpoint after processing, for a later --resume.
+    #[arg(long, conflicts_with = "batch")]
+    checkpoint: Option<PathBuf>,
+
+    /// Run every CSV in a directory, or every asset in a TOML manifest,
+    /// writing per-asset outputs to subdirectories of --output-dir.
+    #[arg(long, conflicts_with = "input")]
+    batch: Option<PathBuf>,
+
+    /// Number of assets to process in parallel in batch mode.
+    #[arg(long, default_value_t = default_jobs())]
+    jobs: usize,
+}
+
+#[derive(Args, Debug)]
+struct SweepArgs {
+    /// Path to the input OHLCV CSV file.
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// Base TOML configuration; swept keys are overridden per combination.
+    #[arg(short, long)]
+    config: PathBuf,
+
+    /// Override a config key, e.g. `--set state_machine.confirm_k=2`.
+    /// Repeatable; applied over the file and `RUPTURE_*` variables.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    /// TOML sweep spec with a [params] table of dotted keys.
+    #[arg(short, long)]
+    spec: PathBuf,
+
+    /// Directory for the results table.
+    #[arg(short, long, default_value = "output")]
+    output_dir: PathBuf,
+
+    /// CSV of labelled intervals (start, end, optional name); adds
+    /// precision, recall and F1 to the results.
+    #[arg(long)]
+    labels: Option<PathBuf>,
+
+    /// How far either side of a labelled interval a confirmed rupture still
+    /// counts as a hit: bars ("5") or a duration ("10d").
+    #[arg(long, default_value = "0")]
+    tolerance: Tolerance,
+
+    /// Number of combinations to run in parallel.
+    #[arg(long, default_value_t = default_jobs())]
+    jobs: usize,
+}
+
+#[derive(Args, Debug)]
+struct EvaluateArgs {
     /// Path to the input OHLCV CSV file.
     #[arg(short, long)]
     input: PathBuf,
@@ -23,54 +146,375 @@ struct Cli {
     #[arg(short, long)]
     config: PathBuf,
 
-    /// Directory for output files.
+    /// Override a config key, e.g. `--set state_machine.confirm_k=2`.
+    /// Repeatable; applied over the file and `RUPTURE_*` variables.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    /// CSV of labelled intervals with start, end and optional name columns.
+    #[arg(short, long)]
+    labels: PathBuf,
+
+    /// How far either side of a labelled interval a confirmed rupture still
+    /// counts as a hit: bars ("5") or a duration ("10d").
+    #[arg(long, default_value = "0")]
+    tolerance: Tolerance,
+
+    /// Bars per year, for false alarms per year when timestamps are not
+    /// parsed.
+    #[arg(long, default_value_t = DEFAULT_BARS_PER_YEAR)]
+    bars_per_year: f64,
+
+    /// Directory for evaluation.json.
     #[arg(short, long, default_value = "output")]
     output_dir: PathBuf,
 }
 
+#[derive(Args, Debug)]
+struct ValidateConfigArgs {
+    /// TOML configuration files to check.
+    #[arg(required = true)]
+    configs: Vec<PathBuf>,
+
+    /// Override a config key, e.g. `--set state_machine.confirm_k=2`.
+    /// Repeatable; applied over the file and `RUPTURE_*` variables.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct DiagnoseArgs {
+    /// Path to the input OHLCV CSV file.
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// Path to the TOML configuration file.
+    #[arg(short, long)]
+    config: PathBuf,
+
+    /// Override a config key, e.g. `--set state_machine.confirm_k=2`.
+    /// Repeatable; applied over the file and `RUPTURE_*` variables.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct InitConfigArgs {
+    /// File to write; prints to stdout when omitted.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Overwrite an existing file.
+    #[arg(long)]
+    force: bool,
+}
+
+#[derive(Args, Debug)]
+struct ExplainArgs {
+    /// Configuration to explain; the defaults when omitted.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    /// Override a config key, e.g. `--set state_machine.confirm_k=2`.
+    /// Repeatable; applied over the file and `RUPTURE_*` variables.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
 
+    match &cli.command {
+        Some(Command::Run(args)) => run_command(args),
+        Some(Command::ValidateConfig(args)) => run_validate_config(args),
+        Some(Command::Diagnose(args)) => run_diagnose(args),
+        Some(Command::InitConfig(args)) => run_init_config(args),
+        Some(Command::Explain(args)) => run_explain(args),
+        Some(Command::Sweep(args)) => run_sweep_command(args),
+        Some(Command::Evaluate(args)) => run_evaluate_command(args),
+        None => run_command(&cli.run),
+    }
+}
+
+fn run_command(cli: &RunArgs) -> Result<(), Box<dyn std::error::Error>> {
     // Load configuration.
-    let config = Config::from_file(&cli.config)?;
+    let config_path = cli.config.as_ref().expect("clap requires --config");
+    let config = Config::load(Some(config_path), &cli.set)?;
 
     // Ensure output directory exists.
     fs::create_dir_all(&cli.output_dir)?;
 
+    match (&cli.batch, &cli.input) {
+        (Some(batch), _) => run_batch_mode(cli, batch, &config),
+        (None, Some(input)) => run_single(cli, input, &config),
+        (None, None) => unreachable!("clap requires --input or --batch"),
+    }
+}
+
+fn run_single(
+    cli: &RunArgs,
+    input: &Path,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
     // Read and clean input data.
-    let bars = read_bars_csv(&cli.input, &config.io)?;
-    let bars = clean_bars(bars, config.io.sort_by_timestamp);
+    let bars = load_bars(input, config)?;
 
-    // Validate.
-    validate_bars(&bars, &config.preprocess, &config.windows)?;
+    // Report gaps on the input as read; the engine forward-fills them if
+    // requested.
+    run_diagnostics(&bars, config);
 
-    // Run diagnostics.
-    run_diagnostics(&bars, &config);
+    // Restore engine state if resuming, and skip bars already processed.
+    let mut engine = match &cli.resume {
+        Some(path) => read_checkpoint(path)?.into_engine(config)?,
+        None => RuptureEngine::new(config)?,
+    };
+    let history = engine.bars_processed();
+    let new_bars = unprocessed_bars(&engine, &bars)?;
+    if cli.resume.is_some() && new_bars.is_empty() {
+        println!("No new bars since checkpoint; nothing to do.");
+        return Ok(());
+    }
 
-    // Run engine.
-    let (rows, events) = run_engine(&bars, &config);
+    // Validate.
+    validate_bars_with_history(new_bars, &config.preprocess, &config.windows, history)?;
 
-    // Write outputs.
-    if config.outputs.write_csv_timeseries {
-        let path = cli.output_dir.join(&config.outputs.csv_timeseries_name);
-        write_timeseries_csv(&rows, &path)?;
-        println!("Wrote time series CSV to {}", path.display());
+    // Run engine. On resume only events and transitions raised by the new
+    // bars, and episodes still open or ended within them, are reported.
+    let events_before = engine.events().len();
+    let transitions_before = engine.transitions().len();
+    let rows: Vec<_> = new_bars
+        .iter()
+        .flat_map(|bar| engine.update(bar))
+        .map(|(row, _)| row)
+        .collect();
+    let filled = rows.iter().filter(|r| r.filled).count();
+    if filled > 0 {
+        eprintln!("[INFO] Forward-filled {} bars across gaps.", filled);
     }
+    let run = EngineRun {
+        rows,
+        events: engine.events()[events_before..].to_vec(),
+        episodes: engine
+            .episodes()
+            .iter()
+            .filter(|e| e.end_index.is_none_or(|end| end >= history))
+            .cloned()
+            .collect(),
+        transitions: engine.transitions()[transitions_before..].to_vec(),
+    };
 
-    if config.outputs.write_json_events {
-        let path = cli.output_dir.join(&config.outputs.json_events_name);
-        write_events_json(&events, &path)?;
-        println!("Wrote events JSON to {}", path.display());
+    // Write outputs.
+    for (what, path) in write_outputs(&run, config, &cli.output_dir)? {
+        println!("Wrote {} to {}", what, path.display());
     }
 
-    if config.outputs.write_json_config_snapshot {
-        let path = cli.output_dir.join(&config.outputs.json_config_snapshot_name);
-        write_config_snapshot(&config, &path)?;
-        println!("Wrote config snapshot to {}", path.display());
+    if let Some(path) = &cli.checkpoint {
+        write_checkpoint(&Checkpoint::new(&engine), path)?;
+        println!("Wrote checkpoint to {}", path.display());
     }
 
     // Print summary.
-    print_summary(&rows, &events);
+    print_summary(&run.rows, &run.events);
+
+    Ok(())
+}
+
+fn run_batch_mode(
+    cli: &RunArgs,
+    batch: &Path,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let items = batch_items(batch)?;
+    println!("Running {} assets on {} threads", items.len(), cli.jobs);
+
+    let outcomes = run_batch(&items, config, &cli.output_dir, cli.jobs);
+
+    for outcome in &outcomes {
+        match &outcome.result {
+            Ok(summary) => println!(
+                "  {:<16} ok      {:>7} bars, {} events",
+                outcome.name,
+                summary.bars,
+                summary.events.len()
+            ),
+            Err(message) => println!("  {:<16} FAILED  {}", outcome.name, message),
+        }
+    }
+
+    let summary_path = cli.output_dir.join("batch_summary.csv");
+    write_batch_summary(&outcomes, &summary_path)?;
+    println!("Wrote batch summary to {}", summary_path.display());
+
+    let events_path = cli.output_dir.join("batch_events.csv");
+    write_batch_events(&outcomes, &events_path)?;
+    println!("Wrote batch events to {}", events_path.display());
+
+    if config.contagion.enabled {
+        let (rows, events) = batch_contagion(&outcomes, config)?;
+        let csv_path = cli.output_dir.join(&config.contagion.csv_name);
+        write_contagion_csv(&rows, &csv_path)?;
+        println!("Wrote contagion index to {}", csv_path.display());
+
+        let events_path = cli.output_dir.join(&config.contagion.json_events_name);
+        write_events_json(&events, &events_path)?;
+        println!(
+            "Wrote {} systemic events to {}",
+            events.len(),
+            events_path.display()
+        );
+    }
+
+    // Every asset has run and the summaries are written; a failure on any
+    // asset still makes the process exit non-zero.
+    let failed = outcomes.iter().filter(|o| o.result.is_err()).count();
+    if failed > 0 {
+        return Err(format!("{} of {} assets failed", failed, outcomes.len()).into());
+    }
+    Ok(())
+}
+
+fn run_sweep_command(args: &SweepArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load(Some(&args.config), &args.set)?;
+    let spec = SweepSpec::from_file(&args.spec)?;
+    spec.check_keys(&config)?;
+    fs::create_dir_all(&args.output_dir)?;
+
+    // Every combination runs on the same preprocessed bars.
+    let bars = load_bars(&args.input, &config)?;
+    validate_bars(&bars, &config.preprocess, &config.windows)?;
+    let labels = match &args.labels {
+        Some(path) => {
+            let timestamps: Vec<_> = bars.iter().map(|b| b.ts.clone()).collect();
+            check_time_order(&timestamps)?;
+            Some(read_labels(path, &config.io)?)
+        }
+        None => None,
+    };
+
+    println!(
+        "Sweeping {} combinations of {} parameters on {} threads",
+        spec.len(),
+        spec.params.len(),
+        args.jobs
+    );
+    let results = run_sweep(
+        &bars,
+        &config,
+        &spec,
+        labels.as_deref().map(|labels| SweepLabels {
+            labels,
+            tolerance: args.tolerance,
+        }),
+        args.jobs,
+    );
+
+    let path = args.output_dir.join("sweep_results.csv");
+    write_sweep_csv(&results, &spec, &path)?;
+    let failed = results.iter().filter(|r| r.result.is_err()).count();
+    println!(
+        "Wrote {} results ({} failed) to {}",
+        results.len(),
+        failed,
+        path.display()
+    );
+    Ok(())
+}
+
+fn run_evaluate_command(args: &EvaluateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load(Some(&args.config), &args.set)?;
+    let labels = read_labels(&args.labels, &config.io)?;
+    fs::create_dir_all(&args.output_dir)?;
+
+    let run = run_file(&args.input, &config)?;
+    let timestamps: Vec<_> = run.rows.iter().map(|r| r.timestamp.clone()).collect();
+    let report = evaluation_report(
+        &run.events,
+        &timestamps,
+        &labels,
+        args.tolerance,
+        args.bars_per_year,
+    )?;
+
+    let path = args.output_dir.join("evaluation.json");
+    write_evaluation_json(&report, &path)?;
+    println!("Wrote evaluation to {}", path.display());
+    print_evaluation(&report);
+    Ok(())
+}
+
+fn run_validate_config(args: &ValidateConfigArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut failed = 0;
+    for path in &args.configs {
+        let report = match Config::load(Some(path), &args.set) {
+            Ok(config) => {
+                println!("{}: ok", path.display());
+                config.validation_report()
+            }
+            Err(RuptureError::InvalidConfig(report)) => {
+                println!("{}: invalid", path.display());
+                failed += 1;
+                report
+            }
+            Err(e) => {
+                println!("{}: {}", path.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+        for issue in &report.issues {
+            println!("  {}: {}", issue.severity, issue);
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} of {} configs are invalid", failed, args.configs.len()).into());
+    }
+    Ok(())
+}
+
+fn run_diagnose(args: &DiagnoseArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load(Some(&args.config), &args.set)?;
+    let bars = load_bars(&args.input, &config)?;
+    run_diagnostics(&bars, &config);
+
+    if config.gaps.enabled && config.gaps.policy == GapPolicy::ForwardFill {
+        let detector = GapDetector::from_config(&config.gaps, &config.io)?;
+        let filled: usize = bars
+            .windows(2)
+            .map(|w| fill_between(&w[0], &w[1], &detector).len())
+            .sum();
+        if filled > 0 {
+            println!("Gap policy would forward-fill {} bars.", filled);
+        }
+    }
+    validate_bars(&bars, &config.preprocess, &config.windows)?;
+    if let (Some(first), Some(last)) = (bars.first(), bars.last()) {
+        println!(
+            "{}: {} bars from {} to {}; ready to run.",
+            args.input.display(),
+            bars.len(),
+            first.ts,
+            last.ts
+        );
+    }
+    Ok(())
+}
+
+fn run_init_config(args: &InitConfigArgs) -> Result<(), Box<dyn std::error::Error>> {
+    match &args.output {
+        Some(path) => {
+            if path.exists() && !args.force {
+                return Err(format!("{} exists; use --force to overwrite", path.display()).into());
+            }
+            fs::write(path, DEFAULT_CONFIG_TOML)?;
+            println!("Wrote default configuration to {}", path.display());
+        }
+        None => print!("{}", DEFAULT_CONFIG_TOML),
+    }
+    Ok(())
+}
 
+fn run_explain(args: &ExplainArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load(args.config.as_deref(), &args.set)?;
+    print!("{}", explain_config(&config)?);
     Ok(())
 }
@@ -1,9 +1,11 @@
-use crate::stats::{quantile, RollingWindow};
+use crate::stats::OrderedWindow;
+use serde::{Deserialize, Serialize};
 
 /// Adaptive capacity estimator using a rolling quantile of the strain history.
 /// Optionally applies exponential smoothing for stability.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CapacityEstimator {
-    window: RollingWindow,
+    window: OrderedWindow,
     q: f64,
     smooth: bool,
     lambda: f64,
@@ -14,7 +16,7 @@ pub struct CapacityEstimator {
 impl CapacityEstimator {
     pub fn new(window_l: usize, q: f64, smooth: bool, lambda: f64, e_min: f64) -> Self {
         Self {
-            window: RollingWindow::new(window_l),
+            window: OrderedWindow::new(window_l),
             q,
             smooth,
             lambda,
@@ -26,8 +28,7 @@ impl CapacityEstimator {
     /// Update the capacity estimate with a new strain value and return the current capacity.
     pub fn update(&mut self, strain: f64) -> f64 {
         self.window.push(strain);
-        let snapshot = self.window.as_slice();
-        let e_raw = quantile(&snapshot, self.q).max(self.e_min);
+        let e_raw = self.window.quantile(self.q).max(self.e_min);
 
         let e = if self.smooth {
             match self.prev_e {
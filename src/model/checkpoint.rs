@@ -0,0 +1,86 @@
+use crate::config::Config;
+use crate::error::{Result, RuptureError};
+use crate::model::engine::RuptureEngine;
+use crate::model::residuals::ChannelRegistry;
+use crate::types::Bar;
+use serde::{Deserialize, Serialize};
+
+/// Serialisable snapshot of the full engine state: feature windows, memory
+/// buffer, capacity window and smoothing state, and state machine tracking.
+/// Resuming from a checkpoint and continuing produces the same output as a
+/// single uninterrupted run over the same bars.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub format_version: u32,
+    pub engine: RuptureEngine,
+}
+
+impl Checkpoint {
+    pub const FORMAT_VERSION: u32 = 9;
+
+    /// Capture the current state of an engine.
+    pub fn new(engine: &RuptureEngine) -> Self {
+        Self {
+            format_version: Self::FORMAT_VERSION,
+            engine: engine.clone(),
+        }
+    }
+
+    /// Restore the engine, checking that the checkpoint was written with a
+    /// compatible format and with the same model parameters as `config`.
+    /// Only the `outputs` section may differ between the two configs.
+    pub fn into_engine(self, config: &Config) -> Result<RuptureEngine> {
+        self.into_engine_with_registry(config, &ChannelRegistry::builtin())
+    }
+
+    /// [`Checkpoint::into_engine`] for an engine built with
+    /// [`RuptureEngine::with_registry`].
+    pub fn into_engine_with_registry(
+        self,
+        config: &Config,
+        registry: &ChannelRegistry,
+    ) -> Result<RuptureEngine> {
+        if self.format_version != Self::FORMAT_VERSION {
+            return Err(RuptureError::Checkpoint(format!(
+                "unsupported checkpoint format version {} (expected {})",
+                self.format_version,
+                Self::FORMAT_VERSION
+            )));
+        }
+        if model_params(self.engine.config())? != model_params(config)? {
+            return Err(RuptureError::Checkpoint(
+                "checkpoint was written with different model parameters than the current config"
+                    .into(),
+            ));
+        }
+        let mut engine = self.engine;
+        engine.build_stages(registry)?;
+        Ok(engine)
+    }
+}
+
+/// Return the bars that come after the last bar the engine has processed,
+/// comparing timestamps. `bars` may hold the full history or only newer
+/// bars; it is an error if it overlaps the processed bars without containing
+/// the last of them, since the engine cannot continue from it.
+pub fn unprocessed_bars<'a>(engine: &RuptureEngine, bars: &'a [Bar]) -> Result<&'a [Bar]> {
+    let Some(last) = engine.last_timestamp() else {
+        return Ok(bars);
+    };
+    let start = bars.iter().position(|b| b.ts > *last).unwrap_or(bars.len());
+    if start > 0 && bars[start - 1].ts != *last {
+        return Err(RuptureError::Checkpoint(format!(
+            "input does not continue from the checkpoint: its last bar ({}) is not in the input",
+            last
+        )));
+    }
+    Ok(&bars[start..])
+}
+
+fn model_params(config: &Config) -> Result<serde_json::Value> {
+    let mut value = serde_json::to_value(config)?;
+    if let Some(map) = value.as_object_mut() {
+        map.remove("outputs");
+    }
+    Ok(value)
+}
@@ -1,8 +1,16 @@
+use crate::config::{CombineConfig, CombineMethod};
+
 /// Numerically stable log-sum-exp soft-max combiner for residual channels.
 ///   r = tau * log( exp(r_vol/tau) + exp(r_liq/tau) + exp(r_acc/tau) )
 /// The implementation shifts by the maximum exponent to avoid overflow.
 pub fn soft_max_combine(r_vol: f64, r_liq: f64, r_acc: f64, tau: f64) -> f64 {
-    let vals = [r_vol / tau, r_liq / tau, r_acc / tau];
+    soft_max_combine_n(&[r_vol, r_liq, r_acc], tau)
+}
+
+/// Soft-max over any number of channels:
+///   r = tau * log( sum_i exp(r_i/tau) )
+pub fn soft_max_combine_n(residuals: &[f64], tau: f64) -> f64 {
+    let vals: Vec<f64> = residuals.iter().map(|r| r / tau).collect();
     let max_val = vals.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
 
     // If all residuals are zero, the combined residual is zero.
@@ -14,6 +22,153 @@ pub fn soft_max_combine(r_vol: f64, r_liq: f64, r_acc: f64, tau: f64) -> f64 {
     tau * (max_val + sum_exp.ln())
 }
 
+/// Weighted soft-max:
+///   r = tau * log( sum_i w_i exp(r_i/tau) )
+/// Channels with zero weight are ignored.
+pub fn weighted_soft_max_combine(residuals: &[f64], weights: &[f64], tau: f64) -> f64 {
+    let vals = weighted_exponents(residuals, weights, tau);
+    let max_val = vals.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if max_val == f64::NEG_INFINITY || max_val.is_nan() {
+        return 0.0;
+    }
+
+    let sum_exp: f64 = vals.iter().map(|&x| (x - max_val).exp()).sum();
+    tau * (max_val + sum_exp.ln())
+}
+
+/// Weighted sum: r = sum_i w_i r_i.
+pub fn weighted_sum_combine(residuals: &[f64], weights: &[f64]) -> f64 {
+    residuals.iter().zip(weights).map(|(r, w)| w * r).sum()
+}
+
+/// Hard max: r = max_i w_i r_i, or 0 with no channels.
+pub fn hard_max_combine(residuals: &[f64], weights: &[f64]) -> f64 {
+    residuals
+        .iter()
+        .zip(weights)
+        .map(|(r, w)| w * r)
+        .fold(0.0, f64::max)
+}
+
+/// p-norm: r = ( sum_i (w_i r_i)^p )^(1/p). p = 1 is the weighted sum and
+/// large p approaches the hard max.
+pub fn p_norm_combine(residuals: &[f64], weights: &[f64], p: f64) -> f64 {
+    // Scale by the largest term so that large p does not overflow.
+    let scale = hard_max_combine(residuals, weights);
+    if scale <= 0.0 {
+        return 0.0;
+    }
+    let sum: f64 = residuals
+        .iter()
+        .zip(weights)
+        .map(|(r, w)| (w * r / scale).powf(p))
+        .sum();
+    scale * sum.powf(1.0 / p)
+}
+
+fn weighted_exponents(residuals: &[f64], weights: &[f64], tau: f64) -> Vec<f64> {
+    residuals
+        .iter()
+        .zip(weights)
+        .map(|(r, w)| r / tau + w.ln())
+        .collect()
+}
+
+/// Normalise non-negative shares to sum to one. All-zero shares stay zero.
+fn normalise_shares(mut shares: Vec<f64>) -> Vec<f64> {
+    let total: f64 = shares.iter().sum();
+    if total > 0.0 && total.is_finite() {
+        shares.iter_mut().for_each(|s| *s /= total);
+    } else {
+        shares.iter_mut().for_each(|s| *s = 0.0);
+    }
+    shares
+}
+
+/// Channel combiner selected by `[combine]`, with per-channel weights
+/// resolved against the channel names.
+#[derive(Debug, Clone)]
+pub struct Combiner {
+    method: CombineMethod,
+    tau: f64,
+    p: f64,
+    weights: Vec<f64>,
+}
+
+impl Combiner {
+    /// Channels without an entry in `combine.weights` get weight 1.
+    pub fn from_config(cfg: &CombineConfig, channel_names: &[String]) -> Self {
+        Self {
+            method: cfg.method,
+            tau: cfg.tau,
+            p: cfg.p,
+            weights: channel_names
+                .iter()
+                .map(|name| cfg.weights.get(name).copied().unwrap_or(1.0))
+                .collect(),
+        }
+    }
+
+    /// Combined residual.
+    pub fn combine(&self, residuals: &[f64]) -> f64 {
+        match self.method {
+            CombineMethod::SoftMax => soft_max_combine_n(residuals, self.tau),
+            CombineMethod::WeightedSoftMax => {
+                weighted_soft_max_combine(residuals, &self.weights, self.tau)
+            }
+            CombineMethod::WeightedSum => weighted_sum_combine(residuals, &self.weights),
+            CombineMethod::HardMax => hard_max_combine(residuals, &self.weights),
+            CombineMethod::PNorm => p_norm_combine(residuals, &self.weights, self.p),
+        }
+    }
+
+    /// Combined residual and each channel's contribution to it. The
+    /// contributions sum to the combined value:
+    ///   soft-max:     softmax weight of the channel times r
+    ///   weighted sum: w_i r_i
+    ///   hard max:     all of r to the first largest channel
+    ///   p-norm:       share (w_i r_i)^p / sum_j (w_j r_j)^p of r
+    pub fn combine_with_attribution(&self, residuals: &[f64]) -> (f64, Vec<f64>) {
+        let r = self.combine(residuals);
+        let shares = match self.method {
+            CombineMethod::SoftMax | CombineMethod::WeightedSoftMax => {
+                let vals = match self.method {
+                    CombineMethod::SoftMax => residuals.iter().map(|x| x / self.tau).collect(),
+                    _ => weighted_exponents(residuals, &self.weights, self.tau),
+                };
+                let max_val = vals.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                normalise_shares(vals.iter().map(|&x| (x - max_val).exp()).collect())
+            }
+            CombineMethod::WeightedSum => normalise_shares(
+                residuals
+                    .iter()
+                    .zip(&self.weights)
+                    .map(|(x, w)| w * x)
+                    .collect(),
+            ),
+            CombineMethod::HardMax => {
+                let mut shares = vec![0.0; residuals.len()];
+                if let Some(i) = residuals
+                    .iter()
+                    .zip(&self.weights)
+                    .position(|(x, w)| w * x == r)
+                {
+                    shares[i] = 1.0;
+                }
+                shares
+            }
+            CombineMethod::PNorm => normalise_shares(
+                residuals
+                    .iter()
+                    .zip(&self.weights)
+                    .map(|(x, w)| (w * x / r).powf(self.p))
+                    .collect(),
+            ),
+        };
+        (r, shares.into_iter().map(|s| s * r).collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -32,4 +187,55 @@ mod tests {
         let r = soft_max_combine(10.0, 1.0, 0.5, 0.01);
         assert!((r - 10.0).abs() < 0.1);
     }
+
+    fn combiner(method: CombineMethod, weights: &[(&str, f64)]) -> Combiner {
+        let cfg = CombineConfig {
+            tau: 0.35,
+            method,
+            p: 2.0,
+            weights: weights.iter().map(|(k, w)| (k.to_string(), *w)).collect(),
+        };
+        let names = ["vol", "liq", "acc"].map(String::from);
+        Combiner::from_config(&cfg, &names)
+    }
+
+    #[test]
+    fn test_attribution_sums_to_combined() {
+        let residuals = [2.0, 0.5, 0.0];
+        for method in [
+            CombineMethod::SoftMax,
+            CombineMethod::WeightedSoftMax,
+            CombineMethod::WeightedSum,
+            CombineMethod::HardMax,
+            CombineMethod::PNorm,
+        ] {
+            let (r, attr) = combiner(method, &[("liq", 3.0)]).combine_with_attribution(&residuals);
+            let total: f64 = attr.iter().sum();
+            assert!((total - r).abs() < 1e-12, "{:?}", method);
+            assert!(attr[0] >= attr[2], "{:?}", method);
+        }
+    }
+
+    #[test]
+    fn test_soft_max_combiner_matches_function() {
+        let c = combiner(CombineMethod::SoftMax, &[]);
+        let r = c.combine(&[1.0, 2.0, 0.5]);
+        assert_eq!(r.to_bits(), soft_max_combine(1.0, 2.0, 0.5, 0.35).to_bits());
+    }
+
+    #[test]
+    fn test_weighted_combiners() {
+        let weights = [("vol", 2.0), ("acc", 0.0)];
+        let residuals = [1.0, 1.5, 4.0];
+        assert_eq!(
+            combiner(CombineMethod::WeightedSum, &weights).combine(&residuals),
+            3.5
+        );
+        assert_eq!(
+            combiner(CombineMethod::HardMax, &weights).combine(&residuals),
+            2.0
+        );
+        let p2 = combiner(CombineMethod::PNorm, &weights).combine(&residuals);
+        assert!((p2 - (4.0_f64 + 2.25).sqrt()).abs() < 1e-12);
+    }
 }
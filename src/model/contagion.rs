@@ -0,0 +1,117 @@
+use crate::config::{ContagionConfig, ContagionSignal, StateMachineConfig};
+use crate::error::Result;
+use crate::model::state_machine::StateMachine;
+use crate::stats::quantile;
+use crate::types::{EngineOutputRow, RuptureEvent, RuptureState, Timestamp};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// The per-bar fields of one asset's output needed for aggregation.
+#[derive(Debug, Clone)]
+pub struct AssetPoint {
+    pub timestamp: Timestamp,
+    pub rho: f64,
+    /// In Critical or ConfirmedRupture.
+    pub critical: bool,
+    /// Bar is a fresh candidate rupture.
+    pub candidate: bool,
+}
+
+impl From<&EngineOutputRow> for AssetPoint {
+    fn from(row: &EngineOutputRow) -> Self {
+        Self {
+            timestamp: row.timestamp.clone(),
+            rho: row.rho,
+            critical: matches!(
+                row.state,
+                RuptureState::Critical | RuptureState::ConfirmedRupture
+            ),
+            candidate: row.candidate_flag,
+        }
+    }
+}
+
+/// Cross-sectional breadth metrics at one timestamp, plus the systemic
+/// state machine's reading of the aggregate signal.
+#[derive(Debug, Clone, Serialize)]
+pub struct ContagionRow {
+    pub timestamp: Timestamp,
+    /// Number of assets with a bar at this timestamp.
+    pub assets: usize,
+    /// Fraction of those assets in Critical or ConfirmedRupture.
+    pub frac_critical: f64,
+    pub mean_rho: f64,
+    /// The configured quantile of rho across assets.
+    pub quantile_rho: f64,
+    /// Number of assets flagging a candidate rupture at this timestamp.
+    pub candidates: usize,
+    /// Value fed to the systemic state machine.
+    pub signal: f64,
+    pub state: RuptureState,
+    pub state_label: String,
+    pub candidate_flag: bool,
+    pub confirmed_flag: bool,
+}
+
+/// Align several assets' series on timestamp, compute breadth metrics at
+/// each timestamp, and run the selected aggregate signal through its own
+/// state machine to detect systemic ruptures. Timestamps are matched exactly,
+/// so series should use the same timestamp format and time zone.
+pub fn contagion_index(
+    series: &[Vec<AssetPoint>],
+    cfg: &ContagionConfig,
+    default_sm: &StateMachineConfig,
+) -> Result<(Vec<ContagionRow>, Vec<RuptureEvent>)> {
+    let mut aligned: BTreeMap<&Timestamp, Vec<&AssetPoint>> = BTreeMap::new();
+    for points in series {
+        for point in points {
+            aligned.entry(&point.timestamp).or_default().push(point);
+        }
+    }
+
+    let sm_cfg = cfg
+        .state_machine
+        .clone()
+        .unwrap_or_else(|| default_sm.clone());
+    let mut state_machine = StateMachine::new(sm_cfg)?;
+    let mut rows = Vec::new();
+
+    for (timestamp, points) in aligned {
+        let assets = points.len();
+        if assets == 0 || assets < cfg.min_assets {
+            continue;
+        }
+
+        let rhos: Vec<f64> = points.iter().map(|p| p.rho).collect();
+        let critical = points.iter().filter(|p| p.critical).count();
+        let frac_critical = critical as f64 / assets as f64;
+        let mean_rho = rhos.iter().sum::<f64>() / assets as f64;
+        let quantile_rho = quantile(&rhos, cfg.rho_quantile);
+        let candidates = points.iter().filter(|p| p.candidate).count();
+
+        let signal = match cfg.signal {
+            ContagionSignal::MeanRho => mean_rho,
+            ContagionSignal::QuantileRho => quantile_rho,
+            ContagionSignal::Breadth => frac_critical,
+        };
+        let (state, candidate_flag, confirmed_flag) =
+            state_machine.update(rows.len(), timestamp, signal);
+
+        rows.push(ContagionRow {
+            timestamp: timestamp.clone(),
+            assets,
+            frac_critical,
+            mean_rho,
+            quantile_rho,
+            candidates,
+            signal,
+            state,
+            state_label: state_machine.state_name().to_string(),
+            candidate_flag,
+            confirmed_flag,
+        });
+    }
+
+    let events = state_machine.events().to_vec();
+    Ok((rows, events))
+}
@@ -1,66 +1,335 @@
-use crate::config::Config;
-use crate::features::{compute_acceleration, compute_normed, compute_returns};
+use crate::config::{Config, GapPolicy};
+use crate::error::Result;
+use crate::features::FeatureState;
 use crate::model::capacity::CapacityEstimator;
-use crate::model::combine::soft_max_combine;
+use crate::model::combine::Combiner;
 use crate::model::memory::MemoryKernel;
-use crate::model::residuals::compute_residuals;
+use crate::model::residuals::{ChannelInput, ChannelRegistry, ChannelSet};
 use crate::model::state_machine::StateMachine;
-use crate::types::{Bar, EngineOutputRow, RuptureEvent};
+use crate::preprocess::gaps::{fill_between, GapDetector};
+use crate::types::{
+    Bar, ChannelStrain, EngineOutputRow, Residuals, RuptureEpisode, RuptureEvent, StateTransition,
+    Timestamp,
+};
+use serde::{Deserialize, Serialize};
 
-/// Run the full engine pipeline on a sequence of bars and return the per-bar
-/// output rows and the event log.
-pub fn run_engine(bars: &[Bar], config: &Config) -> (Vec<EngineOutputRow>, Vec<RuptureEvent>) {
-    let returns = compute_returns(bars, config.features.use_log_returns);
-    let accel = compute_acceleration(&returns);
-    let normed = compute_normed(
-        bars,
-        &returns,
-        &accel,
-        config.windows.robust_scale_n,
-        config.robust.mad_scale_factor,
-        config.numerics.eps,
-    );
+/// Stateful engine that processes one bar at a time. Every stage of the
+/// pipeline is incremental, and forward-filling of gaps happens here too, so
+/// feeding bars through `update` produces exactly the same rows and events as
+/// `run_engine` over the full slice.
+///
+/// The engine is serialisable; see `model::checkpoint` for persisting it
+/// between runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuptureEngine {
+    config: Config,
+    features: FeatureState,
+    memory: MemoryKernel,
+    /// One kernel per residual channel, fed with the channel's attribution,
+    /// so that the strain can be decomposed by channel.
+    channel_memory: Vec<MemoryKernel>,
+    /// Per-channel strain of each bar since the open candidate, if any.
+    pending: Vec<(usize, Vec<f64>)>,
+    capacity: CapacityEstimator,
+    state_machine: StateMachine,
+    index: usize,
+    /// The most recent bar, which forward-filled bars repeat.
+    last_bar: Option<Bar>,
+    /// The gap detector (when gaps are enabled), residual channels and the
+    /// combiner are rebuilt from the config when restoring a checkpoint.
+    #[serde(skip)]
+    gaps: Option<GapDetector>,
+    #[serde(skip)]
+    channels: Option<ChannelSet>,
+    #[serde(skip)]
+    combiner: Option<Combiner>,
+}
+
+impl RuptureEngine {
+    /// Build an engine with the built-in residual channel kinds.
+    pub fn new(config: &Config) -> Result<Self> {
+        Self::with_registry(config, &ChannelRegistry::builtin())
+    }
+
+    /// Build an engine whose residual channels come from `registry`, so that
+    /// `residuals.channels` may use custom kinds. Fails on a channel kind the
+    /// registry does not know.
+    pub fn with_registry(config: &Config, registry: &ChannelRegistry) -> Result<Self> {
+        let mut engine = Self {
+            config: config.clone(),
+            features: feature_state(config),
+            memory: MemoryKernel::from_config(config.windows.memory_k, &config.memory),
+            channel_memory: vec![
+                MemoryKernel::from_config(config.windows.memory_k, &config.memory);
+                config.residual_channels().len()
+            ],
+            pending: Vec::new(),
+            capacity: CapacityEstimator::new(
+                config.windows.capacity_l,
+                config.capacity.q,
+                config.capacity.smooth,
+                config.capacity.lambda,
+                config.capacity.e_min,
+            ),
+            state_machine: StateMachine::new(config.state_machine.clone())?,
+            index: 0,
+            last_bar: None,
+            gaps: None,
+            channels: None,
+            combiner: None,
+        };
+        engine.build_stages(registry)?;
+        Ok(engine)
+    }
+
+    /// Build the stages that are not part of checkpoints from the config.
+    pub(crate) fn build_stages(&mut self, registry: &ChannelRegistry) -> Result<()> {
+        let config = &self.config;
+        self.gaps = if config.gaps.enabled {
+            Some(GapDetector::from_config(&config.gaps, &config.io)?)
+        } else {
+            None
+        };
+        self.channels = Some(registry.build(&config.residual_channels())?);
+        self.combiner = Some(Combiner::from_config(
+            &config.combine,
+            &config.residual_channel_names(),
+        ));
+        Ok(())
+    }
+
+    /// Names of the residual channels, in the order of `residuals` in each
+    /// output row.
+    pub fn channel_names(&self) -> Vec<String> {
+        self.config.residual_channel_names()
+    }
+
+    /// Process the next bar. Returns an output row for each bar processed,
+    /// paired with the event the bar closed, if any: with `gaps.policy =
+    /// "forward_fill"`, the bars synthesised for a gap before `bar` come
+    /// first, and the row for `bar` itself is always last. A confirmed
+    /// event's `recovery_index` and `duration_bars` are only known later and
+    /// are filled in on the copy held in `events`.
+    pub fn update(&mut self, bar: &Bar) -> Vec<(EngineOutputRow, Option<RuptureEvent>)> {
+        let mut out: Vec<_> = self
+            .filled_before(bar)
+            .iter()
+            .map(|filled| self.step(filled))
+            .collect();
+        out.push(self.step(bar));
+        out
+    }
 
-    let mut memory = MemoryKernel::new(config.windows.memory_k, config.memory.alpha);
-    let mut capacity = CapacityEstimator::new(
-        config.windows.capacity_l,
-        config.capacity.q,
-        config.capacity.smooth,
-        config.capacity.lambda,
-        config.capacity.e_min,
-    );
-    let mut state_machine = StateMachine::new(config.state_machine.clone());
+    /// Synthetic bars for the gap before `bar`, under the forward-fill
+    /// policy.
+    fn filled_before(&self, bar: &Bar) -> Vec<Bar> {
+        match (&self.gaps, &self.last_bar) {
+            (Some(detector), Some(prev)) if self.config.gaps.policy == GapPolicy::ForwardFill => {
+                fill_between(prev, bar, detector)
+            }
+            _ => Vec::new(),
+        }
+    }
 
-    let mut rows = Vec::with_capacity(bars.len());
+    fn step(&mut self, bar: &Bar) -> (EngineOutputRow, Option<RuptureEvent>) {
+        let gap = self.missing_before(&bar.ts);
+        if gap > 0 {
+            match self.config.gaps.policy {
+                GapPolicy::ResetWindows => self.features = feature_state(&self.config),
+                GapPolicy::Decay => {
+                    for _ in 0..gap.min(self.config.windows.memory_k) {
+                        self.memory.push_and_accumulate(0.0);
+                        for kernel in &mut self.channel_memory {
+                            kernel.push_and_accumulate(0.0);
+                        }
+                    }
+                }
+                // Gaps left after forward-filling (skipped sessions of
+                // sub-daily data) are only flagged.
+                GapPolicy::Flag | GapPolicy::ForwardFill => {}
+            }
+        }
 
-    for i in 0..bars.len() {
-        let mut res = compute_residuals(&normed[i], &config.residuals, &config.numerics);
-        res.r = soft_max_combine(res.r_vol, res.r_liq, res.r_acc, config.combine.tau);
+        let normed = self.features.update(bar);
+        let input = ChannelInput {
+            bar,
+            normed: &normed,
+            gap,
+            eps: self.config.numerics.eps,
+        };
+        let channels = self
+            .channels
+            .as_ref()
+            .expect(STAGES_BUILT)
+            .compute(&input, &self.config.numerics);
+        let (r, attribution) = self
+            .combiner
+            .as_ref()
+            .expect(STAGES_BUILT)
+            .combine_with_attribution(&channels);
+        let res = Residuals { channels, r };
+        let cfg = &self.config;
 
-        let strain = memory.push_and_accumulate(res.r);
-        let cap = capacity.update(strain);
-        let rho = strain / (cap + config.numerics.eps);
+        let strain = self.memory.push_and_accumulate(res.r);
+        let channel_strain: Vec<f64> = self
+            .channel_memory
+            .iter_mut()
+            .zip(&attribution)
+            .map(|(kernel, &a)| kernel.push_and_accumulate(a))
+            .collect();
+        let cap = self.capacity.update(strain);
+        let rho = strain / (cap + cfg.numerics.eps);
 
+        let events_before = self.state_machine.events().len();
         let (state, candidate_flag, confirmed_flag) =
-            state_machine.update(i, &bars[i].ts, rho);
-
-        rows.push(EngineOutputRow {
-            timestamp: bars[i].ts.clone(),
-            close: bars[i].close,
-            volume: bars[i].volume,
-            r_vol: res.r_vol,
-            r_liq: res.r_liq,
-            r_acc: res.r_acc,
+            self.state_machine.update(self.index, &bar.ts, rho);
+
+        if candidate_flag {
+            self.pending = vec![(self.index, channel_strain)];
+        } else if !self.pending.is_empty() {
+            self.pending.push((self.index, channel_strain));
+        }
+        if self.state_machine.events().len() > events_before {
+            self.attribute_last_event();
+        }
+        let event = self.state_machine.events()[events_before..].last().cloned();
+
+        self.index += 1;
+        self.last_bar = Some(bar.clone());
+
+        let row = EngineOutputRow {
+            timestamp: bar.ts.clone(),
+            close: bar.close,
+            volume: bar.volume,
+            residuals: res.channels,
             r_combined: res.r,
+            attribution,
             strain,
             capacity: cap,
             rho,
-            state: state.to_string(),
+            state,
+            state_label: self.state_machine.state_name().to_string(),
             candidate_flag,
             confirmed_flag,
-        });
+            gap,
+            filled: bar.filled,
+        };
+        (row, event)
+    }
+
+    /// Fill in the channel breakdown of the event that just closed, from the
+    /// per-channel strain recorded since its candidate bar.
+    fn attribute_last_event(&mut self) {
+        let names = self.channel_names();
+        let pending = std::mem::take(&mut self.pending);
+        let breakdown = |index: usize| -> Vec<ChannelStrain> {
+            let Some((_, strains)) = pending.iter().find(|(i, _)| *i == index) else {
+                return Vec::new();
+            };
+            let total: f64 = strains.iter().sum();
+            names
+                .iter()
+                .zip(strains)
+                .map(|(name, &strain)| ChannelStrain {
+                    channel: name.clone(),
+                    strain,
+                    share: if total > 0.0 { strain / total } else { 0.0 },
+                })
+                .collect()
+        };
+        if let Some(event) = self.state_machine.events_mut().last_mut() {
+            event.candidate_attribution = breakdown(event.candidate_index);
+            event.peak_attribution = breakdown(event.peak_index);
+        }
     }
 
-    let events = state_machine.events().to_vec();
-    (rows, events)
+    /// Number of expected bars missing between the previous bar and `ts`.
+    fn missing_before(&self, ts: &Timestamp) -> usize {
+        match (&self.gaps, &self.last_bar) {
+            (Some(detector), Some(prev)) => detector.missing_between(&prev.ts, ts),
+            _ => 0,
+        }
+    }
+
+    /// Number of bars processed so far.
+    pub fn bars_processed(&self) -> usize {
+        self.index
+    }
+
+    /// Timestamp of the most recently processed bar.
+    pub fn last_timestamp(&self) -> Option<&Timestamp> {
+        self.last_bar.as_ref().map(|bar| &bar.ts)
+    }
+
+    /// Config the engine was built with.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Return the accumulated event log.
+    pub fn events(&self) -> &[RuptureEvent] {
+        self.state_machine.events()
+    }
+
+    /// Episodes of confirmed ruptures so far; the last may still be open.
+    pub fn episodes(&self) -> &[RuptureEpisode] {
+        self.state_machine.episodes()
+    }
+
+    /// The state machine's transition log.
+    pub fn transitions(&self) -> &[StateTransition] {
+        self.state_machine.transitions()
+    }
+}
+
+const STAGES_BUILT: &str =
+    "engine stages are built by RuptureEngine::new and Checkpoint::into_engine";
+
+fn feature_state(config: &Config) -> FeatureState {
+    FeatureState::new(
+        config.windows.robust_scale_n,
+        config.features.use_log_returns,
+        config.robust.mad_scale_factor,
+        config.numerics.eps,
+    )
+    .with_volatility_estimator(
+        config.features.volatility_estimator,
+        !config.features.range_channel,
+    )
+}
+
+/// Run the full engine pipeline on a sequence of bars and return the per-bar
+/// output rows, including any forward-filled bars, and the event log.
+pub fn run_engine(
+    bars: &[Bar],
+    config: &Config,
+) -> Result<(Vec<EngineOutputRow>, Vec<RuptureEvent>)> {
+    let run = run_engine_with_episodes(bars, config)?;
+    Ok((run.rows, run.events))
+}
+
+/// Everything a run produces.
+#[derive(Debug, Clone)]
+pub struct EngineRun {
+    pub rows: Vec<EngineOutputRow>,
+    pub events: Vec<RuptureEvent>,
+    pub episodes: Vec<RuptureEpisode>,
+    pub transitions: Vec<StateTransition>,
+}
+
+/// [`run_engine`], also returning the rupture episodes and the transition
+/// log.
+pub fn run_engine_with_episodes(bars: &[Bar], config: &Config) -> Result<EngineRun> {
+    let mut engine = RuptureEngine::new(config)?;
+    let rows = bars
+        .iter()
+        .flat_map(|bar| engine.update(bar))
+        .map(|(row, _)| row)
+        .collect();
+    Ok(EngineRun {
+        rows,
+        events: engine.events().to_vec(),
+        episodes: engine.episodes().to_vec(),
+        transitions: engine.transitions().to_vec(),
+    })
 }
@@ -1,18 +1,60 @@
+use crate::config::{KernelFamily, MemoryConfig, MemoryMethod};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 /// Power-law (Caputo-like) memory kernel.
 /// Weights are w_k = k^(-alpha) for k = 1..K, normalised so they sum to 1.
 /// The kernel accumulates strain by convolving recent residuals with these weights.
+/// `from_config` builds the other families in `KernelFamily` the same way.
+///
+/// With the sum-of-exponentials method the convolution is replaced by an
+/// O(J) recursion over J exponential terms; see `ExponentialSum`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryKernel {
     weights: Vec<f64>,
     buffer: VecDeque<f64>,
     capacity: usize,
+    #[serde(default)]
+    approx: Option<ExponentialSum>,
+}
+
+/// Sum-of-exponentials approximation of the normalised power-law weights:
+///   w_k ~ sum_j c_j exp(-s_j k)
+/// from trapezoidal quadrature of k^(-alpha) = 1/Gamma(alpha) * int s^(alpha-1) exp(-k s) ds
+/// on a logarithmic grid in s. Each term keeps a running sum over the last K
+/// residuals, updated in O(1) per bar; the residual leaving the window is
+/// subtracted, so the kernel is truncated at K exactly like the exact one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExponentialSum {
+    coeffs: Vec<f64>,
+    decay: Vec<f64>,
+    /// exp(-s_j K): weight of the residual dropping out of the window.
+    tail: Vec<f64>,
+    sums: Vec<f64>,
+    error: KernelError,
+}
+
+/// Error of an approximate kernel against the exact normalised weights.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct KernelError {
+    /// sum_k |w_k - w~_k|. The strain error is at most this times the
+    /// largest residual in the window.
+    pub l1: f64,
+    /// max_k |w_k - w~_k| / w_k.
+    pub max_relative: f64,
 }
 
 impl MemoryKernel {
     /// Create a new memory kernel with the given capacity K and decay exponent alpha.
     pub fn new(k: usize, alpha: f64) -> Self {
         let raw_weights: Vec<f64> = (1..=k).map(|i| (i as f64).powf(-alpha)).collect();
+        Self::from_weights(raw_weights)
+    }
+
+    /// Create a kernel from raw weights, lag 1 first, normalised to sum to 1.
+    /// The capacity K is the number of weights.
+    pub fn from_weights(raw_weights: Vec<f64>) -> Self {
+        let k = raw_weights.len();
         let total: f64 = raw_weights.iter().sum();
         let weights: Vec<f64> = raw_weights.iter().map(|w| w / total).collect();
 
@@ -20,18 +62,67 @@ impl MemoryKernel {
             weights,
             buffer: VecDeque::with_capacity(k),
             capacity: k,
+            approx: None,
+        }
+    }
+
+    /// Create a kernel of length K with the family and method selected in
+    /// `[memory]`. `File` weights must already hold K entries (see
+    /// `Config::validate`).
+    pub fn from_config(k: usize, cfg: &MemoryConfig) -> Self {
+        let alpha = cfg.alpha;
+        let lambda = cfg.lambda;
+        let raw_weights: Vec<f64> = match cfg.kernel {
+            KernelFamily::PowerLaw => return Self::new(k, alpha).with_method(cfg, 0.0),
+            KernelFamily::Exponential => (0..k).map(|i| (-lambda * i as f64).exp()).collect(),
+            KernelFamily::Fractional => {
+                let mut w = 1.0;
+                (1..=k)
+                    .map(|i| {
+                        let current = w;
+                        w *= (i as f64 - alpha) / i as f64;
+                        current
+                    })
+                    .collect()
+            }
+            KernelFamily::TemperedPowerLaw => (1..=k)
+                .map(|i| (i as f64).powf(-alpha) * (-lambda * i as f64).exp())
+                .collect(),
+            KernelFamily::File => cfg.weights.iter().copied().take(k).collect(),
+        };
+        Self::from_weights(raw_weights).with_method(cfg, lambda)
+    }
+
+    /// Attach the sum-of-exponentials approximation if `[memory]` selects it.
+    /// Tempering by exp(-lambda k) shifts every exponential rate by lambda.
+    fn with_method(mut self, cfg: &MemoryConfig, lambda: f64) -> Self {
+        if cfg.method == MemoryMethod::SumOfExponentials {
+            let approx = ExponentialSum::new(&self.weights, cfg.alpha, lambda, cfg.terms);
+            self.approx = Some(approx);
         }
+        self
+    }
+
+    /// Approximation error versus the exact weights, for approximate kernels.
+    pub fn approximation_error(&self) -> Option<KernelError> {
+        self.approx.as_ref().map(|a| a.error)
     }
 
     /// Push a new residual value and compute the accumulated strain.
     /// Strain is the dot product of the weight vector with the buffer contents,
     /// where the most recent value receives weight w_1 (the largest weight).
     pub fn push_and_accumulate(&mut self, value: f64) -> f64 {
-        if self.buffer.len() == self.capacity {
-            self.buffer.pop_front();
-        }
+        let evicted = if self.buffer.len() == self.capacity {
+            self.buffer.pop_front()
+        } else {
+            None
+        };
         self.buffer.push_back(value);
 
+        if let Some(approx) = &mut self.approx {
+            return approx.push(value, evicted);
+        }
+
         let _n = self.buffer.len();
         let mut strain = 0.0;
         for (i, &val) in self.buffer.iter().rev().enumerate() {
@@ -48,6 +139,78 @@ impl MemoryKernel {
     }
 }
 
+impl ExponentialSum {
+    /// Fit `terms` exponentials to the normalised `exact` weights of a
+    /// power-law kernel with exponent `alpha`, tempered by exp(-lambda k).
+    fn new(exact: &[f64], alpha: f64, lambda: f64, terms: usize) -> Self {
+        let k = exact.len().max(1) as f64;
+        let terms = terms.max(2);
+        // Nodes s = exp(x). Below x_lo the integrand contributes about 1e-6
+        // relative to K^(-alpha); above x_hi, exp(-s) is negligible.
+        let x_lo = (1e-6_f64).ln() / alpha - k.ln();
+        let x_hi = 40.0_f64.ln();
+        let h = (x_hi - x_lo) / (terms - 1) as f64;
+
+        let nodes: Vec<f64> = (0..terms).map(|j| (x_lo + h * j as f64).exp()).collect();
+        // Trapezoid weights; the constant h / Gamma(alpha) cancels on normalising.
+        let mut coeffs: Vec<f64> = nodes
+            .iter()
+            .enumerate()
+            .map(|(j, s)| {
+                let end = if j == 0 || j == terms - 1 { 0.5 } else { 1.0 };
+                end * s.powf(alpha)
+            })
+            .collect();
+        let rates: Vec<f64> = nodes.iter().map(|s| s + lambda).collect();
+
+        let approx_weight = |coeffs: &[f64], lag: f64| -> f64 {
+            coeffs
+                .iter()
+                .zip(&rates)
+                .map(|(c, s)| c * (-s * lag).exp())
+                .sum()
+        };
+        let total: f64 = (1..=exact.len())
+            .map(|i| approx_weight(&coeffs, i as f64))
+            .sum();
+        coeffs.iter_mut().for_each(|c| *c /= total);
+
+        let mut error = KernelError {
+            l1: 0.0,
+            max_relative: 0.0,
+        };
+        for (i, w) in exact.iter().enumerate() {
+            let diff = (approx_weight(&coeffs, (i + 1) as f64) - w).abs();
+            error.l1 += diff;
+            error.max_relative = error.max_relative.max(diff / w);
+        }
+
+        Self {
+            // Fold the lag-1 factor into the coefficients: the newest residual
+            // has lag 1 and the running sums start at lag 0.
+            coeffs: coeffs
+                .iter()
+                .zip(&rates)
+                .map(|(c, s)| c * (-s).exp())
+                .collect(),
+            decay: rates.iter().map(|s| (-s).exp()).collect(),
+            tail: rates.iter().map(|s| (-s * k).exp()).collect(),
+            sums: vec![0.0; terms],
+            error,
+        }
+    }
+
+    fn push(&mut self, value: f64, evicted: Option<f64>) -> f64 {
+        let old = evicted.unwrap_or(0.0);
+        let mut strain = 0.0;
+        for j in 0..self.sums.len() {
+            self.sums[j] = self.decay[j] * self.sums[j] + value - self.tail[j] * old;
+            strain += self.coeffs[j] * self.sums[j];
+        }
+        strain
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,8 +1,13 @@
 pub mod capacity;
+pub mod checkpoint;
 pub mod combine;
+pub mod contagion;
 pub mod engine;
 pub mod memory;
 pub mod residuals;
+pub mod state_graph;
 pub mod state_machine;
 
-pub use engine::run_engine;
+pub use checkpoint::Checkpoint;
+pub use contagion::{contagion_index, AssetPoint, ContagionRow};
+pub use engine::{run_engine, run_engine_with_episodes, EngineRun, RuptureEngine};
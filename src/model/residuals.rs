@@ -1,31 +1,177 @@
-use crate::config::{NumericsConfig, ResidualsConfig};
+use crate::config::{ChannelConfig, Config, NumericsConfig};
+use crate::error::{Result, RuptureError};
 use crate::stats::robust::clip;
-use crate::types::{Normed, Residuals};
-
-/// Compute the three residual channels from normalised features.
-///   r_vol = max(0, u - theta_vol)
-///   r_liq = max(0, u / (v + eps) - theta_liq)
-///   r_acc = max(0, a - theta_acc)
-/// Residuals are optionally clipped to prevent outlier domination.
-pub fn compute_residuals(
-    normed: &Normed,
-    residuals_cfg: &ResidualsConfig,
-    numerics_cfg: &NumericsConfig,
-) -> Residuals {
-    let mut r_vol = (normed.u - residuals_cfg.theta_vol).max(0.0);
-    let mut r_liq = (normed.u / (normed.v + numerics_cfg.eps) - residuals_cfg.theta_liq).max(0.0);
-    let mut r_acc = (normed.a - residuals_cfg.theta_acc).max(0.0);
-
-    if numerics_cfg.clip_residuals {
-        r_vol = clip(r_vol, numerics_cfg.residual_clip_max);
-        r_liq = clip(r_liq, numerics_cfg.residual_clip_max);
-        r_acc = clip(r_acc, numerics_cfg.residual_clip_max);
-    }
-
-    Residuals {
-        r_vol,
-        r_liq,
-        r_acc,
-        r: 0.0, // combined is set later by the combiner
+use crate::types::{Bar, Normed};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Everything a channel may read for one bar.
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelInput<'a> {
+    pub bar: &'a Bar,
+    pub normed: &'a Normed,
+    /// Expected bars missing before this bar (0 unless `[gaps]` is enabled).
+    pub gap: usize,
+    pub eps: f64,
+}
+
+/// A residual channel: a dimensionless activity measure and the threshold
+/// above which it counts as excess. The residual is
+///   r = max(0, activity - threshold)
+/// optionally clipped by `numerics.residual_clip_max`.
+pub trait ResidualChannel: fmt::Debug + Send + Sync {
+    /// Channel name; the time series CSV column is `r_<name>`.
+    fn name(&self) -> &str;
+
+    fn threshold(&self) -> f64;
+
+    fn activity(&self, input: &ChannelInput) -> f64;
+
+    fn residual(&self, input: &ChannelInput, numerics: &NumericsConfig) -> f64 {
+        let r = (self.activity(input) - self.threshold()).max(0.0);
+        if numerics.clip_residuals {
+            clip(r, numerics.residual_clip_max)
+        } else {
+            r
+        }
+    }
+}
+
+/// A built-in channel reading one activity measure.
+#[derive(Debug, Clone)]
+struct BuiltinChannel {
+    name: String,
+    theta: f64,
+    activity: ActivityFn,
+}
+
+type ActivityFn = fn(&ChannelInput) -> f64;
+
+impl ResidualChannel for BuiltinChannel {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn threshold(&self) -> f64 {
+        self.theta
+    }
+
+    fn activity(&self, input: &ChannelInput) -> f64 {
+        (self.activity)(input)
+    }
+}
+
+/// Built-in channel kinds and their activity measures.
+const BUILTIN_KINDS: [(&str, ActivityFn); 5] = [
+    // u
+    ("vol", |i| i.normed.u),
+    // u / (v + eps)
+    ("liq", |i| i.normed.u / (i.normed.v + i.eps)),
+    // a
+    ("acc", |i| i.normed.a),
+    // g, the normalised range volatility
+    ("rng", |i| i.normed.g),
+    // number of expected bars missing before this bar
+    ("gap", |i| i.gap as f64),
+];
+
+type ChannelFactory = Arc<dyn Fn(&ChannelConfig) -> Arc<dyn ResidualChannel> + Send + Sync>;
+
+/// Maps channel kinds to constructors. `builtin()` knows `vol`, `liq`,
+/// `acc`, `rng` and `gap`; library users can `register` further kinds and
+/// build engines with `RuptureEngine::with_registry`. `Config::validate`
+/// only warns about kinds that are not built in; building the engine fails
+/// on a kind its registry does not know.
+#[derive(Clone)]
+pub struct ChannelRegistry {
+    factories: BTreeMap<String, ChannelFactory>,
+}
+
+impl fmt::Debug for ChannelRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.factories.keys()).finish()
+    }
+}
+
+impl ChannelRegistry {
+    pub fn builtin() -> Self {
+        let mut registry = Self {
+            factories: BTreeMap::new(),
+        };
+        for (kind, activity) in BUILTIN_KINDS {
+            registry.register(kind, move |cfg: &ChannelConfig| {
+                Arc::new(BuiltinChannel {
+                    name: cfg.name().to_string(),
+                    theta: cfg.theta,
+                    activity,
+                }) as Arc<dyn ResidualChannel>
+            });
+        }
+        registry
+    }
+
+    /// Register a channel kind, replacing any existing one of that name.
+    pub fn register<F>(&mut self, kind: &str, factory: F)
+    where
+        F: Fn(&ChannelConfig) -> Arc<dyn ResidualChannel> + Send + Sync + 'static,
+    {
+        self.factories.insert(kind.to_string(), Arc::new(factory));
+    }
+
+    pub fn contains(&self, kind: &str) -> bool {
+        self.factories.contains_key(kind)
+    }
+
+    /// Build the channels listed in `configs`, in order.
+    pub fn build(&self, configs: &[ChannelConfig]) -> Result<ChannelSet> {
+        let channels = configs
+            .iter()
+            .map(|cfg| {
+                self.factories
+                    .get(&cfg.kind)
+                    .map(|factory| factory(cfg))
+                    .ok_or_else(|| {
+                        RuptureError::Config(format!(
+                            "residuals.channels: unknown channel kind '{}'",
+                            cfg.kind
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ChannelSet { channels })
+    }
+}
+
+/// The ordered residual channels of an engine.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelSet {
+    channels: Vec<Arc<dyn ResidualChannel>>,
+}
+
+impl ChannelSet {
+    /// Build the channels configured in `config` from the built-in registry.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        ChannelRegistry::builtin().build(&config.residual_channels())
+    }
+
+    pub fn len(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.channels.iter().map(|c| c.name().to_string()).collect()
+    }
+
+    /// Compute every channel's residual for one bar.
+    pub fn compute(&self, input: &ChannelInput, numerics: &NumericsConfig) -> Vec<f64> {
+        self.channels
+            .iter()
+            .map(|c| c.residual(input, numerics))
+            .collect()
     }
 }
@@ -0,0 +1,372 @@
+use crate::config::{StateConfig, StateMachineConfig, TransitionConfig, TransitionGuard};
+use crate::error::{Result, RuptureError};
+use crate::types::RuptureState;
+use serde::{Deserialize, Serialize};
+
+/// A state machine definition resolved from `[state_machine]`: states by
+/// index, transitions between them, and the rho levels.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateGraph {
+    states: Vec<StateNode>,
+    transitions: Vec<Transition>,
+    /// Level states, lowest first.
+    levels: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateNode {
+    pub name: String,
+    pub kind: RuptureState,
+    /// Levels only; `None` for the lowest level.
+    pub enter_rho: Option<f64>,
+    pub exit_rho: Option<f64>,
+    pub min_dwell_bars: usize,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Transition {
+    pub from: usize,
+    /// `None`: the level matching rho.
+    pub to: Option<usize>,
+    pub guard: Guard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Guard {
+    Crossing { rho: f64 },
+    Confirmed,
+    Rejected,
+    Below { rho: f64, bars: usize },
+}
+
+impl Guard {
+    pub fn on(&self) -> TransitionGuard {
+        match self {
+            Guard::Crossing { .. } => TransitionGuard::Crossing,
+            Guard::Confirmed => TransitionGuard::Confirmed,
+            Guard::Rejected => TransitionGuard::Rejected,
+            Guard::Below { .. } => TransitionGuard::Below,
+        }
+    }
+}
+
+impl StateGraph {
+    /// Resolve and check the states and transitions of `cfg`.
+    pub fn from_config(cfg: &StateMachineConfig) -> Result<Self> {
+        let (graph, problems) = Self::build(cfg);
+        if problems.is_empty() {
+            Ok(graph)
+        } else {
+            Err(RuptureError::Config(problems.join("; ")))
+        }
+    }
+
+    /// Every problem with the definition in `cfg`: unknown or duplicate
+    /// names, misordered levels, transitions a state cannot take, states
+    /// that cannot be reached and states with no way back to a level.
+    pub fn problems(cfg: &StateMachineConfig) -> Vec<String> {
+        Self::build(cfg).1
+    }
+
+    pub fn states(&self) -> &[StateNode] {
+        &self.states
+    }
+
+    pub fn transitions(&self) -> &[Transition] {
+        &self.transitions
+    }
+
+    /// The lowest level, where the machine starts.
+    pub fn initial(&self) -> usize {
+        self.levels.first().copied().unwrap_or(0)
+    }
+
+    /// The transition out of `from` taken `on` the given guard, if any.
+    pub fn transition(&self, from: usize, on: TransitionGuard) -> Option<&Transition> {
+        self.transitions
+            .iter()
+            .find(|t| t.from == from && t.guard.on() == on)
+    }
+
+    /// The level for `rho`. A level is entered at its `enter_rho` and, from
+    /// `current` (a candidate counting as the highest level), left only
+    /// below its `exit_rho`.
+    pub fn level_for(&self, rho: f64, current: usize) -> usize {
+        let held = match self.states[current].kind {
+            RuptureState::CandidateRupture => self.levels.len().checked_sub(1),
+            _ => self.levels.iter().position(|&l| l == current),
+        };
+        for (pos, &level) in self.levels.iter().enumerate().rev() {
+            let node = &self.states[level];
+            let Some(enter) = node.enter_rho else {
+                return level;
+            };
+            let exit = node.exit_rho.unwrap_or(enter);
+            if rho >= enter || (held.is_some_and(|held| pos <= held) && rho >= exit) {
+                return level;
+            }
+        }
+        self.initial()
+    }
+
+    fn build(cfg: &StateMachineConfig) -> (Self, Vec<String>) {
+        let mut problems = Vec::new();
+        if cfg.states.is_empty() && !cfg.transitions.is_empty() {
+            problems.push("transitions need declared states".to_string());
+        }
+        let states: Vec<StateNode> = cfg
+            .state_configs()
+            .iter()
+            .map(|s| node(s, cfg, &mut problems))
+            .collect();
+        for (i, state) in states.iter().enumerate() {
+            if state.name.is_empty() {
+                problems.push("state names must not be empty".into());
+            } else if states[..i].iter().any(|s| s.name == state.name) {
+                problems.push(format!("duplicate state '{}'", state.name));
+            }
+        }
+
+        let levels: Vec<usize> = (0..states.len())
+            .filter(|&i| states[i].kind.is_level())
+            .collect();
+        check_levels(&states, &levels, &mut problems);
+        for (kind, name) in [
+            (RuptureState::CandidateRupture, "candidate"),
+            (RuptureState::ConfirmedRupture, "confirmed"),
+        ] {
+            if states.iter().filter(|s| s.kind == kind).count() != 1 {
+                problems.push(format!("exactly one {} state is required", name));
+            }
+        }
+        let recovery = RuptureState::Recovery;
+        if states.iter().filter(|s| s.kind == recovery).count() > 1 {
+            problems.push("at most one recovery state is allowed".into());
+        }
+
+        let transitions: Vec<Transition> = cfg
+            .transition_configs()
+            .iter()
+            .filter_map(|t| transition(t, &states, cfg, &mut problems))
+            .collect();
+        let graph = Self {
+            states,
+            transitions,
+            levels,
+        };
+        graph.check_transitions(&mut problems);
+        if problems.is_empty() {
+            graph.check_reachability(&mut problems);
+        }
+        (graph, problems)
+    }
+
+    fn check_transitions(&self, problems: &mut Vec<String>) {
+        for (i, t) in self.transitions.iter().enumerate() {
+            let duplicate = self.transitions[..i]
+                .iter()
+                .any(|u| u.from == t.from && u.guard.on() == t.guard.on());
+            if duplicate {
+                problems.push(format!(
+                    "'{}' has more than one {} transition",
+                    self.states[t.from].name,
+                    guard_name(t.guard.on())
+                ));
+            }
+        }
+        for (i, state) in self.states.iter().enumerate() {
+            if state.kind == RuptureState::CandidateRupture
+                && (self.transition(i, TransitionGuard::Confirmed).is_none()
+                    || self.transition(i, TransitionGuard::Rejected).is_none())
+            {
+                problems.push(format!(
+                    "'{}' needs a confirmed and a rejected transition",
+                    state.name
+                ));
+            }
+        }
+    }
+
+    /// Every state is reachable from the initial level, and every state can
+    /// get back to a level.
+    fn check_reachability(&self, problems: &mut Vec<String>) {
+        let n = self.states.len();
+        // Levels move between each other with rho.
+        let mut edges: Vec<(usize, usize)> = Vec::new();
+        for &a in &self.levels {
+            edges.extend(self.levels.iter().map(|&b| (a, b)));
+        }
+        for t in &self.transitions {
+            match t.to {
+                Some(to) => edges.push((t.from, to)),
+                None => edges.extend(self.levels.iter().map(|&l| (t.from, l))),
+            }
+        }
+
+        let reached = closure(n, &[self.initial()], &edges);
+        let reversed: Vec<(usize, usize)> = edges.iter().map(|&(a, b)| (b, a)).collect();
+        let returns = closure(n, &self.levels, &reversed);
+        for (i, state) in self.states.iter().enumerate() {
+            if !reached[i] {
+                problems.push(format!("'{}' is unreachable", state.name));
+            } else if !returns[i] {
+                problems.push(format!("'{}' has no way back to a level state", state.name));
+            }
+        }
+    }
+}
+
+fn node(state: &StateConfig, cfg: &StateMachineConfig, problems: &mut Vec<String>) -> StateNode {
+    let level_only =
+        state.enter_rho.is_some() || state.exit_rho.is_some() || state.min_dwell_bars.is_some();
+    if !state.kind.is_level() && level_only {
+        problems.push(format!(
+            "'{}' is not a level; enter_rho, exit_rho and min_dwell_bars apply only to levels",
+            state.name
+        ));
+    }
+    StateNode {
+        name: state.name.clone(),
+        kind: state.kind,
+        enter_rho: state.enter_rho,
+        exit_rho: state.exit_rho,
+        min_dwell_bars: state.min_dwell_bars.unwrap_or(cfg.min_dwell_bars),
+    }
+}
+
+fn check_levels(states: &[StateNode], levels: &[usize], problems: &mut Vec<String>) {
+    let Some((&lowest, rest)) = levels.split_first() else {
+        problems.push("at least one level state is required".into());
+        return;
+    };
+    let lowest = &states[lowest];
+    if lowest.enter_rho.is_some() || lowest.exit_rho.is_some() {
+        problems.push(format!(
+            "'{}' is the lowest level and takes no enter_rho or exit_rho",
+            lowest.name
+        ));
+    }
+    let mut previous = f64::NEG_INFINITY;
+    for &level in rest {
+        let state = &states[level];
+        let Some(enter) = state.enter_rho.filter(|e| e.is_finite()) else {
+            problems.push(format!("level '{}' needs a finite enter_rho", state.name));
+            continue;
+        };
+        if enter <= previous {
+            problems.push(format!(
+                "level '{}' must have a higher enter_rho than the level before it",
+                state.name
+            ));
+        }
+        previous = enter;
+        if state
+            .exit_rho
+            .is_some_and(|exit| !exit.is_finite() || exit > enter)
+        {
+            problems.push(format!(
+                "level '{}': exit_rho must be finite and at most enter_rho",
+                state.name
+            ));
+        }
+    }
+}
+
+fn transition(
+    t: &TransitionConfig,
+    states: &[StateNode],
+    cfg: &StateMachineConfig,
+    problems: &mut Vec<String>,
+) -> Option<Transition> {
+    let mut find = |name: &str| {
+        let index = states.iter().position(|s| s.name == name);
+        if index.is_none() {
+            problems.push(format!("transition from '{}': no state '{}'", t.from, name));
+        }
+        index
+    };
+    let from = find(&t.from);
+    let to = match &t.to {
+        Some(name) => Some(find(name)?),
+        None => None,
+    };
+    let from = from?;
+    let from_kind = states[from].kind;
+    let to_kind = to.map(|to| states[to].kind);
+    let to_level = to_kind.is_none_or(RuptureState::is_level);
+
+    let rho = t.rho.unwrap_or(match t.on {
+        TransitionGuard::Crossing => cfg.rho_rupture,
+        _ => cfg.rho_critical,
+    });
+    let (allowed, guard) = match t.on {
+        TransitionGuard::Crossing => (
+            from_kind != RuptureState::CandidateRupture
+                && to_kind == Some(RuptureState::CandidateRupture),
+            Guard::Crossing { rho },
+        ),
+        TransitionGuard::Confirmed => (
+            from_kind == RuptureState::CandidateRupture
+                && to_kind == Some(RuptureState::ConfirmedRupture),
+            Guard::Confirmed,
+        ),
+        TransitionGuard::Rejected => (
+            from_kind == RuptureState::CandidateRupture && to_level,
+            Guard::Rejected,
+        ),
+        TransitionGuard::Below => (
+            match from_kind {
+                RuptureState::ConfirmedRupture => {
+                    to_level || to_kind == Some(RuptureState::Recovery)
+                }
+                RuptureState::Recovery => to_level,
+                _ => false,
+            },
+            Guard::Below {
+                rho,
+                bars: t.bars.unwrap_or(1),
+            },
+        ),
+    };
+    let target = t.to.as_deref().unwrap_or("the level matching rho");
+    if !allowed {
+        problems.push(format!(
+            "'{}' cannot take a {} transition to {}",
+            t.from,
+            guard_name(t.on),
+            target
+        ));
+    }
+    if !rho.is_finite() {
+        problems.push(format!("transition from '{}': rho must be finite", t.from));
+    }
+    if matches!(guard, Guard::Below { bars: 0, .. }) {
+        problems.push(format!(
+            "transition from '{}': bars must be at least 1",
+            t.from
+        ));
+    }
+    Some(Transition { from, to, guard })
+}
+
+/// States reachable from `start` along `edges`.
+fn closure(n: usize, start: &[usize], edges: &[(usize, usize)]) -> Vec<bool> {
+    let mut seen = vec![false; n];
+    let mut stack = start.to_vec();
+    while let Some(state) = stack.pop() {
+        if std::mem::replace(&mut seen[state], true) {
+            continue;
+        }
+        stack.extend(edges.iter().filter(|e| e.0 == state).map(|e| e.1));
+    }
+    seen
+}
+
+fn guard_name(on: TransitionGuard) -> &'static str {
+    match on {
+        TransitionGuard::Crossing => "crossing",
+        TransitionGuard::Confirmed => "confirmed",
+        TransitionGuard::Rejected => "rejected",
+        TransitionGuard::Below => "below",
+    }
+}
@@ -1,154 +1,433 @@
-use crate::config::StateMachineConfig;
-use crate::types::{RuptureEvent, RuptureState};
+use crate::config::{ConfirmMode, StateMachineConfig, TransitionGuard};
+use crate::error::Result;
+use crate::model::state_graph::{Guard, StateGraph, Transition};
+use crate::types::{
+    EpisodeEnd, RuptureEpisode, RuptureEvent, RuptureState, StateTransition, Timestamp,
+    TransitionRule,
+};
+use serde::{Deserialize, Serialize};
 
 /// Deterministic state machine for rupture detection.
-/// Transitions are driven by rho = S / (E + eps).
-/// Candidate ruptures are detected at the first crossing of rho >= 1.0,
-/// and confirmed using an m-of-k rule over subsequent bars.
+/// Transitions are driven by rho = S / (E + eps) and follow the
+/// [`StateGraph`] defined by the config. Candidate ruptures are detected at
+/// a fresh crossing of the rupture threshold, and confirmed using an m-of-k
+/// rule over subsequent bars, or by time spent above the threshold within a
+/// window. A confirmed rupture opens an episode that lasts through Recovery.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StateMachine {
     cfg: StateMachineConfig,
-    state: RuptureState,
+    graph: StateGraph,
+    /// Index of the current state in `graph`.
+    state: usize,
+    /// Consecutive bars in `state`, including the current one.
+    state_bars: usize,
     prev_rho: f64,
 
     // Candidate tracking.
     candidate_index: Option<usize>,
-    candidate_timestamp: Option<String>,
+    candidate_timestamp: Option<Timestamp>,
+    /// Threshold of the crossing that opened the candidate.
+    candidate_rho: f64,
     candidate_peak_rho: f64,
+    candidate_peak_index: usize,
+    candidate_peak_timestamp: Option<Timestamp>,
     confirm_buffer: Vec<bool>,
+    // Time confirmation: the previous bar's timestamp, and how long rho has
+    // been at or above threshold since the crossing.
+    last_timestamp: Option<Timestamp>,
+    time_above_ms: i64,
 
-    // Recovery tracking.
+    // Bars counting towards a `below` transition.
     recovery_count: usize,
 
-    // Event log.
+    // Event log, and episodes of confirmed ruptures; the last episode is
+    // open while the machine is in ConfirmedRupture or Recovery.
     events: Vec<RuptureEvent>,
+    episodes: Vec<RuptureEpisode>,
+    // Every change of state.
+    transitions: Vec<StateTransition>,
 }
 
 impl StateMachine {
-    pub fn new(cfg: StateMachineConfig) -> Self {
-        Self {
+    /// Build the machine, failing if the state machine definition is
+    /// invalid (see [`StateGraph::problems`]).
+    pub fn new(cfg: StateMachineConfig) -> Result<Self> {
+        let graph = StateGraph::from_config(&cfg)?;
+        Ok(Self {
             cfg,
-            state: RuptureState::Stable,
+            state: graph.initial(),
+            graph,
+            state_bars: 0,
             prev_rho: 0.0,
             candidate_index: None,
             candidate_timestamp: None,
+            candidate_rho: 0.0,
             candidate_peak_rho: 0.0,
+            candidate_peak_index: 0,
+            candidate_peak_timestamp: None,
             confirm_buffer: Vec::new(),
+            last_timestamp: None,
+            time_above_ms: 0,
             recovery_count: 0,
             events: Vec::new(),
-        }
+            episodes: Vec::new(),
+            transitions: Vec::new(),
+        })
     }
 
-    /// Update the state machine with a new rho value. Returns the current state
-    /// and flags indicating whether this bar is a candidate or confirmed rupture.
+    /// Update the state machine with a new rho value. Returns the kind of the
+    /// current state and flags indicating whether this bar is a candidate or
+    /// confirmed rupture.
     pub fn update(
         &mut self,
         index: usize,
-        timestamp: &str,
+        timestamp: impl Into<Timestamp>,
         rho: f64,
     ) -> (RuptureState, bool, bool) {
+        let timestamp = timestamp.into();
+        let previous_state = self.state;
         let mut candidate_flag = false;
         let mut confirmed_flag = false;
+        let mut rule = TransitionRule::Level;
 
-        match self.state {
-            RuptureState::Stable | RuptureState::Stressed | RuptureState::Critical => {
-                if rho >= self.cfg.rho_rupture && self.prev_rho < self.cfg.rho_rupture {
-                    // First crossing: enter candidate state.
-                    self.state = RuptureState::CandidateRupture;
-                    self.candidate_index = Some(index);
-                    self.candidate_timestamp = Some(timestamp.to_string());
-                    self.candidate_peak_rho = rho;
-                    self.confirm_buffer.clear();
-                    self.confirm_buffer.push(true); // The crossing bar itself counts.
-                    candidate_flag = true;
-                } else {
-                    // Normal state assignment by rho level.
-                    self.state = base_state(rho, &self.cfg);
-                }
+        if let Some((to, threshold)) = self.crossing(rho) {
+            rule = TransitionRule::Crossing;
+            if self.in_rupture() {
+                // A new rupture cuts the recovery short.
+                self.close_episode(index, &timestamp, EpisodeEnd::NewRupture);
             }
-            RuptureState::CandidateRupture => {
-                // We are in the confirmation window.
-                self.confirm_buffer
-                    .push(rho >= self.cfg.rho_rupture);
-                if rho > self.candidate_peak_rho {
-                    self.candidate_peak_rho = rho;
+            self.start_candidate(to, index, &timestamp, rho, threshold);
+            candidate_flag = true;
+        } else {
+            match self.kind() {
+                RuptureState::Stable | RuptureState::Stressed | RuptureState::Critical => {
+                    // Normal state assignment by rho level.
+                    let target = self.graph.level_for(rho, self.state);
+                    self.state = self.dwell(target);
                 }
+                RuptureState::CandidateRupture => {
+                    // We are in the confirmation window.
+                    let above = rho >= self.candidate_rho;
+                    if rho > self.candidate_peak_rho {
+                        self.candidate_peak_rho = rho;
+                        self.candidate_peak_index = index;
+                        self.candidate_peak_timestamp = Some(timestamp.clone());
+                    }
+                    let decision = match self.cfg.confirm_mode {
+                        ConfirmMode::Bars => self.bars_decision(above),
+                        ConfirmMode::Time => self.time_decision(&timestamp, above),
+                    };
 
-                if self.confirm_buffer.len() >= self.cfg.confirm_k {
-                    let above_count = self.confirm_buffer.iter().filter(|&&b| b).count();
-                    if above_count >= self.cfg.confirm_m {
-                        // Confirmed rupture.
-                        self.state = RuptureState::ConfirmedRupture;
-                        confirmed_flag = true;
-                        self.events.push(RuptureEvent {
-                            candidate_index: self.candidate_index.unwrap_or(index),
-                            candidate_timestamp: self
-                                .candidate_timestamp
-                                .clone()
-                                .unwrap_or_default(),
-                            confirmed_index: Some(index),
-                            confirmed_timestamp: Some(timestamp.to_string()),
-                            peak_rho: self.candidate_peak_rho,
-                            confirmation_k: self.cfg.confirm_k,
-                            confirmation_m: self.cfg.confirm_m,
-                        });
-                        self.recovery_count = 0;
-                    } else {
-                        // Failed confirmation: revert to base state.
-                        self.events.push(RuptureEvent {
-                            candidate_index: self.candidate_index.unwrap_or(index),
-                            candidate_timestamp: self
-                                .candidate_timestamp
-                                .clone()
-                                .unwrap_or_default(),
-                            confirmed_index: None,
-                            confirmed_timestamp: None,
-                            peak_rho: self.candidate_peak_rho,
-                            confirmation_k: self.cfg.confirm_k,
-                            confirmation_m: self.cfg.confirm_m,
-                        });
-                        self.state = base_state(rho, &self.cfg);
+                    if let Some(confirmed) = decision {
+                        let event = self.candidate_event(index, &timestamp, confirmed);
+                        self.events.push(event);
+                        if confirmed {
+                            // Confirmed rupture.
+                            confirmed_flag = true;
+                            rule = TransitionRule::Confirmed;
+                            self.state = self.target(TransitionGuard::Confirmed, rho);
+                            self.open_episode(index, &timestamp);
+                            self.recovery_count = 0;
+                        } else {
+                            // Failed confirmation: revert to a level.
+                            rule = TransitionRule::Rejected;
+                            self.state = self.target(TransitionGuard::Rejected, rho);
+                        }
+                        self.candidate_index = None;
+                        self.candidate_timestamp = None;
+                        self.confirm_buffer.clear();
+                        self.last_timestamp = None;
                     }
-                    self.candidate_index = None;
-                    self.candidate_timestamp = None;
-                    self.confirm_buffer.clear();
                 }
-            }
-            RuptureState::ConfirmedRupture => {
-                if self.cfg.enable_recovery && rho < self.cfg.rho_critical {
-                    self.recovery_count += 1;
-                    if self.recovery_count >= self.cfg.recovery_hold {
-                        self.state = RuptureState::Recovery;
-                        self.recovery_count = 0;
-                    }
-                } else {
-                    self.recovery_count = 0;
+                RuptureState::ConfirmedRupture | RuptureState::Recovery => {
+                    self.track_episode_peak(index, &timestamp, rho);
+                    rule = TransitionRule::Below;
+                    self.count_below(index, &timestamp, rho);
                 }
             }
-            RuptureState::Recovery => {
-                // Transition back to base state.
-                self.state = base_state(rho, &self.cfg);
-            }
         }
 
         self.prev_rho = rho;
-        (self.state, candidate_flag, confirmed_flag)
+        self.state_bars = if self.state == previous_state {
+            self.state_bars + 1
+        } else {
+            let states = self.graph.states();
+            self.transitions.push(StateTransition {
+                index,
+                timestamp,
+                from: states[previous_state].name.clone(),
+                to: states[self.state].name.clone(),
+                rho,
+                rule,
+            });
+            1
+        };
+        (self.kind(), candidate_flag, confirmed_flag)
+    }
+
+    /// Every change of state so far.
+    pub fn transitions(&self) -> &[StateTransition] {
+        &self.transitions
+    }
+
+    /// Name of the current state in the definition, e.g. "Stressed".
+    pub fn state_name(&self) -> &str {
+        &self.graph.states()[self.state].name
+    }
+
+    /// The definition the machine runs.
+    pub fn graph(&self) -> &StateGraph {
+        &self.graph
     }
 
     /// Return the accumulated event log.
     pub fn events(&self) -> &[RuptureEvent] {
         &self.events
     }
-}
 
-/// Determine the base state from rho without candidate/confirmation logic.
-fn base_state(rho: f64, cfg: &StateMachineConfig) -> RuptureState {
-    if rho >= cfg.rho_rupture {
-        RuptureState::Critical
-    } else if rho >= cfg.rho_critical {
-        RuptureState::Critical
-    } else if rho >= cfg.rho_stressed {
-        RuptureState::Stressed
-    } else {
-        RuptureState::Stable
+    /// Mutable access to the event log, for enriching events after the fact.
+    pub(crate) fn events_mut(&mut self) -> &mut [RuptureEvent] {
+        &mut self.events
+    }
+
+    /// Episodes of confirmed ruptures; the last is still open (no end) while
+    /// the machine is in ConfirmedRupture or Recovery.
+    pub fn episodes(&self) -> &[RuptureEpisode] {
+        &self.episodes
+    }
+
+    fn kind(&self) -> RuptureState {
+        self.graph.states()[self.state].kind
+    }
+
+    /// In ConfirmedRupture or Recovery, with an open episode.
+    fn in_rupture(&self) -> bool {
+        matches!(
+            self.kind(),
+            RuptureState::ConfirmedRupture | RuptureState::Recovery
+        )
+    }
+
+    /// The target and threshold of a `crossing` transition out of the
+    /// current state, when rho crosses that threshold upwards on this bar.
+    fn crossing(&self, rho: f64) -> Option<(usize, f64)> {
+        let t = self
+            .graph
+            .transition(self.state, TransitionGuard::Crossing)?;
+        match (t.guard, t.to) {
+            (Guard::Crossing { rho: threshold }, Some(to))
+                if rho >= threshold && self.prev_rho < threshold =>
+            {
+                Some((to, threshold))
+            }
+            _ => None,
+        }
+    }
+
+    /// Where the transition `on` the given guard leads from the current
+    /// state: its target, or the level matching rho.
+    fn target(&self, on: TransitionGuard, rho: f64) -> usize {
+        self.graph
+            .transition(self.state, on)
+            .and_then(|t| t.to)
+            .unwrap_or_else(|| self.graph.level_for(rho, self.state))
+    }
+
+    /// Count a bar towards the `below` transition out of ConfirmedRupture or
+    /// Recovery, and take it once rho has been below its threshold long
+    /// enough.
+    fn count_below(&mut self, index: usize, timestamp: &Timestamp, rho: f64) {
+        let Some(&Transition {
+            to,
+            guard:
+                Guard::Below {
+                    rho: threshold,
+                    bars,
+                },
+            ..
+        }) = self.graph.transition(self.state, TransitionGuard::Below)
+        else {
+            return;
+        };
+        if rho >= threshold {
+            self.recovery_count = 0;
+            return;
+        }
+        self.recovery_count += 1;
+        if self.recovery_count < bars {
+            return;
+        }
+        self.recovery_count = 0;
+        let target = to.unwrap_or_else(|| self.graph.level_for(rho, self.state));
+        // The confirmed event is always the latest one here. Without a
+        // Recovery phase it recovers when the episode ends.
+        if let Some(event) = self.events.last_mut() {
+            if event.recovery_index.is_none() {
+                event.recovery_index = Some(index);
+                event.duration_bars = Some(index - event.candidate_index);
+            }
+        }
+        if self.graph.states()[target].kind == RuptureState::Recovery {
+            if let Some(episode) = self.episodes.last_mut() {
+                episode.recovery_index = Some(index);
+                episode.recovery_timestamp = Some(timestamp.clone());
+            }
+        } else {
+            self.close_episode(index, timestamp, EpisodeEnd::Recovered);
+        }
+        self.state = target;
+    }
+
+    /// Fresh crossing of `threshold`: enter the candidate state `to`.
+    fn start_candidate(
+        &mut self,
+        to: usize,
+        index: usize,
+        timestamp: &Timestamp,
+        rho: f64,
+        threshold: f64,
+    ) {
+        self.state = to;
+        self.candidate_index = Some(index);
+        self.candidate_timestamp = Some(timestamp.clone());
+        self.candidate_rho = threshold;
+        self.candidate_peak_rho = rho;
+        self.candidate_peak_index = index;
+        self.candidate_peak_timestamp = Some(timestamp.clone());
+        self.confirm_buffer.clear();
+        self.confirm_buffer.push(true); // The crossing bar itself counts.
+        self.last_timestamp = Some(timestamp.clone());
+        self.time_above_ms = 0;
+        self.recovery_count = 0;
+    }
+
+    /// Open an episode for the candidate confirmed at `index`.
+    fn open_episode(&mut self, index: usize, timestamp: &Timestamp) {
+        let start_timestamp = self
+            .candidate_timestamp
+            .clone()
+            .unwrap_or_else(|| timestamp.clone());
+        self.episodes.push(RuptureEpisode {
+            start_index: self.candidate_index.unwrap_or(index),
+            start_timestamp: start_timestamp.clone(),
+            confirmed_index: index,
+            confirmed_timestamp: timestamp.clone(),
+            peak_rho: self.candidate_peak_rho,
+            peak_index: self.candidate_peak_index,
+            peak_timestamp: self
+                .candidate_peak_timestamp
+                .clone()
+                .unwrap_or(start_timestamp),
+            recovery_index: None,
+            recovery_timestamp: None,
+            end_index: None,
+            end_timestamp: None,
+            end_reason: None,
+            recovery_bars: None,
+            duration_bars: None,
+        });
+    }
+
+    fn track_episode_peak(&mut self, index: usize, timestamp: &Timestamp, rho: f64) {
+        if let Some(episode) = self.episodes.last_mut() {
+            if rho > episode.peak_rho {
+                episode.peak_rho = rho;
+                episode.peak_index = index;
+                episode.peak_timestamp = timestamp.clone();
+            }
+        }
+    }
+
+    fn close_episode(&mut self, index: usize, timestamp: &Timestamp, reason: EpisodeEnd) {
+        if let Some(episode) = self.episodes.last_mut() {
+            episode.end_index = Some(index);
+            episode.end_timestamp = Some(timestamp.clone());
+            episode.end_reason = Some(reason);
+            episode.recovery_bars = episode.recovery_index.map(|r| index - r);
+            episode.duration_bars = Some(index - episode.start_index);
+        }
+    }
+
+    /// Stay in the current level until it has lasted its `min_dwell_bars`.
+    fn dwell(&self, target: usize) -> usize {
+        let current = &self.graph.states()[self.state];
+        if current.kind.is_level()
+            && self.state_bars > 0
+            && self.state_bars < current.min_dwell_bars
+        {
+            self.state
+        } else {
+            target
+        }
+    }
+
+    /// Record one more bar of an m-of-k window. Returns the outcome once the
+    /// window is complete, or as soon as m bars are above with `confirm_early`.
+    fn bars_decision(&mut self, above: bool) -> Option<bool> {
+        self.confirm_buffer.push(above);
+        let above_count = self.confirm_buffer.iter().filter(|&&b| b).count();
+        if self.cfg.confirm_early && above_count >= self.cfg.confirm_m {
+            return Some(true);
+        }
+        (self.confirm_buffer.len() >= self.cfg.confirm_k)
+            .then_some(above_count >= self.cfg.confirm_m)
+    }
+
+    /// Record one more bar of a time window. Each bar's rho holds until the
+    /// next bar, so the interval since the previous bar counts as above
+    /// threshold when that bar was. The window closes at the first bar at or
+    /// after its end; a bar without a parsed timestamp fails the candidate.
+    fn time_decision(&mut self, timestamp: &Timestamp, above: bool) -> Option<bool> {
+        let held = self.confirm_buffer.last().copied().unwrap_or(false);
+        self.confirm_buffer.push(above);
+        let previous = self.last_timestamp.replace(timestamp.clone());
+
+        let times = (
+            self.cfg.confirm_time_rule(),
+            self.candidate_timestamp
+                .as_ref()
+                .and_then(|t| t.as_datetime()),
+            previous.and_then(|t| t.as_datetime()),
+            timestamp.as_datetime(),
+        );
+        let (Some((window, duration)), Some(start), Some(previous), Some(now)) = times else {
+            return Some(false);
+        };
+        let end = start + window;
+        if held {
+            let interval = now.min(end) - previous;
+            self.time_above_ms += interval.num_milliseconds().max(0);
+        }
+        let met = self.time_above_ms >= duration.num_milliseconds();
+        if self.cfg.confirm_early && met {
+            return Some(true);
+        }
+        (now >= end).then_some(met)
+    }
+
+    /// Build the event closing the current candidate window at `index`.
+    fn candidate_event(
+        &self,
+        index: usize,
+        timestamp: &Timestamp,
+        confirmed: bool,
+    ) -> RuptureEvent {
+        RuptureEvent {
+            candidate_index: self.candidate_index.unwrap_or(index),
+            candidate_timestamp: self
+                .candidate_timestamp
+                .clone()
+                .unwrap_or_else(|| timestamp.clone()),
+            confirmed_index: confirmed.then_some(index),
+            confirmed_timestamp: confirmed.then(|| timestamp.clone()),
+            peak_rho: self.candidate_peak_rho,
+            peak_index: self.candidate_peak_index,
+            confirmation_k: self.cfg.confirm_k,
+            confirmation_m: self.cfg.confirm_m,
+            candidate_attribution: Vec::new(),
+            peak_attribution: Vec::new(),
+            recovery_index: None,
+            duration_bars: None,
+        }
     }
 }
@@ -0,0 +1,74 @@
+use crate::config::Config;
+use crate::error::Result;
+use crate::io::{
+    read_bars, write_config_snapshot, write_episodes_json, write_events_json, write_timeseries_csv,
+    write_transitions_jsonl,
+};
+use crate::model::{run_engine_with_episodes, EngineRun};
+use crate::preprocess::{clean_bars, validate_bars};
+use crate::types::Bar;
+use std::path::{Path, PathBuf};
+
+/// Read and clean bars from a CSV, Parquet or Arrow IPC file according to
+/// the IO config.
+pub fn load_bars(input: &Path, config: &Config) -> Result<Vec<Bar>> {
+    let bars = read_bars(input, &config.io)?;
+    Ok(clean_bars(bars, config.io.sort_by_timestamp))
+}
+
+/// Run the full pipeline on one input file: load, validation and the engine,
+/// which also handles gaps. Diagnostics are not printed.
+pub fn run_file(input: &Path, config: &Config) -> Result<EngineRun> {
+    let bars = load_bars(input, config)?;
+    validate_bars(&bars, &config.preprocess, &config.windows)?;
+    run_engine_with_episodes(&bars, config)
+}
+
+/// Write the outputs enabled in `config.outputs` under `output_dir`. Returns
+/// a description and path for each file written.
+pub fn write_outputs(
+    run: &EngineRun,
+    config: &Config,
+    output_dir: &Path,
+) -> Result<Vec<(&'static str, PathBuf)>> {
+    let mut written = Vec::new();
+
+    if config.outputs.write_csv_timeseries {
+        let path = output_dir.join(&config.outputs.csv_timeseries_name);
+        write_timeseries_csv(&run.rows, &config.residual_channel_names(), &path)?;
+        written.push(("time series CSV", path));
+    }
+
+    if config.outputs.write_json_events {
+        let path = output_dir.join(&config.outputs.json_events_name);
+        write_events_json(&run.events, &path)?;
+        written.push(("events JSON", path));
+    }
+
+    if config.outputs.write_json_episodes {
+        let path = output_dir.join(&config.outputs.json_episodes_name);
+        write_episodes_json(&run.episodes, &path)?;
+        written.push(("episodes JSON", path));
+    }
+
+    if config.outputs.write_jsonl_transitions {
+        let path = output_dir.join(&config.outputs.jsonl_transitions_name);
+        write_transitions_jsonl(&run.transitions, &path)?;
+        written.push(("transition log", path));
+    }
+
+    #[cfg(feature = "parquet")]
+    if config.outputs.write_parquet_timeseries {
+        let path = output_dir.join(&config.outputs.parquet_timeseries_name);
+        crate::io::write_timeseries_parquet(&run.rows, &config.residual_channel_names(), &path)?;
+        written.push(("time series Parquet", path));
+    }
+
+    if config.outputs.write_json_config_snapshot {
+        let path = output_dir.join(&config.outputs.json_config_snapshot_name);
+        write_config_snapshot(config, &path)?;
+        written.push(("config snapshot", path));
+    }
+
+    Ok(written)
+}
@@ -1,6 +1,7 @@
 use crate::types::Bar;
 
-/// Clean bars by deduplicating timestamps and optionally sorting. Rows with
+/// Clean bars by deduplicating timestamps and optionally sorting. Parsed
+/// timestamps sort chronologically and raw ones lexicographically. Rows with
 /// impossible values (negative close) are removed.
 pub fn clean_bars(bars: Vec<Bar>, sort: bool) -> Vec<Bar> {
     let mut cleaned = bars;
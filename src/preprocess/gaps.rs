@@ -0,0 +1,265 @@
+use crate::config::{parse_duration, GapCalendar, GapsConfig, IoConfig};
+use crate::error::{Result, RuptureError};
+use crate::io::timestamp::TimestampParser;
+use crate::types::{Bar, Timestamp};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// A gap between two consecutive bars.
+#[derive(Debug, Clone, Serialize)]
+pub struct Gap {
+    /// Index of the first bar after the gap.
+    pub index: usize,
+    pub from: Timestamp,
+    pub to: Timestamp,
+    /// Number of expected bars missing between the two.
+    pub missing: usize,
+}
+
+/// Measures gaps between bar timestamps against an expected interval and
+/// trading calendar. Calendar dates are taken in `io.timezone`.
+///
+/// For sub-daily intervals on the weekdays calendar, the first bar of each
+/// trading day is treated as a session open: the overnight break is not a
+/// gap, and each whole trading day skipped counts as one missing bar.
+#[derive(Debug, Clone)]
+pub struct GapDetector {
+    interval: Duration,
+    calendar: GapCalendar,
+    holidays: BTreeSet<NaiveDate>,
+    clock: TimestampParser,
+}
+
+impl GapDetector {
+    pub fn from_config(gaps: &GapsConfig, io: &IoConfig) -> Result<Self> {
+        let interval = parse_duration(&gaps.expected_interval)?;
+        let holidays = gaps
+            .holidays
+            .iter()
+            .map(|d| {
+                NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").map_err(|_| {
+                    RuptureError::Config(format!("gaps.holidays: '{}' is not a YYYY-MM-DD date", d))
+                })
+            })
+            .collect::<Result<BTreeSet<_>>>()?;
+        Ok(Self {
+            interval,
+            calendar: gaps.calendar,
+            holidays,
+            clock: TimestampParser::from_config(io)?,
+        })
+    }
+
+    /// Number of expected bars missing between two consecutive timestamps.
+    /// Raw (unparsed) timestamps never report a gap.
+    pub fn missing_between(&self, prev: &Timestamp, curr: &Timestamp) -> usize {
+        let (Some(a), Some(b)) = (prev.as_datetime(), curr.as_datetime()) else {
+            return 0;
+        };
+        if b <= a {
+            return 0;
+        }
+        if self.calendar == GapCalendar::Continuous || self.interval > Duration::days(1) {
+            return self.steps(b - a);
+        }
+
+        let (da, db) = (self.clock.to_local(a).date(), self.clock.to_local(b).date());
+        if self.interval < Duration::days(1) && da == db {
+            self.steps(b - a)
+        } else {
+            self.trading_days_between(da, db).count()
+        }
+    }
+
+    /// Timestamps of the bars expected between two consecutive timestamps,
+    /// used for forward-filling. Skipped sessions of sub-daily data on the
+    /// weekdays calendar cannot be reconstructed and yield nothing.
+    pub fn expected_between(&self, prev: &Timestamp, curr: &Timestamp) -> Vec<Timestamp> {
+        let missing = self.missing_between(prev, curr);
+        let (Some(a), Some(b)) = (prev.as_datetime(), curr.as_datetime()) else {
+            return Vec::new();
+        };
+        if missing == 0 {
+            return Vec::new();
+        }
+
+        let (la, lb) = (self.clock.to_local(a), self.clock.to_local(b));
+        let stepwise = self.calendar == GapCalendar::Continuous
+            || self.interval > Duration::days(1)
+            || (self.interval < Duration::days(1) && la.date() == lb.date());
+
+        if stepwise {
+            (1..=missing as i32)
+                .map(|k| Timestamp::Parsed(a + self.interval * k))
+                .collect()
+        } else if self.interval == Duration::days(1) {
+            self.trading_days_between(la.date(), lb.date())
+                .filter_map(|d| self.clock.from_local(d.and_time(la.time())))
+                .map(Timestamp::Parsed)
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn steps(&self, delta: Duration) -> usize {
+        let ratio = delta.num_milliseconds() as f64 / self.interval.num_milliseconds() as f64;
+        (ratio.round() as i64 - 1).max(0) as usize
+    }
+
+    fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+
+    fn trading_days_between(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> impl Iterator<Item = NaiveDate> + '_ {
+        from.iter_days()
+            .skip(1)
+            .take_while(move |d| *d < to)
+            .filter(move |d| self.is_trading_day(*d))
+    }
+}
+
+/// Find all gaps in a sequence of bars.
+pub fn detect_gaps(bars: &[Bar], detector: &GapDetector) -> Vec<Gap> {
+    bars.windows(2)
+        .enumerate()
+        .filter_map(|(i, pair)| {
+            let missing = detector.missing_between(&pair[0].ts, &pair[1].ts);
+            (missing > 0).then(|| Gap {
+                index: i + 1,
+                from: pair[0].ts.clone(),
+                to: pair[1].ts.clone(),
+                missing,
+            })
+        })
+        .collect()
+}
+
+/// Insert synthetic bars for missing timestamps, as [`fill_between`] does
+/// for each pair of consecutive bars.
+pub fn fill_gaps(bars: Vec<Bar>, detector: &GapDetector) -> Vec<Bar> {
+    let mut out: Vec<Bar> = Vec::with_capacity(bars.len());
+    for bar in bars {
+        if let Some(prev) = out.last() {
+            let filled = fill_between(prev, &bar, detector);
+            out.extend(filled);
+        }
+        out.push(bar);
+    }
+    out
+}
+
+/// Synthetic bars for the timestamps missing between `prev` and `next`.
+/// Each carries the previous close as its open, high, low and close,
+/// repeats the previous volume, and is marked `filled`.
+pub fn fill_between(prev: &Bar, next: &Bar, detector: &GapDetector) -> Vec<Bar> {
+    detector
+        .expected_between(&prev.ts, &next.ts)
+        .into_iter()
+        .map(|ts| Bar {
+            ts,
+            open: prev.close,
+            high: prev.close,
+            low: prev.close,
+            close: prev.close,
+            volume: prev.volume,
+            filled: true,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GapPolicy;
+    use chrono::{DateTime, Utc};
+
+    fn detector(interval: &str, calendar: GapCalendar, holidays: &[&str]) -> GapDetector {
+        let gaps = GapsConfig {
+            enabled: true,
+            expected_interval: interval.into(),
+            calendar,
+            holidays: holidays.iter().map(|s| s.to_string()).collect(),
+            policy: GapPolicy::Flag,
+        };
+        let io = IoConfig {
+            timestamp_column: "timestamp".into(),
+            open_column: "open".into(),
+            high_column: "high".into(),
+            low_column: "low".into(),
+            close_column: "close".into(),
+            volume_column: "volume".into(),
+            parse_timestamps: true,
+            timestamp_format: "auto".into(),
+            timezone: "UTC".into(),
+            drop_invalid_rows: true,
+            sort_by_timestamp: true,
+        };
+        GapDetector::from_config(&gaps, &io).unwrap()
+    }
+
+    fn ts(text: &str) -> Timestamp {
+        Timestamp::Parsed(
+            DateTime::parse_from_rfc3339(text)
+                .unwrap()
+                .with_timezone(&Utc),
+        )
+    }
+
+    #[test]
+    fn test_weekend_is_not_a_gap() {
+        let d = detector("1d", GapCalendar::Weekdays, &[]);
+        // Friday to Monday.
+        assert_eq!(
+            d.missing_between(&ts("2008-01-04T00:00:00Z"), &ts("2008-01-07T00:00:00Z")),
+            0
+        );
+        // Friday to Wednesday skips Monday and Tuesday.
+        assert_eq!(
+            d.missing_between(&ts("2008-01-04T00:00:00Z"), &ts("2008-01-09T00:00:00Z")),
+            2
+        );
+    }
+
+    #[test]
+    fn test_holidays_are_excluded() {
+        let d = detector("1d", GapCalendar::Weekdays, &["2008-01-01"]);
+        assert_eq!(
+            d.missing_between(&ts("2007-12-31T00:00:00Z"), &ts("2008-01-02T00:00:00Z")),
+            0
+        );
+    }
+
+    #[test]
+    fn test_continuous_intraday() {
+        let d = detector("5m", GapCalendar::Continuous, &[]);
+        let a = ts("2008-01-02T10:00:00Z");
+        assert_eq!(d.missing_between(&a, &ts("2008-01-02T10:05:00Z")), 0);
+        assert_eq!(d.missing_between(&a, &ts("2008-01-02T10:30:00Z")), 5);
+        assert_eq!(d.expected_between(&a, &ts("2008-01-02T10:15:00Z")).len(), 2);
+    }
+
+    #[test]
+    fn test_intraday_overnight_is_session_break() {
+        let d = detector("5m", GapCalendar::Weekdays, &[]);
+        let close = ts("2008-01-02T21:00:00Z");
+        assert_eq!(d.missing_between(&close, &ts("2008-01-03T14:30:00Z")), 0);
+        // Wednesday close to Monday open skips Thursday and Friday.
+        assert_eq!(d.missing_between(&close, &ts("2008-01-07T14:30:00Z")), 2);
+    }
+
+    #[test]
+    fn test_fill_daily_weekdays() {
+        let d = detector("1d", GapCalendar::Weekdays, &[]);
+        let expected = d.expected_between(&ts("2008-01-04T00:00:00Z"), &ts("2008-01-09T00:00:00Z"));
+        assert_eq!(
+            expected,
+            vec![ts("2008-01-07T00:00:00Z"), ts("2008-01-08T00:00:00Z")]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 pub mod clean;
+pub mod gaps;
 pub mod validate;
 
 pub use clean::clean_bars;
-pub use validate::validate_bars;
+pub use gaps::{detect_gaps, fill_between, fill_gaps, Gap, GapDetector};
+pub use validate::{validate_bars, validate_bars_with_history};
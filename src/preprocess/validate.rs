@@ -7,6 +7,18 @@ pub fn validate_bars(
     bars: &[Bar],
     preprocess: &PreprocessConfig,
     windows: &WindowsConfig,
+) -> Result<()> {
+    validate_bars_with_history(bars, preprocess, windows, 0)
+}
+
+/// Validate bars that continue a resumed run. `history` is the number of bars
+/// the engine has already processed; it counts towards the minimum row
+/// requirement, so a resumed run may append only a handful of new bars.
+pub fn validate_bars_with_history(
+    bars: &[Bar],
+    preprocess: &PreprocessConfig,
+    windows: &WindowsConfig,
+    history: usize,
 ) -> Result<()> {
     if bars.is_empty() {
         return Err(RuptureError::EmptyData(
@@ -20,11 +32,11 @@ pub fn validate_bars(
         .max(windows.memory_k)
         .max(windows.capacity_l);
 
-    if bars.len() < required {
+    if history + bars.len() < required {
         return Err(RuptureError::Validation(format!(
             "Need at least {} rows for the configured windows, but only {} rows are present",
             required,
-            bars.len()
+            history + bars.len()
         )));
     }
 
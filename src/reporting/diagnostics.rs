@@ -1,4 +1,6 @@
 use crate::config::Config;
+use crate::model::memory::MemoryKernel;
+use crate::preprocess::gaps::{detect_gaps, GapDetector};
 use crate::types::Bar;
 
 /// Run basic diagnostic checks on the data and configuration and print any warnings.
@@ -14,19 +16,17 @@ pub fn run_diagnostics(bars: &[Bar], config: &Config) {
         );
     }
 
-    if config.memory.alpha < 0.3 {
-        eprintln!(
-            "[WARN] memory.alpha = {:.2} is quite low, producing a very long memory tail. \
-             This may make the model slow to adapt to regime changes.",
-            config.memory.alpha
-        );
+    for warning in config.validation_report().warnings() {
+        eprintln!("[WARN] {}", warning);
     }
 
-    if config.memory.alpha > 0.9 {
+    if let Some(error) =
+        MemoryKernel::from_config(config.windows.memory_k, &config.memory).approximation_error()
+    {
         eprintln!(
-            "[WARN] memory.alpha = {:.2} is close to 1, producing very short memory. \
-             The model may behave more like a simple moving average.",
-            config.memory.alpha
+            "[INFO] memory kernel: {} exponential terms for K = {}, weight error L1 = {:.2e} \
+             (max relative {:.2e}). Strain error is at most L1 times the largest residual.",
+            config.memory.terms, config.windows.memory_k, error.l1, error.max_relative
         );
     }
 
@@ -45,7 +45,7 @@ pub fn run_diagnostics(bars: &[Bar], config: &Config) {
     let mut large_gap_count = 0;
     for i in 1..bars.len() {
         let ratio = bars[i].close / bars[i - 1].close;
-        if ratio > 1.2 || ratio < 0.8 {
+        if !(0.8..=1.2).contains(&ratio) {
             large_gap_count += 1;
         }
     }
@@ -56,4 +56,28 @@ pub fn run_diagnostics(bars: &[Bar], config: &Config) {
             large_gap_count
         );
     }
+
+    // Check for time gaps against the expected interval and calendar.
+    if config.gaps.enabled {
+        if let Ok(detector) = GapDetector::from_config(&config.gaps, &config.io) {
+            let gaps = detect_gaps(bars, &detector);
+            if !gaps.is_empty() {
+                let missing: usize = gaps.iter().map(|g| g.missing).sum();
+                let largest = gaps
+                    .iter()
+                    .max_by_key(|g| g.missing)
+                    .expect("gaps is non-empty");
+                eprintln!(
+                    "[WARN] {} gaps totalling {} missing bars (policy: {:?}). Largest: {} bars \
+                     between {} and {}.",
+                    gaps.len(),
+                    missing,
+                    config.gaps.policy,
+                    largest.missing,
+                    largest.from,
+                    largest.to
+                );
+            }
+        }
+    }
 }
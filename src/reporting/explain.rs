@@ -0,0 +1,68 @@
+use crate::config::Config;
+use crate::error::{Result, RuptureError};
+
+/// Render the effective config as TOML, every field included. Values that
+/// differ from `Config::default()` are marked with the default.
+pub fn explain_config(config: &Config) -> Result<String> {
+    let to_err = |e: &dyn std::fmt::Display| RuptureError::Config(e.to_string());
+    let text = toml::to_string(config).map_err(|e| to_err(&e))?;
+    let effective = toml::Value::try_from(config).map_err(|e| to_err(&e))?;
+    let defaults = toml::Value::try_from(Config::default()).map_err(|e| to_err(&e))?;
+
+    let mut out = String::new();
+    let mut section: Vec<String> = Vec::new();
+    let mut in_array = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(header) = trimmed
+            .strip_prefix("[[")
+            .and_then(|h| h.strip_suffix("]]"))
+        {
+            // Arrays of tables (residuals.channels) are empty by default.
+            in_array = true;
+            out.push_str(line);
+            if lookup(&defaults, &path(header)).is_none_or(is_empty_array) {
+                out.push_str("  # default: none");
+            }
+            out.push('\n');
+            continue;
+        }
+        if let Some(header) = trimmed.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+            section = path(header);
+            in_array = false;
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+        out.push_str(line);
+        if let (false, Some((key, _))) = (in_array, trimmed.split_once(" = ")) {
+            let mut full = section.clone();
+            full.push(key.trim_matches('"').to_string());
+            match lookup(&defaults, &full) {
+                Some(default) if Some(default) != lookup(&effective, &full) => {
+                    out.push_str(&format!("  # default: {}", default));
+                }
+                None => out.push_str("  # default: unset"),
+                _ => {}
+            }
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn path(header: &str) -> Vec<String> {
+    header
+        .split('.')
+        .map(|p| p.trim_matches('"').to_string())
+        .collect()
+}
+
+fn lookup<'a>(value: &'a toml::Value, path: &[String]) -> Option<&'a toml::Value> {
+    path.iter()
+        .try_fold(value, |v, key| v.as_table().and_then(|t| t.get(key)))
+}
+
+fn is_empty_array(value: &toml::Value) -> bool {
+    value.as_array().is_some_and(|a| a.is_empty())
+}
@@ -1,4 +1,6 @@
 pub mod diagnostics;
+pub mod explain;
 pub mod summary;
 
-pub use summary::print_summary;
+pub use explain::explain_config;
+pub use summary::{print_evaluation, print_summary, state_distribution};
@@ -1,4 +1,5 @@
-use crate::types::{EngineOutputRow, RuptureEvent};
+use crate::evaluate::{Distribution, EvaluationReport};
+use crate::types::{EngineOutputRow, RuptureEvent, RuptureState};
 
 /// Print a human-readable summary of the engine run to stdout.
 pub fn print_summary(rows: &[EngineOutputRow], events: &[RuptureEvent]) {
@@ -33,15 +34,73 @@ pub fn print_summary(rows: &[EngineOutputRow], events: &[RuptureEvent]) {
         println!("Maximum peak rho (confirmed): {:.4}", max_peak);
     }
 
-    // State distribution.
-    let mut state_counts = std::collections::HashMap::new();
-    for row in rows {
-        *state_counts.entry(row.state.clone()).or_insert(0usize) += 1;
-    }
     println!("\nState distribution:");
-    for (state, count) in &state_counts {
-        let pct = 100.0 * *count as f64 / total_bars as f64;
-        println!("  {:<12} {:>6} bars ({:.1}%)", state, count, pct);
+    for (label, count) in state_distribution(rows) {
+        let pct = 100.0 * count as f64 / total_bars as f64;
+        println!("  {:<12} {:>6} bars ({:.1}%)", label, count, pct);
     }
     println!("==============================");
 }
+
+/// Bars per state, in order of escalation (`RuptureState::ALL`). Declared
+/// states of the same kind follow in order of first appearance.
+pub fn state_distribution(rows: &[EngineOutputRow]) -> Vec<(String, usize)> {
+    let mut counts: Vec<(RuptureState, String, usize)> = Vec::new();
+    for row in rows {
+        match counts.iter_mut().find(|c| c.1 == row.state_label) {
+            Some((_, _, count)) => *count += 1,
+            None => counts.push((row.state, row.state_label.clone(), 1)),
+        }
+    }
+    let rank = |state: &RuptureState| RuptureState::ALL.iter().position(|s| s == state);
+    counts.sort_by_key(|(state, _, _)| rank(state));
+    counts
+        .into_iter()
+        .map(|(_, label, count)| (label, count))
+        .collect()
+}
+
+/// Print a labelled-event evaluation to stdout.
+pub fn print_evaluation(report: &EvaluationReport) {
+    let counts = &report.counts;
+    println!("=== Rupture Engine Evaluation ===");
+    println!("Tolerance:            {}", report.tolerance);
+    println!("Labels in data:       {}", counts.labels);
+    println!("Confirmed ruptures:   {}", counts.detections);
+    println!(
+        "Hit rate:             {:.1}% ({} of {})",
+        100.0 * report.hit_rate,
+        counts.labels_hit,
+        counts.labels
+    );
+    println!("Precision:            {:.1}%", 100.0 * report.precision);
+    match report.false_alarms_per_year {
+        Some(per_year) => println!(
+            "False alarms:         {} ({:.2} per year over {:.1} years)",
+            counts.false_alarms(),
+            per_year,
+            report.years
+        ),
+        None => println!("False alarms:         {}", counts.false_alarms()),
+    }
+    print_distribution("Lead (bars)", &report.lead_bars);
+    print_distribution("Confirmation lead", &report.confirmation_lead_bars);
+    print_distribution("Lead (days)", &report.lead_days);
+    if !report.missed.is_empty() {
+        println!("Missed:               {}", report.missed.join(", "));
+    }
+    println!("=================================");
+}
+
+fn print_distribution(name: &str, dist: &Option<Distribution>) {
+    if let Some(d) = dist {
+        println!(
+            "{:<21} min {:.1}, median {:.1}, mean {:.1}, max {:.1}",
+            format!("{}:", name),
+            d.min,
+            d.median,
+            d.mean,
+            d.max
+        );
+    }
+}
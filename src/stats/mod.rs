@@ -1,11 +1,13 @@
 pub mod mad;
 pub mod median;
+pub mod ordered_window;
 pub mod quantile;
 pub mod robust;
 pub mod rolling_window;
 
 pub use mad::mad;
 pub use median::median;
+pub use ordered_window::OrderedWindow;
 pub use quantile::quantile;
 pub use robust::safe_div;
 pub use rolling_window::RollingWindow;
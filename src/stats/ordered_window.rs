@@ -0,0 +1,377 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+/// Fixed-capacity rolling window that keeps its contents in sorted order, so
+/// that order statistics are available without copying or re-sorting.
+///
+/// Push, median and quantile are O(log n); MAD is O(log^2 n). Results are
+/// identical to `median`, `mad` and `quantile` applied to the window
+/// contents. Values are ordered by `f64::total_cmp`, so windows should hold
+/// finite values.
+///
+/// Serialises as the window contents in insertion order plus capacity, the
+/// same shape as `RollingWindow`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "WindowState", into = "WindowState")]
+pub struct OrderedWindow {
+    fifo: VecDeque<(f64, u64)>,
+    capacity: usize,
+    next_seq: u64,
+    tree: Treap,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WindowState {
+    buf: VecDeque<f64>,
+    capacity: usize,
+}
+
+impl From<WindowState> for OrderedWindow {
+    fn from(state: WindowState) -> Self {
+        let mut window = OrderedWindow::new(state.capacity);
+        for value in state.buf {
+            window.push(value);
+        }
+        window
+    }
+}
+
+impl From<OrderedWindow> for WindowState {
+    fn from(window: OrderedWindow) -> Self {
+        WindowState {
+            buf: window.fifo.iter().map(|&(v, _)| v).collect(),
+            capacity: window.capacity,
+        }
+    }
+}
+
+impl OrderedWindow {
+    /// Create a new window with the given capacity.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            fifo: VecDeque::with_capacity(capacity),
+            capacity,
+            next_seq: 0,
+            tree: Treap::new(),
+        }
+    }
+
+    /// Push a value into the window, evicting the oldest if full.
+    pub fn push(&mut self, value: f64) {
+        if self.fifo.len() == self.capacity {
+            if let Some((old, seq)) = self.fifo.pop_front() {
+                self.tree.remove(old, seq);
+            }
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.fifo.push_back((value, seq));
+        self.tree.insert(value, seq);
+    }
+
+    /// Return whether the window has reached its full capacity.
+    pub fn is_full(&self) -> bool {
+        self.fifo.len() == self.capacity
+    }
+
+    /// Return the number of elements currently in the window.
+    pub fn len(&self) -> usize {
+        self.fifo.len()
+    }
+
+    /// Return whether the window is empty.
+    pub fn is_empty(&self) -> bool {
+        self.fifo.is_empty()
+    }
+
+    /// Return a copy of the current window contents in insertion order.
+    pub fn as_slice(&self) -> Vec<f64> {
+        self.fifo.iter().map(|&(v, _)| v).collect()
+    }
+
+    /// The k-th smallest value (0-based). Panics if `k >= len()`.
+    pub fn kth(&self, k: usize) -> f64 {
+        self.tree.kth(k)
+    }
+
+    /// Median, as `stats::median`. Returns 0.0 for an empty window.
+    pub fn median(&self) -> f64 {
+        let n = self.len();
+        if n == 0 {
+            return 0.0;
+        }
+        if n % 2 == 1 {
+            self.kth(n / 2)
+        } else {
+            (self.kth(n / 2 - 1) + self.kth(n / 2)) / 2.0
+        }
+    }
+
+    /// Quantile with linear interpolation, as `stats::quantile`.
+    pub fn quantile(&self, q: f64) -> f64 {
+        let n = self.len();
+        if n == 0 {
+            return 0.0;
+        }
+        if n == 1 {
+            return self.kth(0);
+        }
+        let pos = q * (n - 1) as f64;
+        let lo = pos.floor() as usize;
+        let hi = pos.ceil() as usize;
+        if lo == hi {
+            self.kth(lo)
+        } else {
+            let frac = pos - lo as f64;
+            self.kth(lo) * (1.0 - frac) + self.kth(hi) * frac
+        }
+    }
+
+    /// Median absolute deviation, as `stats::mad`.
+    ///
+    /// The deviations of the values below the median, read downwards, and of
+    /// the values from the median up, read upwards, are two ascending
+    /// sequences; the k-th deviation is found by binary search over how many
+    /// come from each.
+    pub fn mad(&self) -> f64 {
+        let n = self.len();
+        if n == 0 {
+            return 0.0;
+        }
+        let med = self.median();
+        let split = self.tree.count_less(med);
+        let below = |j: usize| med - self.kth(split - 1 - j);
+        let above = |j: usize| self.kth(split + j) - med;
+        let kth_deviation = |k: usize| kth_of_two(k, split, n - split, &below, &above);
+
+        if n % 2 == 1 {
+            kth_deviation(n / 2)
+        } else {
+            (kth_deviation(n / 2 - 1) + kth_deviation(n / 2)) / 2.0
+        }
+    }
+}
+
+/// The k-th smallest element (0-based) of the merge of two ascending
+/// sequences of lengths `na` and `nb`, given by index functions.
+fn kth_of_two(
+    k: usize,
+    na: usize,
+    nb: usize,
+    a: &dyn Fn(usize) -> f64,
+    b: &dyn Fn(usize) -> f64,
+) -> f64 {
+    // Find how many of the first k + 1 merged elements come from `a`.
+    let take = k + 1;
+    let mut lo = take.saturating_sub(nb);
+    let mut hi = take.min(na);
+    while lo < hi {
+        let i = (lo + hi) / 2;
+        let j = take - i;
+        if a(i) < b(j - 1) {
+            lo = i + 1;
+        } else {
+            hi = i;
+        }
+    }
+    let (i, j) = (lo, take - lo);
+    match (i > 0, j > 0) {
+        (true, true) => a(i - 1).max(b(j - 1)),
+        (true, false) => a(i - 1),
+        (false, _) => b(j - 1),
+    }
+}
+
+const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone)]
+struct Node {
+    value: f64,
+    seq: u64,
+    priority: u64,
+    left: usize,
+    right: usize,
+    size: usize,
+}
+
+/// Treap keyed by (value, insertion sequence) with subtree sizes. Priorities
+/// are a hash of the sequence number, so the shape is deterministic.
+#[derive(Debug, Clone)]
+struct Treap {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    root: usize,
+}
+
+impl Treap {
+    fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: NIL,
+        }
+    }
+
+    fn size(&self, t: usize) -> usize {
+        if t == NIL {
+            0
+        } else {
+            self.nodes[t].size
+        }
+    }
+
+    fn update(&mut self, t: usize) {
+        self.nodes[t].size = 1 + self.size(self.nodes[t].left) + self.size(self.nodes[t].right);
+    }
+
+    fn less(&self, t: usize, value: f64, seq: u64) -> bool {
+        let node = &self.nodes[t];
+        match node.value.total_cmp(&value) {
+            Ordering::Less => true,
+            Ordering::Greater => false,
+            Ordering::Equal => node.seq < seq,
+        }
+    }
+
+    /// Split into keys below (value, seq) and keys at or above it.
+    fn split(&mut self, t: usize, value: f64, seq: u64) -> (usize, usize) {
+        if t == NIL {
+            return (NIL, NIL);
+        }
+        if self.less(t, value, seq) {
+            let (l, r) = self.split(self.nodes[t].right, value, seq);
+            self.nodes[t].right = l;
+            self.update(t);
+            (t, r)
+        } else {
+            let (l, r) = self.split(self.nodes[t].left, value, seq);
+            self.nodes[t].left = r;
+            self.update(t);
+            (l, t)
+        }
+    }
+
+    fn merge(&mut self, a: usize, b: usize) -> usize {
+        if a == NIL {
+            return b;
+        }
+        if b == NIL {
+            return a;
+        }
+        if self.nodes[a].priority > self.nodes[b].priority {
+            let right = self.merge(self.nodes[a].right, b);
+            self.nodes[a].right = right;
+            self.update(a);
+            a
+        } else {
+            let left = self.merge(a, self.nodes[b].left);
+            self.nodes[b].left = left;
+            self.update(b);
+            b
+        }
+    }
+
+    fn insert(&mut self, value: f64, seq: u64) {
+        let node = Node {
+            value,
+            seq,
+            priority: splitmix64(seq),
+            left: NIL,
+            right: NIL,
+            size: 1,
+        };
+        let t = match self.free.pop() {
+            Some(t) => {
+                self.nodes[t] = node;
+                t
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+        let (l, r) = self.split(self.root, value, seq);
+        let l = self.merge(l, t);
+        self.root = self.merge(l, r);
+    }
+
+    fn remove(&mut self, value: f64, seq: u64) {
+        let (l, r) = self.split(self.root, value, seq);
+        let (m, r) = self.split(r, value, seq + 1);
+        if m != NIL {
+            self.free.push(m);
+        }
+        self.root = self.merge(l, r);
+    }
+
+    fn kth(&self, mut k: usize) -> f64 {
+        let mut t = self.root;
+        loop {
+            assert!(t != NIL, "kth out of range");
+            let left = self.size(self.nodes[t].left);
+            match k.cmp(&left) {
+                Ordering::Less => t = self.nodes[t].left,
+                Ordering::Equal => return self.nodes[t].value,
+                Ordering::Greater => {
+                    k -= left + 1;
+                    t = self.nodes[t].right;
+                }
+            }
+        }
+    }
+
+    /// Number of values ordered strictly before `value`.
+    fn count_less(&self, value: f64) -> usize {
+        let mut t = self.root;
+        let mut count = 0;
+        while t != NIL {
+            if self.nodes[t].value.total_cmp(&value) == Ordering::Less {
+                count += self.size(self.nodes[t].left) + 1;
+                t = self.nodes[t].right;
+            } else {
+                t = self.nodes[t].left;
+            }
+        }
+        count
+    }
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_statistics_after_eviction() {
+        let mut w = OrderedWindow::new(3);
+        for v in [5.0, 1.0, 3.0, 2.0] {
+            w.push(v);
+        }
+        // Window holds [1, 3, 2].
+        assert_eq!(w.len(), 3);
+        assert_eq!(w.kth(0), 1.0);
+        assert_eq!(w.median(), 2.0);
+        assert_eq!(w.quantile(1.0), 3.0);
+        assert_eq!(w.as_slice(), vec![1.0, 3.0, 2.0]);
+    }
+
+    #[test]
+    fn test_mad_with_ties() {
+        let mut w = OrderedWindow::new(10);
+        for v in [2.0, 2.0, 2.0, 5.0, 1.0, 2.0] {
+            w.push(v);
+        }
+        // Median 2, deviations [0, 0, 0, 3, 1, 0], MAD = 0.
+        assert_eq!(w.mad(), 0.0);
+    }
+}
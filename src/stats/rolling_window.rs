@@ -1,8 +1,9 @@
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 /// A simple fixed-capacity rolling window backed by a VecDeque.
 /// On each push, if the window is full, the oldest element is evicted.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RollingWindow {
     buf: VecDeque<f64>,
     capacity: usize,
@@ -35,6 +36,11 @@ impl RollingWindow {
         self.buf.len()
     }
 
+    /// Return whether the window is empty.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
     /// Return a sorted copy of the current window contents.
     pub fn sorted_snapshot(&self) -> Vec<f64> {
         let mut v: Vec<f64> = self.buf.iter().copied().collect();
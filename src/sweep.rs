@@ -0,0 +1,328 @@
+use crate::config::{set_dotted, Config};
+use crate::error::{Result, RuptureError};
+use crate::evaluate::{evaluate, Evaluation, LabelInterval, Tolerance};
+use crate::model::run_engine;
+use crate::parallel::parallel_map;
+use crate::preprocess::validate_bars;
+use crate::types::{Bar, RuptureState, Timestamp};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Sections that shape the preprocessed bars; a sweep runs every
+/// combination on the same bars, so these cannot be swept.
+const FIXED_SECTIONS: [&str; 1] = ["io"];
+
+/// Values to try for one config key: an explicit list, or an inclusive
+/// range. A range of integers yields integers.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum SweepValues {
+    List(Vec<toml::Value>),
+    Range {
+        start: toml::Value,
+        stop: toml::Value,
+        step: toml::Value,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct SweepFile {
+    params: BTreeMap<String, SweepValues>,
+}
+
+/// A parameter grid over dotted config keys.
+///
+/// ```toml
+/// [params]
+/// "residuals.theta_vol" = [1.0, 1.25, 1.5]
+/// "memory.alpha" = { start = 0.55, stop = 0.75, step = 0.1 }
+/// "state_machine.confirm_k" = { start = 2, stop = 3, step = 1 }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SweepSpec {
+    /// Keys in name order, each with its values.
+    pub params: Vec<(String, Vec<toml::Value>)>,
+}
+
+/// One combination of the grid: a value per key, in key order.
+pub type Combination = Vec<(String, toml::Value)>;
+
+impl SweepSpec {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content)
+            .map_err(|e| RuptureError::Config(format!("sweep spec {}: {}", path.display(), e)))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: SweepFile =
+            toml::from_str(content).map_err(|e| RuptureError::Config(e.to_string()))?;
+        let params = file
+            .params
+            .into_iter()
+            .map(|(key, values)| {
+                let values = expand(&key, values)?;
+                if values.is_empty() {
+                    return Err(RuptureError::Config(format!("'{}' has no values", key)));
+                }
+                Ok((key, values))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if params.is_empty() {
+            return Err(RuptureError::Config("no [params] to sweep".into()));
+        }
+        Ok(Self { params })
+    }
+
+    /// Check that every key names an existing, sweepable field of `base`.
+    /// Each key is set to its first value and must survive a round trip
+    /// through `Config`: unknown keys are dropped, and optional fields that
+    /// are unset in `base` are only serialised once they have a value.
+    pub fn check_keys(&self, base: &Config) -> Result<()> {
+        let value = toml::Value::try_from(base).map_err(|e| RuptureError::Config(e.to_string()))?;
+        for (key, values) in &self.params {
+            let section = key.split('.').next().unwrap_or("");
+            if FIXED_SECTIONS.contains(&section) {
+                return Err(RuptureError::Config(format!(
+                    "cannot sweep '{}': [{}] is fixed for the preprocessed bars",
+                    key, section
+                )));
+            }
+            let invalid =
+                |e: String| RuptureError::Config(format!("cannot sweep '{}': {}", key, e));
+            let mut swept = value.clone();
+            set_dotted(&mut swept, key, values[0].clone())?;
+            let config: Config = swept
+                .try_into()
+                .map_err(|e: toml::de::Error| invalid(e.to_string()))?;
+            let round_trip = toml::Value::try_from(&config).map_err(|e| invalid(e.to_string()))?;
+            let found = key.split('.').try_fold(&round_trip, |v, part| {
+                v.as_table().and_then(|t| t.get(part))
+            });
+            if found.is_none() {
+                return Err(invalid("no such config key".into()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of combinations in the grid.
+    pub fn len(&self) -> usize {
+        self.params.iter().map(|(_, v)| v.len()).product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every combination, the last key varying fastest.
+    pub fn combinations(&self) -> Vec<Combination> {
+        let mut combos: Vec<Combination> = vec![Vec::new()];
+        for (key, values) in &self.params {
+            combos = combos
+                .into_iter()
+                .flat_map(|combo| {
+                    values.iter().map(move |v| {
+                        let mut next = combo.clone();
+                        next.push((key.clone(), v.clone()));
+                        next
+                    })
+                })
+                .collect();
+        }
+        combos
+    }
+}
+
+fn expand(key: &str, values: SweepValues) -> Result<Vec<toml::Value>> {
+    let (start, stop, step) = match values {
+        SweepValues::List(list) => return Ok(list),
+        SweepValues::Range { start, stop, step } => (start, stop, step),
+    };
+    if let (Some(start), Some(stop), Some(step)) =
+        (start.as_integer(), stop.as_integer(), step.as_integer())
+    {
+        if step <= 0 {
+            return Err(RuptureError::Config(format!(
+                "'{}': step must be positive",
+                key
+            )));
+        }
+        return Ok((start..=stop)
+            .step_by(step as usize)
+            .map(toml::Value::Integer)
+            .collect());
+    }
+    let number = |v: &toml::Value| {
+        v.as_float()
+            .or_else(|| v.as_integer().map(|i| i as f64))
+            .ok_or_else(|| RuptureError::Config(format!("'{}': range bounds must be numbers", key)))
+    };
+    let (start, stop, step) = (number(&start)?, number(&stop)?, number(&step)?);
+    if !(step > 0.0 && step.is_finite() && start.is_finite() && stop.is_finite()) {
+        return Err(RuptureError::Config(format!(
+            "'{}': range needs finite bounds and a positive step",
+            key
+        )));
+    }
+    // Values are start + i * step, rounded to 12 decimals so that the grid
+    // prints cleanly; the stop value is included up to rounding.
+    let count = ((stop - start) / step + 1e-9).floor() as i64 + 1;
+    Ok((0..count.max(0))
+        .map(|i| toml::Value::Float(((start + i as f64 * step) * 1e12).round() / 1e12))
+        .collect())
+}
+
+/// Nested override table setting each dotted key of a combination.
+pub fn combination_overrides(combo: &[(String, toml::Value)]) -> toml::Value {
+    let mut root = toml::Value::Table(toml::value::Table::new());
+    for (key, value) in combo {
+        set_dotted(&mut root, key, value.clone()).expect("dotted keys address tables");
+    }
+    root
+}
+
+/// Outcome of one combination.
+#[derive(Debug, Clone)]
+pub struct SweepResult {
+    pub combination: Combination,
+    /// Metrics, or the reason the combination could not run (for example
+    /// an invalid config).
+    pub result: std::result::Result<SweepMetrics, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SweepMetrics {
+    pub candidates: usize,
+    pub confirmed: usize,
+    /// Fraction of bars in each state, in `RuptureState::ALL` order.
+    pub time_in_state: Vec<f64>,
+    /// Score against labels, when labels were supplied.
+    pub evaluation: Option<Evaluation>,
+}
+
+impl SweepMetrics {
+    /// Fraction of candidates that were confirmed; NaN without candidates.
+    pub fn confirmation_rate(&self) -> f64 {
+        self.confirmed as f64 / self.candidates as f64
+    }
+}
+
+/// Labels and matching tolerance for scoring a sweep.
+#[derive(Debug, Clone, Copy)]
+pub struct SweepLabels<'a> {
+    pub labels: &'a [LabelInterval],
+    pub tolerance: Tolerance,
+}
+
+/// Run every combination of `spec` over the same preprocessed `bars`, on up
+/// to `jobs` threads. Results are in combination order.
+pub fn run_sweep(
+    bars: &[Bar],
+    base: &Config,
+    spec: &SweepSpec,
+    labels: Option<SweepLabels>,
+    jobs: usize,
+) -> Vec<SweepResult> {
+    let timestamps: Vec<Timestamp> = bars.iter().map(|b| b.ts.clone()).collect();
+    parallel_map(&spec.combinations(), jobs, |combo| SweepResult {
+        combination: combo.clone(),
+        result: run_combination(bars, &timestamps, base, combo, labels).map_err(|e| e.to_string()),
+    })
+}
+
+fn run_combination(
+    bars: &[Bar],
+    timestamps: &[Timestamp],
+    base: &Config,
+    combo: &[(String, toml::Value)],
+    labels: Option<SweepLabels>,
+) -> Result<SweepMetrics> {
+    let config = base.with_overrides(&combination_overrides(combo))?;
+    validate_bars(bars, &config.preprocess, &config.windows)?;
+    let (rows, events) = run_engine(bars, &config)?;
+
+    let time_in_state = RuptureState::ALL
+        .iter()
+        .map(|&state| rows.iter().filter(|r| r.state == state).count() as f64 / rows.len() as f64)
+        .collect();
+
+    Ok(SweepMetrics {
+        candidates: events.len(),
+        confirmed: events
+            .iter()
+            .filter(|e| e.confirmed_index.is_some())
+            .count(),
+        time_in_state,
+        evaluation: labels
+            .map(|l| evaluate(&events, timestamps, l.labels, l.tolerance))
+            .transpose()?,
+    })
+}
+
+/// Write one row per combination: the swept values, status, event counts,
+/// confirmation rate, fraction of time in each state and, when labels were
+/// supplied, precision, recall and F1.
+pub fn write_sweep_csv(results: &[SweepResult], spec: &SweepSpec, path: &Path) -> Result<()> {
+    let with_labels = results
+        .iter()
+        .any(|r| matches!(&r.result, Ok(m) if m.evaluation.is_some()));
+
+    let mut header: Vec<String> = vec!["combination".into()];
+    header.extend(spec.params.iter().map(|(key, _)| key.clone()));
+    header.extend(["status", "candidates", "confirmed", "confirmation_rate"].map(String::from));
+    header.extend(
+        RuptureState::ALL
+            .iter()
+            .map(|s| format!("time_{}", s.to_string().to_lowercase())),
+    );
+    if with_labels {
+        header.extend(["precision", "recall", "f1"].map(String::from));
+    }
+    header.push("error".into());
+
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(&header)?;
+    for (i, result) in results.iter().enumerate() {
+        let mut record: Vec<String> = vec![i.to_string()];
+        record.extend(result.combination.iter().map(|(_, v)| v.to_string()));
+        match &result.result {
+            Ok(metrics) => {
+                record.push("ok".into());
+                record.push(metrics.candidates.to_string());
+                record.push(metrics.confirmed.to_string());
+                record.push(format_ratio(metrics.confirmation_rate()));
+                record.extend(metrics.time_in_state.iter().map(|&f| format_ratio(f)));
+                if with_labels {
+                    match &metrics.evaluation {
+                        Some(e) => {
+                            record.extend([e.precision(), e.recall(), e.f1()].map(format_ratio))
+                        }
+                        None => record.extend([String::new(), String::new(), String::new()]),
+                    }
+                }
+                record.push(String::new());
+            }
+            Err(message) => {
+                record.push("failed".into());
+                let blanks = 3 + RuptureState::ALL.len() + if with_labels { 3 } else { 0 };
+                record.extend(std::iter::repeat_n(String::new(), blanks));
+                record.push(message.clone());
+            }
+        }
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Ratios print with six decimals; undefined ratios are left empty.
+fn format_ratio(value: f64) -> String {
+    if value.is_finite() {
+        format!("{:.6}", value)
+    } else {
+        String::new()
+    }
+}
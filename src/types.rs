@@ -1,15 +1,120 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 
+/// Bar timestamp. Either the opaque text from the input file, or an instant
+/// parsed according to `io.timestamp_format` when `io.parse_timestamps` is set.
+/// Parsed timestamps order chronologically; raw ones order lexicographically.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Timestamp {
+    Raw(String),
+    Parsed(DateTime<Utc>),
+}
+
+impl Timestamp {
+    /// Return the parsed instant, if any.
+    pub fn as_datetime(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Timestamp::Raw(_) => None,
+            Timestamp::Parsed(dt) => Some(*dt),
+        }
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Timestamp::Raw(s) => write!(f, "{}", s),
+            Timestamp::Parsed(dt) => {
+                write!(f, "{}", dt.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+            }
+        }
+    }
+}
+
+impl From<&str> for Timestamp {
+    fn from(s: &str) -> Self {
+        Timestamp::Raw(s.to_string())
+    }
+}
+
+impl From<&String> for Timestamp {
+    fn from(s: &String) -> Self {
+        Timestamp::Raw(s.clone())
+    }
+}
+
+impl From<String> for Timestamp {
+    fn from(s: String) -> Self {
+        Timestamp::Raw(s)
+    }
+}
+
+impl From<&Timestamp> for Timestamp {
+    fn from(ts: &Timestamp) -> Self {
+        ts.clone()
+    }
+}
+
+impl From<DateTime<Utc>> for Timestamp {
+    fn from(dt: DateTime<Utc>) -> Self {
+        Timestamp::Parsed(dt)
+    }
+}
+
+/// Timestamps serialise as their display string. Parsed instants display in
+/// one canonical RFC 3339 form, so a string in exactly that form reads back
+/// as parsed and anything else as raw text. Raw text that happens to be in
+/// the canonical form is written as `{"raw": "..."}` so that it stays raw.
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Timestamp::Raw(raw) if canonical_instant(raw).is_some() => {
+                TimestampRepr::Tagged { raw: raw.clone() }.serialize(serializer)
+            }
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ok(match TimestampRepr::deserialize(deserializer)? {
+            TimestampRepr::Text(s) => match canonical_instant(&s) {
+                Some(dt) => Timestamp::Parsed(dt),
+                None => Timestamp::Raw(s),
+            },
+            TimestampRepr::Tagged { raw } => Timestamp::Raw(raw),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum TimestampRepr {
+    Text(String),
+    Tagged { raw: String },
+}
+
+/// The instant `s` is the display form of, if it is exactly the form a
+/// parsed timestamp displays as.
+fn canonical_instant(s: &str) -> Option<DateTime<Utc>> {
+    let dt = DateTime::parse_from_rfc3339(s).ok()?.with_timezone(&Utc);
+    (Timestamp::Parsed(dt).to_string() == s).then_some(dt)
+}
+
 /// Raw OHLCV bar from input data.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bar {
-    pub ts: String,
+    pub ts: Timestamp,
     pub open: f64,
     pub high: f64,
     pub low: f64,
     pub close: f64,
     pub volume: f64,
+    /// Synthetic bar inserted by gap forward-filling.
+    #[serde(default)]
+    pub filled: bool,
 }
 
 /// Derived features computed from consecutive bars.
@@ -29,14 +134,17 @@ pub struct Normed {
     pub v: f64,
     /// Normalised absolute acceleration: |A_t| / robust_scale_accel.
     pub a: f64,
+    /// Normalised range volatility: sigma_range / median_sigma_range.
+    /// Zero when the close-to-close estimator is selected.
+    pub g: f64,
 }
 
 /// Individual residual channel values.
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct Residuals {
-    pub r_vol: f64,
-    pub r_liq: f64,
-    pub r_acc: f64,
+    /// One value per configured channel, in channel order.
+    pub channels: Vec<f64>,
+    /// Combined residual.
     pub r: f64,
 }
 
@@ -50,55 +158,191 @@ pub struct StrainPoint {
 
 /// Deterministic rupture state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum RuptureState {
     Stable,
     Stressed,
     Critical,
+    #[serde(rename = "candidate")]
     CandidateRupture,
+    #[serde(rename = "confirmed")]
     ConfirmedRupture,
     Recovery,
 }
 
-impl fmt::Display for RuptureState {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let label = match self {
+impl RuptureState {
+    /// Every state, in order of escalation.
+    pub const ALL: [RuptureState; 6] = [
+        RuptureState::Stable,
+        RuptureState::Stressed,
+        RuptureState::Critical,
+        RuptureState::CandidateRupture,
+        RuptureState::ConfirmedRupture,
+        RuptureState::Recovery,
+    ];
+
+    /// Stable machine-readable code, as serialised: "stable", "stressed",
+    /// "critical", "candidate", "confirmed" or "recovery".
+    pub fn code(self) -> &'static str {
+        match self {
+            RuptureState::Stable => "stable",
+            RuptureState::Stressed => "stressed",
+            RuptureState::Critical => "critical",
+            RuptureState::CandidateRupture => "candidate",
+            RuptureState::ConfirmedRupture => "confirmed",
+            RuptureState::Recovery => "recovery",
+        }
+    }
+
+    /// Human-readable label, also the default state name.
+    pub fn label(self) -> &'static str {
+        match self {
             RuptureState::Stable => "Stable",
             RuptureState::Stressed => "Stressed",
             RuptureState::Critical => "Critical",
-            RuptureState::CandidateRupture => "Candidate",
-            RuptureState::ConfirmedRupture => "Confirmed",
+            RuptureState::CandidateRupture => "Candidate rupture",
+            RuptureState::ConfirmedRupture => "Confirmed rupture",
             RuptureState::Recovery => "Recovery",
-        };
-        write!(f, "{}", label)
+        }
+    }
+
+    /// Stable, Stressed and Critical, which follow rho outside a rupture.
+    pub fn is_level(self) -> bool {
+        matches!(
+            self,
+            RuptureState::Stable | RuptureState::Stressed | RuptureState::Critical
+        )
+    }
+}
+
+impl fmt::Display for RuptureState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label())
     }
 }
 
 /// Full output row for a single bar in the time series.
 #[derive(Debug, Clone, Serialize)]
 pub struct EngineOutputRow {
-    pub timestamp: String,
+    pub timestamp: Timestamp,
     pub close: f64,
     pub volume: f64,
-    pub r_vol: f64,
-    pub r_liq: f64,
-    pub r_acc: f64,
+    /// Residual per configured channel, in channel order.
+    pub residuals: Vec<f64>,
     pub r_combined: f64,
+    /// Each channel's contribution to `r_combined`, in channel order.
+    pub attribution: Vec<f64>,
     pub strain: f64,
     pub capacity: f64,
     pub rho: f64,
-    pub state: String,
+    pub state: RuptureState,
+    /// Name of the state in the configured state machine; the state's label
+    /// unless states are declared.
+    pub state_label: String,
     pub candidate_flag: bool,
     pub confirmed_flag: bool,
+    /// Number of expected bars missing immediately before this bar.
+    pub gap: usize,
+    /// Whether this bar was synthesised by gap forward-filling.
+    pub filled: bool,
 }
 
 /// A detected rupture event with timing information.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuptureEvent {
     pub candidate_index: usize,
-    pub candidate_timestamp: String,
+    pub candidate_timestamp: Timestamp,
     pub confirmed_index: Option<usize>,
-    pub confirmed_timestamp: Option<String>,
+    pub confirmed_timestamp: Option<Timestamp>,
     pub peak_rho: f64,
+    /// Bar of the highest rho in the confirmation window.
+    pub peak_index: usize,
     pub confirmation_k: usize,
     pub confirmation_m: usize,
+    /// Strain by residual channel at the candidate bar.
+    #[serde(default)]
+    pub candidate_attribution: Vec<ChannelStrain>,
+    /// Strain by residual channel at the peak bar.
+    #[serde(default)]
+    pub peak_attribution: Vec<ChannelStrain>,
+    /// Bar at which a confirmed rupture entered Recovery, or without a
+    /// Recovery phase, returned to a level.
+    #[serde(default)]
+    pub recovery_index: Option<usize>,
+    /// Bars from candidate to `recovery_index`.
+    #[serde(default)]
+    pub duration_bars: Option<usize>,
+}
+
+/// A confirmed rupture from its candidate bar until the machine leaves
+/// Recovery (or, without a Recovery phase, ConfirmedRupture).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuptureEpisode {
+    /// Candidate bar: the first crossing of `rho_rupture`.
+    pub start_index: usize,
+    pub start_timestamp: Timestamp,
+    pub confirmed_index: usize,
+    pub confirmed_timestamp: Timestamp,
+    /// Highest rho over the whole episode.
+    pub peak_rho: f64,
+    pub peak_index: usize,
+    pub peak_timestamp: Timestamp,
+    /// Bar at which Recovery started.
+    pub recovery_index: Option<usize>,
+    pub recovery_timestamp: Option<Timestamp>,
+    /// Bar at which the episode ended; `None` while it is still open.
+    pub end_index: Option<usize>,
+    pub end_timestamp: Option<Timestamp>,
+    pub end_reason: Option<EpisodeEnd>,
+    /// Bars from the start of Recovery to the end of the episode.
+    pub recovery_bars: Option<usize>,
+    /// Bars from the candidate bar to the end of the episode.
+    pub duration_bars: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EpisodeEnd {
+    /// rho met the exit criteria.
+    Recovered,
+    /// A new candidate rupture started during Recovery.
+    NewRupture,
+}
+
+/// One change of state, as written to the transition log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateTransition {
+    pub index: usize,
+    pub timestamp: Timestamp,
+    /// State names as defined by the config, e.g. "Stressed".
+    pub from: String,
+    pub to: String,
+    pub rho: f64,
+    pub rule: TransitionRule,
+}
+
+/// What moved the state machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionRule {
+    /// rho moved into another level.
+    Level,
+    /// A fresh crossing of the rupture threshold.
+    Crossing,
+    /// The candidate met the confirmation rule.
+    Confirmed,
+    /// The candidate failed the confirmation rule.
+    Rejected,
+    /// rho stayed below a threshold for the required bars.
+    Below,
+}
+
+/// The part of the strain accumulated from one residual channel: the memory
+/// kernel applied to that channel's history of contributions to `r`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelStrain {
+    pub channel: String,
+    pub strain: f64,
+    /// Fraction of the total strain.
+    pub share: f64,
 }
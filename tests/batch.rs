@@ -0,0 +1,113 @@
+mod common;
+
+use common::tiny_config;
+use rupture_engine::batch::{batch_items, run_batch, write_batch_events, write_batch_summary};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const FIXTURE: &str = "data/fixtures/tiny_ohlcv_60.csv";
+
+#[test]
+fn test_directory_batch_reports_failures_without_aborting() {
+    let input_dir = TempDir::new().unwrap();
+    let output_dir = TempDir::new().unwrap();
+    fs::copy(FIXTURE, input_dir.path().join("AAA.csv")).unwrap();
+    fs::copy(FIXTURE, input_dir.path().join("CCC.csv")).unwrap();
+    fs::write(
+        input_dir.path().join("BBB.csv"),
+        "timestamp,close\n2005-01-03,1.0\n",
+    )
+    .unwrap();
+    fs::write(input_dir.path().join("notes.txt"), "ignored").unwrap();
+
+    let items = batch_items(input_dir.path()).unwrap();
+    let names: Vec<&str> = items.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, vec!["AAA", "BBB", "CCC"]);
+
+    let outcomes = run_batch(&items, &tiny_config(), output_dir.path(), 2);
+    assert!(outcomes[0].result.is_ok());
+    assert!(outcomes[1].result.is_err());
+    assert!(outcomes[2].result.is_ok());
+    assert!(output_dir
+        .path()
+        .join("AAA/rupture_timeseries.csv")
+        .exists());
+    assert!(!output_dir.path().join("BBB").exists());
+
+    let summary = output_dir.path().join("batch_summary.csv");
+    let events = output_dir.path().join("batch_events.csv");
+    write_batch_summary(&outcomes, &summary).unwrap();
+    write_batch_events(&outcomes, &events).unwrap();
+
+    let summary = fs::read_to_string(summary).unwrap();
+    assert!(summary.lines().any(|l| l.starts_with("BBB,failed,")));
+    let events = fs::read_to_string(events).unwrap();
+    assert!(events
+        .lines()
+        .skip(1)
+        .all(|l| l.starts_with("AAA,") || l.starts_with("CCC,")));
+}
+
+#[test]
+fn test_manifest_with_overrides() {
+    let dir = TempDir::new().unwrap();
+    let output_dir = TempDir::new().unwrap();
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE);
+    fs::copy(&fixture, dir.path().join("spy.csv")).unwrap();
+    fs::write(
+        dir.path().join("manifest.toml"),
+        r#"
+[[asset]]
+name = "base"
+input = "spy.csv"
+
+[[asset]]
+name = "strict"
+input = "spy.csv"
+overrides = { state_machine = { rho_rupture = 1000.0 } }
+"#,
+    )
+    .unwrap();
+
+    let items = batch_items(&dir.path().join("manifest.toml")).unwrap();
+    assert_eq!(items[0].input, dir.path().join("spy.csv"));
+
+    let outcomes = run_batch(&items, &tiny_config(), output_dir.path(), 2);
+    let base = outcomes[0].result.as_ref().unwrap();
+    let strict = outcomes[1].result.as_ref().unwrap();
+    assert!(!base.events.is_empty());
+    assert!(strict.events.is_empty());
+}
+
+#[test]
+fn test_duplicate_manifest_names_rejected() {
+    let dir = TempDir::new().unwrap();
+    let manifest = dir.path().join("manifest.toml");
+    fs::write(
+        &manifest,
+        "[[asset]]\nname = \"x\"\ninput = \"a.csv\"\n\n[[asset]]\nname = \"x\"\ninput = \"b.csv\"\n",
+    )
+    .unwrap();
+    assert!(batch_items(&manifest).is_err());
+}
+
+#[test]
+fn test_manifest_names_must_be_plain_file_names() {
+    let dir = TempDir::new().unwrap();
+    let manifest = dir.path().join("manifest.toml");
+    for name in ["", ".", "..", "../x", "a/b", "/tmp/x", "a\\\\b"] {
+        fs::write(
+            &manifest,
+            format!("[[asset]]\nname = \"{}\"\ninput = \"a.csv\"\n", name),
+        )
+        .unwrap();
+        assert!(batch_items(&manifest).is_err(), "accepted '{}'", name);
+    }
+    fs::write(
+        &manifest,
+        "[[asset]]\nname = \"SPY.daily\"\ninput = \"a.csv\"\n",
+    )
+    .unwrap();
+    assert!(batch_items(&manifest).is_ok());
+}
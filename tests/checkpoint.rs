@@ -0,0 +1,127 @@
+mod common;
+
+use common::{synthetic_bars, tiny_config};
+use rupture_engine::io::{read_checkpoint, write_checkpoint};
+use rupture_engine::model::checkpoint::unprocessed_bars;
+use rupture_engine::model::{run_engine, Checkpoint, RuptureEngine};
+use rupture_engine::types::Timestamp;
+use tempfile::TempDir;
+
+#[test]
+fn test_resume_matches_uninterrupted_run() {
+    let config = tiny_config();
+    let bars = synthetic_bars(400);
+    let (full_rows, full_events) = run_engine(&bars, &config).unwrap();
+
+    for split in [1, 57, 199, 210, 399] {
+        let mut first = RuptureEngine::new(&config).unwrap();
+        for bar in &bars[..split] {
+            first.update(bar);
+        }
+
+        // Round-trip through JSON text, as a daily job would via disk.
+        let json = serde_json::to_string(&Checkpoint::new(&first)).unwrap();
+        let checkpoint: Checkpoint = serde_json::from_str(&json).unwrap();
+        let mut resumed = checkpoint.into_engine(&config).unwrap();
+
+        for (i, bar) in bars.iter().enumerate().skip(split) {
+            let (row, _) = resumed.update(bar).pop().unwrap();
+            assert_eq!(
+                row.rho.to_bits(),
+                full_rows[i].rho.to_bits(),
+                "rho differs at bar {} after resuming at {}",
+                i,
+                split
+            );
+            assert_eq!(row.state, full_rows[i].state);
+        }
+
+        let events = resumed.events();
+        assert_eq!(events.len(), full_events.len());
+        for (a, b) in events.iter().zip(&full_events) {
+            assert_eq!(a.candidate_index, b.candidate_index);
+            assert_eq!(a.confirmed_index, b.confirmed_index);
+            assert_eq!(a.peak_rho.to_bits(), b.peak_rho.to_bits());
+        }
+    }
+}
+
+#[test]
+fn test_checkpoint_file_round_trip() {
+    let config = tiny_config();
+    let bars = synthetic_bars(120);
+    let mut engine = RuptureEngine::new(&config).unwrap();
+    for bar in &bars[..100] {
+        engine.update(bar);
+    }
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("engine.ckpt.json");
+    write_checkpoint(&Checkpoint::new(&engine), &path).unwrap();
+    let restored = read_checkpoint(&path)
+        .unwrap()
+        .into_engine(&config)
+        .unwrap();
+
+    assert_eq!(restored.bars_processed(), 100);
+    assert_eq!(restored.last_timestamp(), Some(&bars[99].ts));
+    let remaining = unprocessed_bars(&restored, &bars).unwrap();
+    assert_eq!(remaining.len(), 20);
+    assert_eq!(remaining[0].ts, bars[100].ts);
+
+    // Only the new bars, no bars after the checkpoint, or bars that skip
+    // the checkpoint's last bar.
+    assert_eq!(unprocessed_bars(&restored, &bars[100..]).unwrap().len(), 20);
+    assert!(unprocessed_bars(&restored, &bars[..100])
+        .unwrap()
+        .is_empty());
+    let mut skipped = bars.clone();
+    skipped.remove(99);
+    assert!(unprocessed_bars(&restored, &skipped).is_err());
+    assert!(unprocessed_bars(&restored, &bars[..90]).is_err());
+}
+
+#[test]
+fn test_resume_rejects_changed_parameters() {
+    let config = tiny_config();
+    let engine = RuptureEngine::new(&config).unwrap();
+
+    let mut changed = config.clone();
+    changed.memory.alpha = 0.5;
+    assert!(Checkpoint::new(&engine).into_engine(&changed).is_err());
+
+    // Output settings may change between runs.
+    let mut renamed = config.clone();
+    renamed.outputs.csv_timeseries_name = "other.csv".into();
+    assert!(Checkpoint::new(&engine).into_engine(&renamed).is_ok());
+}
+
+#[test]
+fn test_raw_timestamps_with_offsets_stay_raw() {
+    let config = tiny_config();
+    assert!(!config.io.parse_timestamps);
+    let mut bars = synthetic_bars(60);
+    for (i, bar) in bars.iter_mut().enumerate() {
+        bar.ts = format!("2005-03-11T{:02}:{:02}:00+01:00", 9 + i / 60, i % 60).into();
+    }
+    let mut engine = RuptureEngine::new(&config).unwrap();
+    for bar in &bars[..50] {
+        engine.update(bar);
+    }
+
+    let json = serde_json::to_string(&Checkpoint::new(&engine)).unwrap();
+    let checkpoint: Checkpoint = serde_json::from_str(&json).unwrap();
+    let restored = checkpoint.into_engine(&config).unwrap();
+    assert_eq!(restored.last_timestamp(), Some(&bars[49].ts));
+    assert_eq!(unprocessed_bars(&restored, &bars).unwrap().len(), 10);
+
+    // Raw text in the form parsed timestamps display as is tagged as raw.
+    let raw = Timestamp::from("2005-03-11T08:30:00Z");
+    let json = serde_json::to_string(&raw).unwrap();
+    assert_eq!(json, r#"{"raw":"2005-03-11T08:30:00Z"}"#);
+    assert_eq!(serde_json::from_str::<Timestamp>(&json).unwrap(), raw);
+    let parsed = Timestamp::Parsed(raw.to_string().parse().unwrap());
+    let json = serde_json::to_string(&parsed).unwrap();
+    assert_eq!(json, r#""2005-03-11T08:30:00Z""#);
+    assert_eq!(serde_json::from_str::<Timestamp>(&json).unwrap(), parsed);
+}
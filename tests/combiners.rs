@@ -0,0 +1,66 @@
+mod common;
+
+use common::{synthetic_bars, tiny_config};
+use rupture_engine::config::CombineMethod;
+use rupture_engine::model::{run_engine, run_engine_with_episodes};
+use rupture_engine::pipeline::write_outputs;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_attribution_explains_combined_residual() {
+    let bars = synthetic_bars(300);
+    for method in [
+        CombineMethod::SoftMax,
+        CombineMethod::WeightedSoftMax,
+        CombineMethod::WeightedSum,
+        CombineMethod::HardMax,
+        CombineMethod::PNorm,
+    ] {
+        let mut config = tiny_config();
+        config.combine.method = method;
+        config.combine.weights.insert("liq".into(), 0.5);
+        config.validate().unwrap();
+
+        let (rows, _) = run_engine(&bars, &config).unwrap();
+        for row in &rows {
+            assert_eq!(row.attribution.len(), row.residuals.len());
+            let total: f64 = row.attribution.iter().sum();
+            assert!(
+                (total - row.r_combined).abs() <= 1e-9 * row.r_combined.max(1.0),
+                "{:?}",
+                method
+            );
+        }
+    }
+}
+
+#[test]
+fn test_hard_max_ignores_all_but_largest_channel() {
+    let mut config = tiny_config();
+    config.combine.method = CombineMethod::HardMax;
+    let (rows, _) = run_engine(&synthetic_bars(300), &config).unwrap();
+    for row in &rows {
+        let max = row.residuals.iter().cloned().fold(0.0, f64::max);
+        assert_eq!(row.r_combined, max);
+        assert!(row.attribution.iter().filter(|a| **a > 0.0).count() <= 1);
+    }
+}
+
+#[test]
+fn test_attribution_columns_and_weight_validation() {
+    let config = tiny_config();
+    let run = run_engine_with_episodes(&synthetic_bars(300), &config).unwrap();
+    let dir = TempDir::new().unwrap();
+    write_outputs(&run, &config, dir.path()).unwrap();
+    let csv = fs::read_to_string(dir.path().join(&config.outputs.csv_timeseries_name)).unwrap();
+    assert!(csv
+        .lines()
+        .next()
+        .unwrap()
+        .contains("r_combined,attr_vol,attr_liq,attr_acc,strain"));
+
+    let mut bad = tiny_config();
+    bad.combine.weights.insert("spread".into(), 1.0);
+    assert!(bad.validate().is_err());
+}
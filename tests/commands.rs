@@ -0,0 +1,130 @@
+use assert_cmd::prelude::*;
+use predicates::str::contains;
+use rupture_engine::config::{Config, DEFAULT_CONFIG_TOML};
+use rupture_engine::reporting::explain_config;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+const FIXTURE: &str = "data/fixtures/tiny_ohlcv_60.csv";
+
+fn engine() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("rupture-engine"))
+}
+
+#[test]
+fn test_default_template_matches_config_default() {
+    let parsed: Config = toml::from_str(DEFAULT_CONFIG_TOML).unwrap();
+    assert_eq!(
+        serde_json::to_value(&parsed).unwrap(),
+        serde_json::to_value(Config::default()).unwrap()
+    );
+    Config::default().validate().unwrap();
+
+    // The explained defaults are valid TOML for the same config, unannotated.
+    let explained = explain_config(&Config::default()).unwrap();
+    assert!(!explained.contains("# default"));
+    let reparsed: Config = toml::from_str(&explained).unwrap();
+    assert_eq!(
+        serde_json::to_value(&reparsed).unwrap(),
+        serde_json::to_value(Config::default()).unwrap()
+    );
+}
+
+#[test]
+fn test_run_subcommand_matches_flat_flags() {
+    let flat = TempDir::new().unwrap();
+    let sub = TempDir::new().unwrap();
+    let args = [
+        "--input",
+        FIXTURE,
+        "--config",
+        "configs/tiny.toml",
+        "--output-dir",
+    ];
+
+    engine().args(args).arg(flat.path()).assert().success();
+    engine()
+        .arg("run")
+        .args(args)
+        .arg(sub.path())
+        .assert()
+        .success();
+
+    let read =
+        |dir: &TempDir| fs::read_to_string(dir.path().join("rupture_timeseries.csv")).unwrap();
+    assert_eq!(read(&flat), read(&sub));
+}
+
+#[test]
+fn test_validate_config_and_explain() {
+    let dir = TempDir::new().unwrap();
+    let bad = dir.path().join("bad.toml");
+    fs::write(
+        &bad,
+        DEFAULT_CONFIG_TOML.replace("alpha = 0.65", "alpha = 1.5"),
+    )
+    .unwrap();
+
+    engine()
+        .args(["validate-config", "configs/tiny.toml", "configs/daily.toml"])
+        .assert()
+        .success();
+    engine()
+        .args(["validate-config", "configs/tiny.toml"])
+        .arg(&bad)
+        .assert()
+        .failure()
+        .stdout(contains("memory.alpha"));
+
+    engine()
+        .args(["explain", "--config", "configs/tiny.toml"])
+        .assert()
+        .success()
+        .stdout(contains("min_rows = 50  # default: 600"));
+}
+
+#[test]
+fn test_init_config_and_diagnose() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("rupture.toml");
+
+    engine()
+        .arg("init-config")
+        .arg("--output")
+        .arg(&path)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&path).unwrap(), DEFAULT_CONFIG_TOML);
+    engine()
+        .arg("init-config")
+        .arg("--output")
+        .arg(&path)
+        .assert()
+        .failure();
+    engine()
+        .arg("init-config")
+        .arg("--output")
+        .arg(&path)
+        .arg("--force")
+        .assert()
+        .success();
+
+    engine()
+        .args([
+            "diagnose",
+            "--input",
+            FIXTURE,
+            "--config",
+            "configs/tiny.toml",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("60 bars"));
+    // The default min_rows is larger than the fixture.
+    engine()
+        .args(["diagnose", "--input", FIXTURE, "--config"])
+        .arg(&path)
+        .assert()
+        .failure();
+}
@@ -0,0 +1,67 @@
+//! Shared helpers for integration tests.
+#![allow(dead_code)]
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use rupture_engine::config::Config;
+use rupture_engine::types::{Bar, Timestamp};
+use std::path::Path;
+
+/// Deterministic synthetic bars with a burst of volatility in the middle.
+pub fn synthetic_bars(n: usize) -> Vec<Bar> {
+    let mut seed: u64 = 42;
+    let mut next = || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((seed >> 11) as f64 / (1u64 << 53) as f64) - 0.5
+    };
+    let mut close = 100.0;
+    (0..n)
+        .map(|i| {
+            let scale = if (n / 2..n / 2 + 20).contains(&i) {
+                0.08
+            } else {
+                0.01
+            };
+            let open = close;
+            close *= (scale * next()).exp();
+            Bar {
+                ts: format!("t{:05}", i).into(),
+                open,
+                high: open.max(close) * 1.001,
+                low: open.min(close) * 0.999,
+                close,
+                volume: 1_000_000.0 * (1.0 + next()),
+                filled: false,
+            }
+        })
+        .collect()
+}
+
+/// Fixture-scale config with short windows.
+pub fn tiny_config() -> Config {
+    Config::from_file(Path::new("configs/tiny.toml")).unwrap()
+}
+
+/// Assign consecutive weekday timestamps starting on 2005-01-03, skipping
+/// `halt` trading days after bar `halt_at`.
+pub fn with_weekday_timestamps(mut bars: Vec<Bar>, halt_at: usize, halt: usize) -> Vec<Bar> {
+    let mut date = NaiveDate::from_ymd_opt(2005, 1, 3).unwrap();
+    let next_weekday = |d: NaiveDate| {
+        let mut d = d + Duration::days(1);
+        while matches!(d.weekday(), Weekday::Sat | Weekday::Sun) {
+            d += Duration::days(1);
+        }
+        d
+    };
+    for (i, bar) in bars.iter_mut().enumerate() {
+        bar.ts = Timestamp::Parsed(date.and_hms_opt(0, 0, 0).unwrap().and_utc());
+        date = next_weekday(date);
+        if i == halt_at {
+            for _ in 0..halt {
+                date = next_weekday(date);
+            }
+        }
+    }
+    bars
+}
@@ -0,0 +1,111 @@
+use assert_cmd::prelude::*;
+use rupture_engine::config::Config;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+    vars.iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_missing_keys_take_defaults() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("partial.toml");
+    fs::write(&path, "[memory]\nalpha = 0.5\n").unwrap();
+
+    let config = Config::from_file(&path).unwrap();
+    let defaults = Config::default();
+    assert_eq!(config.memory.alpha, 0.5);
+    assert_eq!(config.memory.terms, defaults.memory.terms);
+    assert_eq!(
+        config.state_machine.confirm_k,
+        defaults.state_machine.confirm_k
+    );
+    assert_eq!(config.io.close_column, defaults.io.close_column);
+}
+
+#[test]
+fn test_extends_merges_over_base() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("assets")).unwrap();
+    fs::write(dir.path().join("weights.txt"), "10 9 8 7 6 5 4 3 2 1\n").unwrap();
+    fs::write(
+        dir.path().join("base.toml"),
+        "[windows]\nmemory_k = 10\n\n[memory]\nalpha = 0.55\nkernel = \"file\"\n\
+         weights_file = \"weights.txt\"\n\n[state_machine]\nconfirm_k = 2\n",
+    )
+    .unwrap();
+    let child = dir.path().join("assets/spy.toml");
+    fs::write(
+        &child,
+        "extends = \"../base.toml\"\n\n[state_machine]\nconfirm_m = 1\n",
+    )
+    .unwrap();
+
+    let config = Config::from_file(&child).unwrap();
+    assert_eq!(config.memory.alpha, 0.55);
+    assert_eq!(config.memory.weights.len(), 10);
+    assert_eq!(config.memory.weights[0], 10.0);
+    assert_eq!(config.state_machine.confirm_k, 2);
+    assert_eq!(config.state_machine.confirm_m, 1);
+
+    let a = dir.path().join("a.toml");
+    fs::write(&a, "extends = \"b.toml\"\n").unwrap();
+    fs::write(dir.path().join("b.toml"), "extends = \"a.toml\"\n").unwrap();
+    let err = Config::from_file(&a).unwrap_err().to_string();
+    assert!(err.contains("cycle"), "{}", err);
+}
+
+#[test]
+fn test_env_and_set_overrides() {
+    let path = std::path::Path::new("configs/tiny.toml");
+    let vars = env(&[
+        ("RUPTURE_STATE_MACHINE__CONFIRM_K", "2"),
+        ("RUPTURE_IO__TIMEZONE", "Europe/London"),
+        ("HOME", "/root"),
+        ("RUPTURE_HOME", "/opt/rupture"),
+    ]);
+
+    let config = Config::load_with_env(Some(path), vars.clone(), &[]).unwrap();
+    assert_eq!(config.state_machine.confirm_k, 2);
+    assert_eq!(config.io.timezone, "Europe/London");
+
+    let sets = ["state_machine.confirm_k=3".to_string()];
+    let config = Config::load_with_env(Some(path), vars, &sets).unwrap();
+    assert_eq!(config.state_machine.confirm_k, 3);
+
+    let none = Vec::new();
+    let typo = ["state_machine.confrm_k=2".to_string()];
+    let err = Config::load_with_env(Some(path), none.clone(), &typo)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("state_machine.confrm_k"), "{}", err);
+    assert!(Config::load_with_env(None, env(&[("RUPTURE_MEMORY__TYPO", "1")]), &[]).is_err());
+    assert!(Config::load_with_env(None, none.clone(), &["memory.alpha".into()]).is_err());
+    // Overrides are validated like the file.
+    assert!(Config::load_with_env(None, none, &["memory.alpha=1.5".into()]).is_err());
+}
+
+#[test]
+fn test_cli_set_reaches_config_snapshot() {
+    let dir = TempDir::new().unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("rupture-engine"))
+        .args(["--input", "data/fixtures/tiny_ohlcv_60.csv"])
+        .args(["--config", "configs/tiny.toml"])
+        .args(["--set", "state_machine.confirm_k=2"])
+        .args(["--set", "residuals.theta_vol=1.5"])
+        .arg("--output-dir")
+        .arg(dir.path())
+        .assert()
+        .success();
+
+    let snapshot: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("config_used.json")).unwrap())
+            .unwrap();
+    assert_eq!(snapshot["state_machine"]["confirm_k"], 2);
+    assert_eq!(snapshot["residuals"]["theta_vol"], 1.5);
+    assert_eq!(snapshot["memory"]["alpha"], 0.65);
+}
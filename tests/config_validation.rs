@@ -0,0 +1,202 @@
+use assert_cmd::prelude::*;
+use predicates::str::contains;
+use rupture_engine::config::{Config, Severity};
+use rupture_engine::error::RuptureError;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn fields(config: &Config, severity: Severity) -> Vec<String> {
+    config
+        .validation_report()
+        .issues
+        .into_iter()
+        .filter(|i| i.severity == severity)
+        .map(|i| i.field)
+        .collect()
+}
+
+#[test]
+fn test_report_collects_every_error() {
+    let mut config = Config::default();
+    config.state_machine.rho_critical = 1.2;
+    config.capacity.lambda = 0.0;
+    config.numerics.eps = 0.0;
+    config.numerics.residual_clip_max = -1.0;
+    config.state_machine.recovery_hold = 0;
+    config.outputs.json_config_snapshot_name = config.outputs.json_events_name.clone();
+    config.memory.alpha = 1.5;
+
+    let errors = fields(&config, Severity::Error);
+    for field in [
+        "numerics.eps",
+        "numerics.residual_clip_max",
+        "memory.alpha",
+        "capacity.lambda",
+        "state_machine.rho_critical",
+        "state_machine.recovery_hold",
+        "outputs.json_config_snapshot_name",
+    ] {
+        assert!(
+            errors.iter().any(|f| f == field),
+            "{} in {:?}",
+            field,
+            errors
+        );
+    }
+    assert_eq!(errors.len(), 7);
+
+    match config.validate() {
+        Err(RuptureError::InvalidConfig(report)) => {
+            assert_eq!(report.errors().count(), 7);
+            let message = report.to_string();
+            assert!(
+                message.contains("capacity.lambda: must be in (0, 1]"),
+                "{}",
+                message
+            );
+        }
+        other => panic!("expected InvalidConfig, got {:?}", other),
+    }
+
+    let mut contagion = Config::default();
+    let mut systemic = contagion.state_machine.clone();
+    systemic.rho_stressed = systemic.rho_rupture;
+    contagion.contagion.state_machine = Some(systemic);
+    assert_eq!(
+        fields(&contagion, Severity::Error),
+        vec!["contagion.state_machine.rho_critical"]
+    );
+}
+
+#[test]
+fn test_window_and_scale_ranges() {
+    let mut config = Config::default();
+    config.windows.robust_scale_n = 0;
+    config.robust.mad_scale_factor = -3.0;
+    config.capacity.e_min = -1.0;
+    assert_eq!(
+        fields(&config, Severity::Error),
+        vec![
+            "windows.robust_scale_n",
+            "robust.mad_scale_factor",
+            "capacity.e_min"
+        ]
+    );
+
+    config.windows.robust_scale_n = 2;
+    config.robust.mad_scale_factor = f64::NAN;
+    config.capacity.e_min = f64::INFINITY;
+    assert_eq!(
+        fields(&config, Severity::Error),
+        vec!["robust.mad_scale_factor", "capacity.e_min"]
+    );
+}
+
+#[test]
+fn test_warnings_do_not_fail_validation() {
+    let mut config = Config::default();
+    config.memory.alpha = 0.95;
+    config.memory.lambda = 0.1;
+    config.outputs.write_csv_timeseries = false;
+    config.outputs.write_json_events = false;
+    config.outputs.write_json_episodes = false;
+    config.outputs.write_jsonl_transitions = false;
+    config.outputs.write_json_config_snapshot = false;
+
+    config.validate().unwrap();
+    assert!(config.validation_report().is_valid());
+    assert_eq!(
+        fields(&config, Severity::Warning),
+        vec!["memory.alpha", "memory.lambda", "outputs"]
+    );
+    assert!(fields(&Config::default(), Severity::Warning).is_empty());
+}
+
+#[test]
+fn test_parquet_output_needs_the_feature() {
+    let mut config = Config::default();
+    config.outputs.write_parquet_timeseries = true;
+    let errors = fields(&config, Severity::Error);
+    if cfg!(feature = "parquet") {
+        assert!(errors.is_empty());
+    } else {
+        assert_eq!(errors, vec!["outputs.write_parquet_timeseries"]);
+    }
+}
+
+#[test]
+fn test_validate_config_lists_all_errors() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("bad.toml");
+    fs::write(
+        &path,
+        "[state_machine]\nrho_stressed = 0.9\nrecovery_hold = 0\n\n[capacity]\nlambda = 1.5\n",
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("rupture-engine"))
+        .arg("validate-config")
+        .arg(&path)
+        .assert()
+        .failure()
+        .stdout(contains("invalid"))
+        .stdout(contains("error: capacity.lambda"))
+        .stdout(contains("error: state_machine.rho_critical"))
+        .stdout(contains("error: state_machine.recovery_hold"));
+}
+
+#[test]
+fn test_state_machine_definition_checks() {
+    let mut config = Config::default();
+    let sm = &mut config.state_machine;
+    (sm.states, sm.transitions) = (sm.state_configs(), sm.transition_configs());
+    config.validate().unwrap();
+
+    let errors = |config: &Config| {
+        config
+            .validation_report()
+            .errors()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let mut broken = config.clone();
+    // Nothing leaves Recovery, and nothing enters it.
+    broken
+        .state_machine
+        .transitions
+        .retain(|t| t.from != "Recovery");
+    broken.state_machine.transitions[5].to = None;
+    let report = errors(&broken);
+    assert!(
+        report.contains("state_machine.states: 'Recovery' is unreachable"),
+        "{}",
+        report
+    );
+
+    let mut broken = config.clone();
+    broken
+        .state_machine
+        .transitions
+        .retain(|t| t.from != "Recovery");
+    let report = errors(&broken);
+    assert!(
+        report.contains("'Recovery' has no way back to a level"),
+        "{}",
+        report
+    );
+
+    let mut broken = config.clone();
+    broken.state_machine.states.swap(1, 2);
+    broken.state_machine.transitions[0].to = Some("Crtical".into());
+    broken.state_machine.transitions[3].to = None;
+    let report = errors(&broken);
+    for problem in [
+        "'Stressed' must have a higher enter_rho",
+        "no state 'Crtical'",
+        "'Candidate rupture' cannot take a confirmed transition",
+    ] {
+        assert!(report.contains(problem), "{} in {}", problem, report);
+    }
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use common::tiny_config;
+use rupture_engine::batch::{batch_contagion, batch_items, run_batch};
+use rupture_engine::config::{ContagionConfig, ContagionSignal};
+use rupture_engine::model::{contagion_index, AssetPoint};
+use rupture_engine::types::Timestamp;
+use std::fs;
+use tempfile::TempDir;
+
+fn point(ts: &str, rho: f64, critical: bool, candidate: bool) -> AssetPoint {
+    AssetPoint {
+        timestamp: Timestamp::from(ts),
+        rho,
+        critical,
+        candidate,
+    }
+}
+
+#[test]
+fn test_breadth_metrics_align_on_timestamp() {
+    let a = vec![point("t1", 0.5, false, false), point("t2", 2.0, true, true)];
+    let b = vec![
+        point("t2", 1.0, false, true),
+        point("t3", 0.2, false, false),
+    ];
+    let cfg = ContagionConfig {
+        rho_quantile: 1.0,
+        ..Default::default()
+    };
+    let (rows, _) = contagion_index(&[a, b], &cfg, &tiny_config().state_machine).unwrap();
+
+    assert_eq!(rows.len(), 3);
+    let t2 = &rows[1];
+    assert_eq!(t2.timestamp, Timestamp::from("t2"));
+    assert_eq!(t2.assets, 2);
+    assert!((t2.frac_critical - 0.5).abs() < 1e-12);
+    assert!((t2.mean_rho - 1.5).abs() < 1e-12);
+    assert!((t2.quantile_rho - 2.0).abs() < 1e-12);
+    assert_eq!(t2.candidates, 2);
+    assert_eq!(rows[2].assets, 1);
+
+    let cfg = ContagionConfig {
+        min_assets: 2,
+        ..Default::default()
+    };
+    let (rows, _) = contagion_index(
+        &[
+            vec![point("t1", 0.5, false, false), point("t2", 2.0, true, true)],
+            vec![point("t2", 1.0, false, true)],
+        ],
+        &cfg,
+        &tiny_config().state_machine,
+    )
+    .unwrap();
+    assert_eq!(rows.len(), 1);
+}
+
+#[test]
+fn test_systemic_rupture_from_breadth() {
+    let sm = tiny_config().state_machine;
+    let series: Vec<Vec<AssetPoint>> = (0..4)
+        .map(|asset| {
+            (0..40)
+                .map(|i| {
+                    // All assets go critical together from bar 20 to 30.
+                    let hit = (20..30).contains(&i) && asset < 3;
+                    point(
+                        &format!("t{:03}", i),
+                        if hit { 5.0 } else { 0.1 },
+                        hit,
+                        false,
+                    )
+                })
+                .collect()
+        })
+        .collect();
+
+    let cfg = ContagionConfig {
+        signal: ContagionSignal::Breadth,
+        state_machine: Some(rupture_engine::config::StateMachineConfig {
+            rho_stressed: 0.2,
+            rho_critical: 0.4,
+            rho_rupture: 0.5,
+            ..sm.clone()
+        }),
+        ..Default::default()
+    };
+    let mut config = tiny_config();
+    config.contagion = cfg.clone();
+    config.validate().unwrap();
+    // Breadth needs its own thresholds: [state_machine] is on the rho scale.
+    config.contagion.state_machine = None;
+    assert!(config.validate().is_err());
+
+    let (rows, events) = contagion_index(&series, &cfg, &sm).unwrap();
+    assert!((rows[25].signal - 0.75).abs() < 1e-12);
+    assert!(rows[..20].iter().all(|r| !r.candidate_flag));
+    assert!(!events.is_empty());
+    assert_eq!(events[0].candidate_timestamp, Timestamp::from("t020"));
+}
+
+#[test]
+fn test_batch_contagion_covers_all_timestamps() {
+    let input_dir = TempDir::new().unwrap();
+    let output_dir = TempDir::new().unwrap();
+    fs::copy(
+        "data/fixtures/tiny_ohlcv_60.csv",
+        input_dir.path().join("A.csv"),
+    )
+    .unwrap();
+    fs::copy(
+        "data/fixtures/tiny_ohlcv_60.csv",
+        input_dir.path().join("B.csv"),
+    )
+    .unwrap();
+
+    let mut config = tiny_config();
+    config.io.parse_timestamps = true;
+    config.contagion.enabled = true;
+    let items = batch_items(input_dir.path()).unwrap();
+    let outcomes = run_batch(&items, &config, output_dir.path(), 2);
+    let bars = outcomes[0].result.as_ref().unwrap().bars;
+
+    let (rows, _) = batch_contagion(&outcomes, &config).unwrap();
+    assert_eq!(rows.len(), bars);
+    assert!(rows.iter().all(|r| r.assets == 2));
+}
+
+#[test]
+fn test_contagion_aligns_raw_timestamps_in_time_order() {
+    let input_dir = TempDir::new().unwrap();
+    let output_dir = TempDir::new().unwrap();
+    // US-style dates, which sort differently as text: 1/10 before 1/2.
+    let fixture = fs::read_to_string("data/fixtures/tiny_ohlcv_60.csv").unwrap();
+    let us_dates: String = fixture
+        .lines()
+        .map(|line| {
+            let (date, rest) = line.split_once(',').unwrap();
+            let parts: Vec<u32> = date.split('-').filter_map(|p| p.parse().ok()).collect();
+            match parts[..] {
+                [y, m, d] => format!("{}/{}/{},{}\n", m, d, y, rest),
+                _ => format!("{}\n", line),
+            }
+        })
+        .collect();
+    fs::write(input_dir.path().join("A.csv"), &us_dates).unwrap();
+    fs::write(input_dir.path().join("B.csv"), &us_dates).unwrap();
+
+    let mut config = tiny_config();
+    config.contagion.enabled = true;
+    let err = config.validate().unwrap_err().to_string();
+    assert!(err.contains("contagion.enabled"), "{}", err);
+
+    config.io.parse_timestamps = true;
+    config.io.timestamp_format = "%m/%d/%Y".into();
+    config.validate().unwrap();
+    let items = batch_items(input_dir.path()).unwrap();
+    let outcomes = run_batch(&items, &config, output_dir.path(), 2);
+    let (rows, _) = batch_contagion(&outcomes, &config).unwrap();
+    assert_eq!(rows.len(), outcomes[0].result.as_ref().unwrap().bars);
+    assert!(rows.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+    assert_eq!(rows[0].timestamp.to_string(), "2005-01-03T00:00:00Z");
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use rupture_engine::features::{
+    compute_acceleration, compute_normed, compute_range_vol, compute_returns,
+};
+use rupture_engine::io::write_timeseries_csv;
+use rupture_engine::model::capacity::CapacityEstimator;
+use rupture_engine::model::combine::soft_max_combine;
+use rupture_engine::model::memory::MemoryKernel;
+use rupture_engine::model::residuals::{ChannelInput, ChannelSet};
+use rupture_engine::model::state_machine::StateMachine;
+use rupture_engine::model::{run_engine, RuptureEngine};
+use rupture_engine::reporting::state_distribution;
+use rupture_engine::types::RuptureState;
+use std::fs;
+use tempfile::TempDir;
+
+use common::{synthetic_bars, tiny_config};
+
+#[test]
+fn test_streaming_matches_batch() {
+    let config = tiny_config();
+    let bars = synthetic_bars(400);
+    let (batch_rows, batch_events) = run_engine(&bars, &config).unwrap();
+
+    let mut engine = RuptureEngine::new(&config).unwrap();
+    let mut streamed_events = Vec::new();
+    for (i, bar) in bars.iter().enumerate() {
+        let (row, event) = engine.update(bar).pop().unwrap();
+        let expected = &batch_rows[i];
+        assert_eq!(row.timestamp, expected.timestamp);
+        assert_eq!(row.strain.to_bits(), expected.strain.to_bits());
+        assert_eq!(row.capacity.to_bits(), expected.capacity.to_bits());
+        assert_eq!(row.rho.to_bits(), expected.rho.to_bits());
+        assert_eq!(row.state, expected.state);
+        streamed_events.extend(event);
+    }
+
+    assert_eq!(engine.bars_processed(), bars.len());
+    assert_eq!(streamed_events.len(), batch_events.len());
+    assert!(
+        !batch_events.is_empty(),
+        "Synthetic data should produce events"
+    );
+    for (s, b) in streamed_events.iter().zip(&batch_events) {
+        assert_eq!(s.candidate_index, b.candidate_index);
+        assert_eq!(s.confirmed_index, b.confirmed_index);
+        assert_eq!(s.peak_rho.to_bits(), b.peak_rho.to_bits());
+    }
+}
+
+#[test]
+fn test_streaming_matches_precomputed_features() {
+    // Reference pipeline built from the slice-based feature functions.
+    let config = tiny_config();
+    let bars = synthetic_bars(300);
+
+    let returns = compute_returns(&bars, config.features.use_log_returns);
+    let accel = compute_acceleration(&returns);
+    let range_vol = compute_range_vol(
+        &bars,
+        config.features.volatility_estimator,
+        config.windows.robust_scale_n,
+    );
+    let normed = compute_normed(
+        &bars,
+        &returns,
+        &accel,
+        &range_vol,
+        config.windows.robust_scale_n,
+        config.robust.mad_scale_factor,
+        config.numerics.eps,
+    );
+    let mut memory = MemoryKernel::new(config.windows.memory_k, config.memory.alpha);
+    let mut capacity = CapacityEstimator::new(
+        config.windows.capacity_l,
+        config.capacity.q,
+        config.capacity.smooth,
+        config.capacity.lambda,
+        config.capacity.e_min,
+    );
+    let mut sm = StateMachine::new(config.state_machine.clone()).unwrap();
+    let channels = ChannelSet::from_config(&config).unwrap();
+
+    let mut engine = RuptureEngine::new(&config).unwrap();
+    for (i, bar) in bars.iter().enumerate() {
+        let input = ChannelInput {
+            bar,
+            normed: &normed[i],
+            gap: 0,
+            eps: config.numerics.eps,
+        };
+        let res = channels.compute(&input, &config.numerics);
+        let r = soft_max_combine(res[0], res[1], res[2], config.combine.tau);
+        let strain = memory.push_and_accumulate(r);
+        let cap = capacity.update(strain);
+        let rho = strain / (cap + config.numerics.eps);
+        let (state, _, _) = sm.update(i, &bar.ts, rho);
+
+        let (row, _) = engine.update(bar).pop().unwrap();
+        assert_eq!(
+            row.r_combined.to_bits(),
+            r.to_bits(),
+            "r differs at bar {}",
+            i
+        );
+        assert_eq!(row.rho.to_bits(), rho.to_bits(), "rho differs at bar {}", i);
+        assert_eq!(row.state, state);
+    }
+}
+
+#[test]
+fn test_state_column_has_code_and_label() {
+    for state in RuptureState::ALL {
+        assert_eq!(serde_json::to_value(state).unwrap(), state.code());
+    }
+    assert_eq!(RuptureState::CandidateRupture.label(), "Candidate rupture");
+    assert_eq!(RuptureState::ConfirmedRupture.label(), "Confirmed rupture");
+
+    let config = tiny_config();
+    let (rows, _) = run_engine(&synthetic_bars(400), &config).unwrap();
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("ts.csv");
+    write_timeseries_csv(&rows, &config.residual_channel_names(), &path).unwrap();
+    let csv = fs::read_to_string(&path).unwrap();
+    let mut lines = csv.lines();
+    let header = lines.next().unwrap();
+    assert!(header.contains(",rho,state_code,state,candidate,"));
+    for (row, line) in rows.iter().zip(lines) {
+        assert_eq!(row.state_label, row.state.label());
+        let expected = format!(",{},{},", row.state.code(), row.state_label);
+        assert!(line.contains(&expected), "{}", line);
+    }
+
+    let distribution = state_distribution(&rows);
+    let labels: Vec<&str> = distribution.iter().map(|(l, _)| l.as_str()).collect();
+    let expected: Vec<&str> = RuptureState::ALL
+        .iter()
+        .map(|s| s.label())
+        .filter(|label| labels.contains(label))
+        .collect();
+    assert_eq!(labels, expected);
+    assert!(labels.len() > 2);
+    let total: usize = distribution.iter().map(|(_, n)| n).sum();
+    assert_eq!(total, rows.len());
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::{synthetic_bars, tiny_config};
+use rupture_engine::config::StateMachineConfig;
+use rupture_engine::model::run_engine_with_episodes;
+use rupture_engine::model::state_machine::StateMachine;
+use rupture_engine::pipeline::write_outputs;
+use rupture_engine::types::{EpisodeEnd, RuptureState, Timestamp};
+use std::fs;
+use tempfile::TempDir;
+
+fn cfg() -> StateMachineConfig {
+    StateMachineConfig {
+        confirm_k: 3,
+        confirm_m: 2,
+        recovery_hold: 2,
+        ..Default::default()
+    }
+}
+
+fn run(cfg: StateMachineConfig, rhos: &[f64]) -> (StateMachine, Vec<RuptureState>) {
+    let mut sm = StateMachine::new(cfg).unwrap();
+    let states = rhos
+        .iter()
+        .enumerate()
+        .map(|(i, &rho)| sm.update(i, format!("t{}", i), rho).0)
+        .collect();
+    (sm, states)
+}
+
+#[test]
+fn test_recovery_phase_has_its_own_exit() {
+    let cfg = StateMachineConfig {
+        recovery_exit_rho: Some(0.5),
+        recovery_exit_bars: 2,
+        ..cfg()
+    };
+    let rhos = [0.5, 1.5, 1.3, 1.1, 1.6, 0.7, 0.6, 0.55, 0.4, 0.3, 0.2];
+    let (sm, states) = run(cfg, &rhos);
+
+    use RuptureState::*;
+    assert_eq!(
+        states,
+        vec![
+            Stable,
+            CandidateRupture,
+            CandidateRupture,
+            ConfirmedRupture,
+            ConfirmedRupture,
+            ConfirmedRupture,
+            Recovery,
+            Recovery,
+            Recovery,
+            Stable,
+            Stable
+        ]
+    );
+
+    let episode = &sm.episodes()[0];
+    assert_eq!(sm.episodes().len(), 1);
+    assert_eq!(episode.start_index, 1);
+    assert_eq!(episode.confirmed_index, 3);
+    assert_eq!(episode.peak_index, 4);
+    assert_eq!(episode.peak_timestamp, Timestamp::from("t4"));
+    assert_eq!(episode.peak_rho, 1.6);
+    assert_eq!(episode.recovery_index, Some(6));
+    assert_eq!(episode.end_index, Some(9));
+    assert_eq!(episode.end_timestamp, Some(Timestamp::from("t9")));
+    assert_eq!(episode.end_reason, Some(EpisodeEnd::Recovered));
+    assert_eq!(episode.recovery_bars, Some(3));
+    assert_eq!(episode.duration_bars, Some(8));
+}
+
+#[test]
+fn test_new_rupture_during_recovery_and_without_recovery() {
+    let rhos = [0.5, 1.5, 1.3, 1.1, 0.7, 0.6, 0.9, 1.2, 1.4, 1.3, 1.2];
+    let recovery_exit = StateMachineConfig {
+        recovery_exit_rho: Some(0.5),
+        ..cfg()
+    };
+    let (sm, states) = run(recovery_exit, &rhos);
+    assert_eq!(states[5], RuptureState::Recovery);
+    assert_eq!(states[6], RuptureState::Recovery);
+    assert_eq!(states[7], RuptureState::CandidateRupture);
+    assert_eq!(states[9], RuptureState::ConfirmedRupture);
+
+    let episodes = sm.episodes();
+    assert_eq!(episodes.len(), 2);
+    assert_eq!(episodes[0].end_index, Some(7));
+    assert_eq!(episodes[0].end_reason, Some(EpisodeEnd::NewRupture));
+    assert_eq!(episodes[1].start_index, 7);
+    assert_eq!(episodes[1].end_index, None);
+    assert_eq!(sm.events().len(), 2);
+
+    // Without a Recovery phase the episode ends once the recovery rule is
+    // met, instead of the machine staying in ConfirmedRupture.
+    let (sm, states) = run(
+        StateMachineConfig {
+            enable_recovery: false,
+            ..cfg()
+        },
+        &rhos[..7],
+    );
+    assert_eq!(states[5], RuptureState::Stressed);
+    assert_eq!(states[6], RuptureState::Critical);
+    assert_eq!(sm.episodes()[0].end_index, Some(5));
+    assert_eq!(sm.episodes()[0].recovery_index, None);
+}
+
+#[test]
+fn test_engine_episodes_match_confirmed_events() {
+    let config = tiny_config();
+    let run = run_engine_with_episodes(&synthetic_bars(400), &config).unwrap();
+    let confirmed: Vec<_> = run
+        .events
+        .iter()
+        .filter(|e| e.confirmed_index.is_some())
+        .collect();
+    assert!(!confirmed.is_empty());
+    assert_eq!(run.episodes.len(), confirmed.len());
+    for (episode, event) in run.episodes.iter().zip(&confirmed) {
+        assert_eq!(episode.start_index, event.candidate_index);
+        assert_eq!(Some(episode.confirmed_index), event.confirmed_index);
+        assert!(episode.peak_rho >= event.peak_rho);
+        assert_eq!(episode.recovery_index, event.recovery_index);
+    }
+
+    let dir = TempDir::new().unwrap();
+    write_outputs(&run, &config, dir.path()).unwrap();
+    let json: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(dir.path().join("rupture_episodes.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(json.as_array().unwrap().len(), run.episodes.len());
+    assert!(json[0]["end_reason"].is_string() || json[0]["end_reason"].is_null());
+}
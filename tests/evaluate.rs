@@ -0,0 +1,142 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use chrono::Duration;
+use common::{synthetic_bars, tiny_config, with_weekday_timestamps};
+use rupture_engine::evaluate::{evaluation_report, LabelInterval, Tolerance};
+use rupture_engine::model::run_engine;
+use rupture_engine::types::Timestamp;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn label(name: &str, start: &Timestamp, end: &Timestamp) -> LabelInterval {
+    LabelInterval {
+        name: name.into(),
+        start: start.clone(),
+        end: end.clone(),
+    }
+}
+
+#[test]
+fn test_tolerance_parsing() {
+    assert_eq!("5".parse::<Tolerance>().unwrap(), Tolerance::Bars(5));
+    assert_eq!(
+        "10d".parse::<Tolerance>().unwrap(),
+        Tolerance::Duration(Duration::days(10))
+    );
+    assert!("soon".parse::<Tolerance>().is_err());
+}
+
+#[test]
+fn test_report_leads_misses_and_false_alarms() {
+    let bars = with_weekday_timestamps(synthetic_bars(400), 0, 0);
+    let timestamps: Vec<Timestamp> = bars.iter().map(|b| b.ts.clone()).collect();
+    let (_, events) = run_engine(&bars, &tiny_config()).unwrap();
+    let confirmed: Vec<_> = events
+        .iter()
+        .filter(|e| e.confirmed_index.is_some())
+        .collect();
+    let hit = confirmed.iter().find(|e| e.candidate_index >= 200).unwrap();
+    let c = hit.candidate_index;
+
+    // The crisis label starts three bars after the candidate bar, so the
+    // rupture is only a hit with tolerance, and leads by three bars.
+    let after = Timestamp::from(timestamps[399].as_datetime().unwrap() + Duration::days(30));
+    let labels = vec![
+        label("crisis", &timestamps[c + 3], &timestamps[c + 10]),
+        label("quiet", &timestamps[20], &timestamps[21]),
+        label("after_data", &after, &after),
+    ];
+
+    let exact =
+        evaluation_report(&events, &timestamps, &labels, Tolerance::Bars(0), 252.0).unwrap();
+    assert_eq!(exact.counts.labels, 2);
+    assert!(exact.labels[0].detection.is_none());
+
+    for tolerance in [Tolerance::Bars(3), Tolerance::Duration(Duration::days(7))] {
+        let report = evaluation_report(&events, &timestamps, &labels, tolerance, 252.0).unwrap();
+        assert_eq!(report.counts.labels_hit, 1);
+        assert_eq!(report.hit_rate, 0.5);
+        assert_eq!(report.missed, vec!["quiet".to_string()]);
+
+        let detection = report.labels[0].detection.as_ref().unwrap();
+        assert_eq!(detection.candidate_index, c);
+        assert_eq!(detection.lead_bars, 3);
+        assert!(detection.confirmation_lead_bars < 3);
+        assert!(detection.lead_days.unwrap() >= 3.0);
+        assert_eq!(report.lead_bars.unwrap().median, 3.0);
+
+        assert_eq!(report.false_alarms.len(), confirmed.len() - 1);
+        let years = (399.0 / 5.0 * 7.0 - 2.0) / 365.25;
+        assert!((report.years - years).abs() < 0.01, "{}", report.years);
+        let per_year = report.false_alarms_per_year.unwrap();
+        assert!((per_year - report.false_alarms.len() as f64 / report.years).abs() < 1e-12);
+    }
+
+    let raw: Vec<Timestamp> = synthetic_bars(400).iter().map(|b| b.ts.clone()).collect();
+    let raw_labels = vec![label("crisis", &raw[c], &raw[c + 3])];
+    let duration = Tolerance::Duration(Duration::days(7));
+    assert!(evaluation_report(&events, &raw, &raw_labels, duration, 252.0).is_err());
+    let report = evaluation_report(&events, &raw, &raw_labels, Tolerance::Bars(0), 252.0).unwrap();
+    assert!((report.years - 400.0 / 252.0).abs() < 1e-12);
+    assert!(report.labels[0]
+        .detection
+        .as_ref()
+        .unwrap()
+        .lead_days
+        .is_none());
+}
+
+#[test]
+fn test_unsorted_timestamps_and_empty_span() {
+    let bars = synthetic_bars(400);
+    let (_, events) = run_engine(&bars, &tiny_config()).unwrap();
+    // Raw US-style dates are not in time order as text.
+    let raw: Vec<Timestamp> = (0..400)
+        .map(|i| Timestamp::from(format!("{}/{}/2008", 1 + i / 28, 1 + i % 28).as_str()))
+        .collect();
+    let labels = vec![label("crisis", &raw[30], &raw[40])];
+    let err = evaluation_report(&events, &raw, &labels, Tolerance::Bars(0), 252.0)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("io.parse_timestamps"), "{}", err);
+
+    let one = with_weekday_timestamps(synthetic_bars(1), 0, 0);
+    let timestamps = vec![one[0].ts.clone()];
+    let labels = vec![label("crisis", &timestamps[0], &timestamps[0])];
+    let report = evaluation_report(&[], &timestamps, &labels, Tolerance::Bars(0), 252.0).unwrap();
+    assert_eq!(report.years, 0.0);
+    assert_eq!(report.false_alarms_per_year, None);
+}
+
+#[test]
+fn test_evaluate_cli_writes_report() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("labels.csv"),
+        "name,start,end\nearly,2005-01-10,2005-02-20\nlate,2030-01-01,2030-02-01\n",
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("rupture-engine"))
+        .arg("evaluate")
+        .args(["--input", "data/fixtures/tiny_ohlcv_60.csv"])
+        .args(["--config", "configs/tiny.toml"])
+        .arg("--labels")
+        .arg(dir.path().join("labels.csv"))
+        .args(["--tolerance", "2"])
+        .arg("--output-dir")
+        .arg(dir.path())
+        .assert()
+        .success();
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("evaluation.json")).unwrap())
+            .unwrap();
+    assert_eq!(report["labels"].as_array().unwrap().len(), 1);
+    assert_eq!(report["labels"][0]["name"], "early");
+    assert_eq!(report["tolerance"], "2 bars");
+    assert_eq!(report["counts"]["labels"], 1);
+    assert!(report["false_alarms_per_year"].is_number());
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::{synthetic_bars, tiny_config};
+use rupture_engine::model::{run_engine, run_engine_with_episodes};
+use rupture_engine::types::RuptureState;
+
+#[test]
+fn test_event_attribution_decomposes_strain() {
+    let (rows, events) = run_engine(&synthetic_bars(600), &tiny_config()).unwrap();
+    assert!(!events.is_empty());
+
+    for event in &events {
+        for (index, breakdown) in [
+            (event.candidate_index, &event.candidate_attribution),
+            (event.peak_index, &event.peak_attribution),
+        ] {
+            let names: Vec<&str> = breakdown.iter().map(|c| c.channel.as_str()).collect();
+            assert_eq!(names, vec!["vol", "liq", "acc"]);
+            let total: f64 = breakdown.iter().map(|c| c.strain).sum();
+            assert!((total - rows[index].strain).abs() < 1e-9 * rows[index].strain.max(1.0));
+            let shares: f64 = breakdown.iter().map(|c| c.share).sum();
+            assert!((shares - 1.0).abs() < 1e-9);
+        }
+        assert_eq!(rows[event.peak_index].rho, event.peak_rho);
+        assert!(event.peak_index >= event.candidate_index);
+    }
+}
+
+#[test]
+fn test_confirmed_event_duration_until_recovery() {
+    let (rows, events) = run_engine(&synthetic_bars(600), &tiny_config()).unwrap();
+    let recovered: Vec<_> = events
+        .iter()
+        .filter(|e| e.recovery_index.is_some())
+        .collect();
+    assert!(!recovered.is_empty());
+
+    for event in &events {
+        match event.recovery_index {
+            Some(recovery) => {
+                assert!(event.confirmed_index.is_some());
+                assert_eq!(rows[recovery].state, RuptureState::Recovery);
+                assert_eq!(rows[recovery - 1].state, RuptureState::ConfirmedRupture);
+                assert_eq!(event.duration_bars, Some(recovery - event.candidate_index));
+            }
+            None => assert_eq!(event.duration_bars, None),
+        }
+    }
+}
+
+#[test]
+fn test_event_duration_without_recovery_phase() {
+    let mut config = tiny_config();
+    config.state_machine.enable_recovery = false;
+    let run = run_engine_with_episodes(&synthetic_bars(600), &config).unwrap();
+    let confirmed: Vec<_> = run
+        .events
+        .iter()
+        .filter(|e| e.confirmed_index.is_some())
+        .collect();
+    assert_eq!(confirmed.len(), run.episodes.len());
+    assert!(run.episodes.iter().any(|e| e.end_index.is_some()));
+
+    for (event, episode) in confirmed.iter().zip(&run.episodes) {
+        assert_eq!(episode.recovery_index, None);
+        assert_eq!(event.recovery_index, episode.end_index);
+        assert_eq!(event.duration_bars, episode.duration_bars);
+        if let Some(end) = episode.end_index {
+            assert_eq!(run.rows[end - 1].state, RuptureState::ConfirmedRupture);
+            assert!(run.rows[end].state.is_level());
+        }
+    }
+}
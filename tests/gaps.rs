@@ -0,0 +1,142 @@
+mod common;
+
+use common::{synthetic_bars, tiny_config, with_weekday_timestamps};
+use rupture_engine::config::{Config, GapCalendar, GapPolicy};
+use rupture_engine::model::checkpoint::unprocessed_bars;
+use rupture_engine::model::{run_engine, Checkpoint, RuptureEngine};
+use rupture_engine::preprocess::{detect_gaps, fill_gaps, GapDetector};
+use rupture_engine::types::{EngineOutputRow, RuptureEvent};
+
+const HALT_AT: usize = 150;
+const HALT: usize = 5;
+
+fn gap_config(policy: GapPolicy) -> Config {
+    let mut config = tiny_config();
+    config.io.parse_timestamps = true;
+    config.gaps.enabled = true;
+    config.gaps.expected_interval = "1d".into();
+    config.gaps.calendar = GapCalendar::Weekdays;
+    config.gaps.policy = policy;
+    config
+}
+
+#[test]
+fn test_gap_detected_and_flagged() {
+    let config = gap_config(GapPolicy::Flag);
+    let bars = with_weekday_timestamps(synthetic_bars(300), HALT_AT, HALT);
+
+    let detector = GapDetector::from_config(&config.gaps, &config.io).unwrap();
+    let gaps = detect_gaps(&bars, &detector);
+    assert_eq!(gaps.len(), 1);
+    assert_eq!(gaps[0].index, HALT_AT + 1);
+    assert_eq!(gaps[0].missing, HALT);
+
+    let (rows, _) = run_engine(&bars, &config).unwrap();
+    assert_eq!(rows[HALT_AT + 1].gap, HALT);
+    assert_eq!(rows.iter().filter(|r| r.gap > 0).count(), 1);
+
+    // Flagging alone does not change the model output.
+    let mut plain = config.clone();
+    plain.gaps.enabled = false;
+    let (plain_rows, _) = run_engine(&bars, &plain).unwrap();
+    for (a, b) in rows.iter().zip(&plain_rows) {
+        assert_eq!(a.rho.to_bits(), b.rho.to_bits());
+    }
+}
+
+#[test]
+fn test_decay_policy_reduces_strain_after_gap() {
+    let bars = with_weekday_timestamps(synthetic_bars(300), HALT_AT, HALT);
+    let (flagged, _) = run_engine(&bars, &gap_config(GapPolicy::Flag)).unwrap();
+    let (decayed, _) = run_engine(&bars, &gap_config(GapPolicy::Decay)).unwrap();
+
+    for i in 0..=HALT_AT {
+        assert_eq!(flagged[i].strain.to_bits(), decayed[i].strain.to_bits());
+    }
+    assert!(decayed[HALT_AT + 1].strain < flagged[HALT_AT + 1].strain);
+}
+
+#[test]
+fn test_reset_windows_zeroes_return_across_gap() {
+    let bars = with_weekday_timestamps(synthetic_bars(300), HALT_AT, HALT);
+    let (rows, _) = run_engine(&bars, &gap_config(GapPolicy::ResetWindows)).unwrap();
+    let after = &rows[HALT_AT + 1];
+    assert_eq!(after.residuals[0], 0.0);
+    assert_eq!(after.residuals[2], 0.0);
+}
+
+#[test]
+fn test_forward_fill_inserts_marked_bars() {
+    let config = gap_config(GapPolicy::ForwardFill);
+    let bars = with_weekday_timestamps(synthetic_bars(300), HALT_AT, HALT);
+    let detector = GapDetector::from_config(&config.gaps, &config.io).unwrap();
+
+    let filled = fill_gaps(bars.clone(), &detector);
+    assert_eq!(filled.len(), bars.len() + HALT);
+    assert!(detect_gaps(&filled, &detector).is_empty());
+    for bar in &filled[HALT_AT + 1..=HALT_AT + HALT] {
+        assert!(bar.filled);
+        assert_eq!(bar.close, bars[HALT_AT].close);
+    }
+
+    let (rows, _) = run_engine(&filled, &config).unwrap();
+    assert_eq!(rows.iter().filter(|r| r.filled).count(), HALT);
+    // The engine fills the gap itself; filling beforehand changes nothing.
+    let (unfilled_rows, _) = run_engine(&bars, &config).unwrap();
+    let rho =
+        |rows: &[EngineOutputRow]| -> Vec<u64> { rows.iter().map(|r| r.rho.to_bits()).collect() };
+    assert_eq!(rho(&unfilled_rows), rho(&rows));
+}
+
+#[test]
+fn test_forward_fill_streaming_and_resume_match_batch() {
+    let config = gap_config(GapPolicy::ForwardFill);
+    let bars = with_weekday_timestamps(synthetic_bars(300), HALT_AT, HALT);
+    let (batch, batch_events) = run_engine(&bars, &config).unwrap();
+    assert_eq!(batch.len(), bars.len() + HALT);
+
+    let mut engine = RuptureEngine::new(&config).unwrap();
+    let mut streamed = Vec::new();
+    for (i, bar) in bars.iter().enumerate() {
+        let out = engine.update(bar);
+        assert_eq!(out.len(), if i == HALT_AT + 1 { HALT + 1 } else { 1 });
+        assert_eq!(out.last().unwrap().0.timestamp, bar.ts);
+        streamed.extend(out.into_iter().map(|(row, _)| row));
+    }
+    let key = |r: &EngineOutputRow| (r.timestamp.clone(), r.filled, r.gap, r.rho.to_bits());
+    assert_eq!(
+        streamed.iter().map(key).collect::<Vec<_>>(),
+        batch.iter().map(key).collect::<Vec<_>>()
+    );
+    let candidates = |events: &[RuptureEvent]| -> Vec<usize> {
+        events.iter().map(|e| e.candidate_index).collect()
+    };
+    assert_eq!(candidates(engine.events()), candidates(&batch_events));
+
+    // Resuming from a checkpoint taken just before the gap fills it too.
+    let mut first = RuptureEngine::new(&config).unwrap();
+    for bar in &bars[..=HALT_AT] {
+        first.update(bar);
+    }
+    let json = serde_json::to_string(&Checkpoint::new(&first)).unwrap();
+    let checkpoint: Checkpoint = serde_json::from_str(&json).unwrap();
+    let mut resumed = checkpoint.into_engine(&config).unwrap();
+    let new_bars = unprocessed_bars(&resumed, &bars).unwrap();
+    let resumed_rows: Vec<_> = new_bars
+        .iter()
+        .flat_map(|bar| resumed.update(bar))
+        .map(|(row, _)| row)
+        .collect();
+    assert_eq!(
+        resumed_rows.iter().map(key).collect::<Vec<_>>(),
+        batch[HALT_AT + 1..].iter().map(key).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_engine_reports_invalid_gap_settings() {
+    let mut config = gap_config(GapPolicy::Flag);
+    config.gaps.holidays = vec!["2005-02-30".into()];
+    let err = RuptureEngine::new(&config).unwrap_err().to_string();
+    assert!(err.contains("gaps.holidays"), "{}", err);
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::{synthetic_bars, tiny_config};
+use rupture_engine::config::{MemoryConfig, MemoryMethod};
+use rupture_engine::model::memory::MemoryKernel;
+use rupture_engine::model::run_engine;
+
+fn soe(alpha: f64) -> MemoryConfig {
+    MemoryConfig {
+        alpha,
+        method: MemoryMethod::SumOfExponentials,
+        terms: 64,
+        ..tiny_config().memory
+    }
+}
+
+#[test]
+fn test_reported_error_is_small_for_long_kernels() {
+    for k in [10, 200, 5000, 20000] {
+        for alpha in [0.3, 0.55, 0.65, 0.9] {
+            let error = MemoryKernel::from_config(k, &soe(alpha))
+                .approximation_error()
+                .unwrap();
+            assert!(error.l1 < 1e-4, "K={} alpha={} l1={:e}", k, alpha, error.l1);
+        }
+    }
+    let exact = MemoryConfig {
+        alpha: 0.65,
+        method: MemoryMethod::Exact,
+        terms: 64,
+        ..tiny_config().memory
+    };
+    assert!(MemoryKernel::from_config(200, &exact)
+        .approximation_error()
+        .is_none());
+}
+
+#[test]
+fn test_strain_within_reported_bound() {
+    let k = 500;
+    let mut exact = MemoryKernel::new(k, 0.6);
+    let mut approx = MemoryKernel::from_config(k, &soe(0.6));
+    let l1 = approx.approximation_error().unwrap().l1;
+
+    let mut seed: u64 = 7;
+    for i in 0..3000 {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        // Bursty residuals in [0, 5).
+        let r = if (seed >> 60) == 0 {
+            5.0 * (seed >> 11) as f64 / (1u64 << 53) as f64
+        } else {
+            0.0
+        };
+        let s_exact = exact.push_and_accumulate(r);
+        let s_approx = approx.push_and_accumulate(r);
+        assert!(
+            (s_exact - s_approx).abs() <= l1 * 5.0 + 1e-12,
+            "bar {}: {} vs {}",
+            i,
+            s_exact,
+            s_approx
+        );
+    }
+}
+
+#[test]
+fn test_engine_with_approximate_kernel_tracks_exact() {
+    let bars = synthetic_bars(600);
+    let config = tiny_config();
+    let mut approx = config.clone();
+    approx.memory.method = MemoryMethod::SumOfExponentials;
+    approx.validate().unwrap();
+
+    let (exact_rows, _) = run_engine(&bars, &config).unwrap();
+    let (approx_rows, _) = run_engine(&bars, &approx).unwrap();
+    for (e, a) in exact_rows.iter().zip(&approx_rows) {
+        assert!((e.strain - a.strain).abs() < 1e-4 * e.strain.max(1.0));
+    }
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::{synthetic_bars, tiny_config};
+use rupture_engine::config::{Config, KernelFamily, MemoryMethod};
+use rupture_engine::model::memory::MemoryKernel;
+use rupture_engine::model::run_engine;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_family_weights() {
+    let mut cfg = tiny_config().memory;
+    cfg.alpha = 0.6;
+    cfg.lambda = 0.05;
+    let k = 100;
+
+    for family in [
+        KernelFamily::PowerLaw,
+        KernelFamily::Exponential,
+        KernelFamily::Fractional,
+        KernelFamily::TemperedPowerLaw,
+    ] {
+        cfg.kernel = family;
+        let w = MemoryKernel::from_config(k, &cfg).weights().to_vec();
+        assert_eq!(w.len(), k);
+        assert!((w.iter().sum::<f64>() - 1.0).abs() < 1e-12, "{:?}", family);
+        assert!(w.windows(2).all(|p| p[1] < p[0]), "{:?}", family);
+    }
+
+    cfg.kernel = KernelFamily::Exponential;
+    let w = MemoryKernel::from_config(k, &cfg).weights().to_vec();
+    for p in w.windows(2) {
+        assert!((p[1] / p[0] - (-0.05_f64).exp()).abs() < 1e-12);
+    }
+
+    // Grünwald–Letnikov weights decay like k^(-alpha) / Gamma(1 - alpha).
+    cfg.kernel = KernelFamily::Fractional;
+    let w = MemoryKernel::from_config(k, &cfg).weights().to_vec();
+    let tail = w[99] / w[49];
+    assert!(
+        (tail - 2.0_f64.powf(-0.6)).abs() < 1e-2,
+        "tail ratio {}",
+        tail
+    );
+
+    // No tempering reduces to the power law exactly.
+    cfg.kernel = KernelFamily::TemperedPowerLaw;
+    cfg.lambda = 0.0;
+    assert_eq!(
+        MemoryKernel::from_config(k, &cfg).weights(),
+        MemoryKernel::new(k, 0.6).weights()
+    );
+}
+
+#[test]
+fn test_tempered_sum_of_exponentials() {
+    let mut cfg = tiny_config().memory;
+    cfg.kernel = KernelFamily::TemperedPowerLaw;
+    cfg.lambda = 0.01;
+    cfg.method = MemoryMethod::SumOfExponentials;
+    for k in [200, 5000] {
+        let error = MemoryKernel::from_config(k, &cfg)
+            .approximation_error()
+            .unwrap();
+        assert!(error.l1 < 1e-4, "K={} l1={:e}", k, error.l1);
+    }
+
+    let mut config = tiny_config();
+    config.memory.kernel = KernelFamily::Fractional;
+    config.memory.method = MemoryMethod::SumOfExponentials;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_weights_file_kernel() {
+    let dir = TempDir::new().unwrap();
+    let base = fs::read_to_string("configs/tiny.toml").unwrap();
+    let k = tiny_config().windows.memory_k;
+
+    // Flat weights written as a comma list with a comment.
+    let weights = vec!["1.0"; k].join(", ");
+    fs::write(dir.path().join("w.csv"), format!("# flat\n{}\n", weights)).unwrap();
+    let toml = base.replace(
+        "[memory]",
+        "[memory]\nkernel = \"file\"\nweights_file = \"w.csv\"",
+    );
+    fs::write(dir.path().join("cfg.toml"), &toml).unwrap();
+
+    let config = Config::from_file(&dir.path().join("cfg.toml")).unwrap();
+    assert_eq!(config.memory.weights.len(), k);
+    let kernel = MemoryKernel::from_config(k, &config.memory);
+    assert!(kernel
+        .weights()
+        .iter()
+        .all(|w| (w - 1.0 / k as f64).abs() < 1e-15));
+
+    let (rows, _) = run_engine(&synthetic_bars(300), &config).unwrap();
+    assert_eq!(rows.len(), 300);
+
+    fs::write(dir.path().join("w.csv"), "1.0 0.5 0.25\n").unwrap();
+    let err = Config::from_file(&dir.path().join("cfg.toml")).unwrap_err();
+    assert!(err.to_string().contains("memory.weights"), "{}", err);
+}
@@ -0,0 +1,100 @@
+use rupture_engine::features::Normaliser;
+use rupture_engine::model::capacity::CapacityEstimator;
+use rupture_engine::stats::{mad, median, quantile, OrderedWindow, RollingWindow};
+
+/// Deterministic values in [-5, 5) rounded to a coarse grid so that ties occur.
+fn values(n: usize, seed: u64) -> Vec<f64> {
+    let mut state = seed;
+    (0..n)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let u = (state >> 11) as f64 / (1u64 << 53) as f64;
+            (u * 40.0).floor() / 4.0 - 5.0
+        })
+        .collect()
+}
+
+#[test]
+fn test_matches_slice_statistics() {
+    for capacity in [1, 2, 3, 5, 20, 101] {
+        let mut ordered = OrderedWindow::new(capacity);
+        let mut rolling = RollingWindow::new(capacity);
+        for (i, v) in values(500, capacity as u64).into_iter().enumerate() {
+            ordered.push(v);
+            rolling.push(v);
+            let mut snapshot = rolling.as_slice();
+            assert_eq!(ordered.as_slice(), snapshot);
+            assert_eq!(
+                ordered.mad().to_bits(),
+                mad(&snapshot).to_bits(),
+                "mad {} {}",
+                capacity,
+                i
+            );
+            for q in [0.0, 0.1, 0.5, 0.95, 1.0] {
+                assert_eq!(
+                    ordered.quantile(q).to_bits(),
+                    quantile(&snapshot, q).to_bits(),
+                    "quantile {} {} {}",
+                    q,
+                    capacity,
+                    i
+                );
+            }
+            assert_eq!(ordered.median().to_bits(), median(&mut snapshot).to_bits());
+        }
+    }
+}
+
+#[test]
+fn test_serialises_like_rolling_window() {
+    let mut ordered = OrderedWindow::new(7);
+    let mut rolling = RollingWindow::new(7);
+    for v in values(20, 3) {
+        ordered.push(v);
+        rolling.push(v);
+    }
+    let json = serde_json::to_string(&ordered).unwrap();
+    assert_eq!(json, serde_json::to_string(&rolling).unwrap());
+
+    let mut restored: OrderedWindow = serde_json::from_str(&json).unwrap();
+    for v in values(20, 4) {
+        ordered.push(v);
+        restored.push(v);
+        assert_eq!(restored.mad().to_bits(), ordered.mad().to_bits());
+        assert_eq!(
+            restored.quantile(0.9).to_bits(),
+            ordered.quantile(0.9).to_bits()
+        );
+    }
+}
+
+#[test]
+fn test_engine_stages_match_slice_functions() {
+    let (n, factor, eps) = (20, 1.4826, 1e-12);
+    let mut normaliser = Normaliser::new(n, factor, eps);
+    let mut ret_win = RollingWindow::new(n);
+    let mut vol_win = RollingWindow::new(n);
+
+    let mut capacity = CapacityEstimator::new(50, 0.95, false, 0.05, 1e-6);
+    let mut strain_win = RollingWindow::new(50);
+
+    let rets = values(400, 11);
+    let vols: Vec<f64> = values(400, 12).iter().map(|v| 1000.0 + 100.0 * v).collect();
+    for i in 0..rets.len() {
+        let normed = normaliser.update(rets[i], 0.0, vols[i], 0.0);
+        ret_win.push(rets[i]);
+        vol_win.push(vols[i]);
+        let sigma = factor * mad(&ret_win.as_slice()) + eps;
+        let vol_med = median(&mut vol_win.as_slice()) + eps;
+        assert_eq!(normed.u.to_bits(), (rets[i].abs() / sigma).to_bits());
+        assert_eq!(normed.v.to_bits(), (vols[i] / vol_med).to_bits());
+
+        let strain = rets[i].abs();
+        strain_win.push(strain);
+        let expected = quantile(&strain_win.as_slice(), 0.95).max(1e-6);
+        assert_eq!(capacity.update(strain).to_bits(), expected.to_bits());
+    }
+}
@@ -0,0 +1,174 @@
+#![cfg(feature = "parquet")]
+
+mod common;
+
+use arrow::array::{
+    Array, AsArray, Float64Array, Int64Array, RecordBatch, StringArray, TimestampMillisecondArray,
+};
+use arrow::datatypes::{DataType, Field, Float64Type, Schema, TimeUnit, TimestampMicrosecondType};
+use arrow::ipc::writer::FileWriter;
+use common::{synthetic_bars, tiny_config, with_weekday_timestamps};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use rupture_engine::model::run_engine_with_episodes;
+use rupture_engine::pipeline::{load_bars, run_file, write_outputs};
+use rupture_engine::types::{Bar, Timestamp};
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+fn ohlcv(bars: &[Bar]) -> Vec<(String, f64, f64, f64, f64, f64)> {
+    bars.iter()
+        .map(|b| (b.ts.to_string(), b.open, b.high, b.low, b.close, b.volume))
+        .collect()
+}
+
+/// Bars as a record batch with upper-case column names, an integer volume
+/// column and the given timestamp column.
+fn bars_batch(bars: &[Bar], ts: (DataType, Arc<dyn Array>)) -> RecordBatch {
+    let floats = |f: fn(&Bar) -> f64| -> Arc<dyn Array> {
+        Arc::new(bars.iter().map(f).collect::<Float64Array>())
+    };
+    let schema = Schema::new(vec![
+        Field::new("Timestamp", ts.0, true),
+        Field::new("Open", DataType::Float64, true),
+        Field::new("High", DataType::Float64, true),
+        Field::new("Low", DataType::Float64, true),
+        Field::new("Close", DataType::Float64, true),
+        Field::new("Volume", DataType::Int64, true),
+    ]);
+    let volume = bars.iter().map(|b| b.volume.round() as i64);
+    RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            ts.1,
+            floats(|b| b.open),
+            floats(|b| b.high),
+            floats(|b| b.low),
+            floats(|b| b.close),
+            Arc::new(Int64Array::from_iter_values(volume)),
+        ],
+    )
+    .unwrap()
+}
+
+fn write_parquet(batch: &RecordBatch, path: &Path) {
+    let mut writer =
+        ArrowWriter::try_new(File::create(path).unwrap(), batch.schema(), None).unwrap();
+    writer.write(batch).unwrap();
+    writer.close().unwrap();
+}
+
+#[test]
+fn test_parquet_input_matches_bars() {
+    let mut bars = synthetic_bars(400);
+    for bar in &mut bars {
+        bar.volume = bar.volume.round();
+    }
+    let labels: Vec<String> = bars.iter().map(|b| b.ts.to_string()).collect();
+    let batch = bars_batch(&bars, (DataType::Utf8, Arc::new(StringArray::from(labels))));
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("bars.parquet");
+    write_parquet(&batch, &path);
+
+    let config = tiny_config();
+    let loaded = load_bars(&path, &config).unwrap();
+    assert_eq!(ohlcv(&loaded), ohlcv(&bars));
+
+    let from_file = run_file(&path, &config).unwrap();
+    let direct = run_engine_with_episodes(&bars, &config).unwrap();
+    let rho = |rows: &[rupture_engine::types::EngineOutputRow]| -> Vec<f64> {
+        rows.iter().map(|r| r.rho).collect()
+    };
+    assert_eq!(rho(&from_file.rows), rho(&direct.rows));
+}
+
+#[test]
+fn test_arrow_ipc_input_with_timestamp_column() {
+    let bars = with_weekday_timestamps(synthetic_bars(50), 10, 0);
+    let millis: Vec<Option<i64>> = bars
+        .iter()
+        .map(|b| Some(b.ts.as_datetime().unwrap().timestamp_millis()))
+        .collect();
+    let mut batch = bars_batch(
+        &bars,
+        (
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            Arc::new(TimestampMillisecondArray::from(millis)),
+        ),
+    );
+    // A missing close on the third row.
+    let mut closes: Vec<Option<f64>> = bars.iter().map(|b| Some(b.close)).collect();
+    closes[2] = None;
+    let mut columns = batch.columns().to_vec();
+    columns[4] = Arc::new(Float64Array::from(closes));
+    batch = RecordBatch::try_new(batch.schema(), columns).unwrap();
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("bars.arrow");
+    let mut writer = FileWriter::try_new(File::create(&path).unwrap(), &batch.schema()).unwrap();
+    writer.write(&batch).unwrap();
+    writer.finish().unwrap();
+
+    let mut config = tiny_config();
+    config.io.parse_timestamps = true;
+    let loaded = load_bars(&path, &config).unwrap();
+    assert_eq!(loaded.len(), bars.len() - 1);
+    assert!(matches!(loaded[0].ts, Timestamp::Parsed(_)));
+    assert_eq!(loaded[0].ts, bars[0].ts);
+    assert_eq!(loaded[2].ts, bars[3].ts);
+
+    config.io.drop_invalid_rows = false;
+    let err = load_bars(&path, &config).unwrap_err().to_string();
+    assert!(err.contains("line 3"), "{}", err);
+}
+
+#[test]
+fn test_parquet_timeseries_output_is_typed() {
+    let bars = with_weekday_timestamps(synthetic_bars(400), 100, 0);
+    let mut config = tiny_config();
+    config.io.parse_timestamps = true;
+    config.outputs.write_parquet_timeseries = true;
+    let run = run_engine_with_episodes(&bars, &config).unwrap();
+
+    let dir = TempDir::new().unwrap();
+    write_outputs(&run, &config, dir.path()).unwrap();
+    let file = File::open(dir.path().join("rupture_timeseries.parquet")).unwrap();
+    let batches: Vec<RecordBatch> = ParquetRecordBatchReaderBuilder::try_new(file)
+        .unwrap()
+        .build()
+        .unwrap()
+        .map(|b| b.unwrap())
+        .collect();
+    let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+    assert_eq!(batch.num_rows(), run.rows.len());
+
+    let schema = batch.schema();
+    let data_type = |name: &str| schema.field_with_name(name).unwrap().data_type().clone();
+    assert_eq!(
+        data_type("timestamp"),
+        DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+    );
+    assert_eq!(data_type("rho"), DataType::Float64);
+    assert_eq!(data_type("r_combined"), DataType::Float64);
+    assert_eq!(data_type("state_code"), DataType::Utf8);
+    assert_eq!(data_type("confirmed"), DataType::Boolean);
+    assert_eq!(data_type("gap"), DataType::UInt64);
+
+    let column = |name: &str| batch.column(schema.index_of(name).unwrap()).clone();
+    let rho = column("rho");
+    let rho = rho.as_primitive::<Float64Type>();
+    let ts = column("timestamp");
+    let ts = ts.as_primitive::<TimestampMicrosecondType>();
+    let codes = column("state_code");
+    let codes = codes.as_string::<i32>();
+    for (i, row) in run.rows.iter().enumerate() {
+        assert_eq!(rho.value(i).to_bits(), row.rho.to_bits());
+        assert_eq!(
+            ts.value(i),
+            row.timestamp.as_datetime().unwrap().timestamp_micros()
+        );
+        assert_eq!(codes.value(i), row.state.code());
+    }
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{synthetic_bars, tiny_config};
+use rupture_engine::config::VolatilityEstimator;
+use rupture_engine::features::{
+    compute_acceleration, compute_normed, compute_range_vol, compute_returns, FeatureState,
+};
+use rupture_engine::model::run_engine;
+use rupture_engine::types::Bar;
+
+/// Close-to-close returns cycle through a fixed pattern that never crosses
+/// theta_vol, while the intraday range widens sharply for ten bars.
+fn intraday_stress_bars(n: usize) -> Vec<Bar> {
+    let cycle: [f64; 5] = [1.0, -1.0, 1.5, -1.5, 0.5];
+    let mut close = 100.0;
+    (0..n)
+        .map(|i| {
+            close *= (0.003 * cycle[i % cycle.len()]).exp();
+            let half_range = if (n / 2..n / 2 + 10).contains(&i) {
+                0.05
+            } else {
+                0.005
+            };
+            Bar {
+                ts: format!("t{:05}", i).into(),
+                open: close,
+                high: close * (1.0 + half_range),
+                low: close * (1.0 - half_range),
+                close,
+                volume: 1_000_000.0,
+                filled: false,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_incremental_range_matches_slice_features() {
+    let config = tiny_config();
+    let bars = synthetic_bars(200);
+    let n = config.windows.robust_scale_n;
+
+    for estimator in [
+        VolatilityEstimator::Parkinson,
+        VolatilityEstimator::GarmanKlass,
+        VolatilityEstimator::RogersSatchell,
+        VolatilityEstimator::YangZhang,
+    ] {
+        let returns = compute_returns(&bars, true);
+        let accel = compute_acceleration(&returns);
+        let range_vol = compute_range_vol(&bars, estimator, n);
+        let normed = compute_normed(&bars, &returns, &accel, &range_vol, n, 1.4826, 1e-12);
+
+        let mut state =
+            FeatureState::new(n, true, 1.4826, 1e-12).with_volatility_estimator(estimator, false);
+        for (i, bar) in bars.iter().enumerate() {
+            let got = state.update(bar);
+            assert_eq!(
+                got.g.to_bits(),
+                normed[i].g.to_bits(),
+                "{:?} bar {}",
+                estimator,
+                i
+            );
+            assert_eq!(got.u.to_bits(), normed[i].u.to_bits());
+        }
+    }
+}
+
+#[test]
+fn test_range_estimators_detect_intraday_stress() {
+    let bars = intraday_stress_bars(300);
+    let burst = 150..160;
+
+    let (rows, _) = run_engine(&bars, &tiny_config()).unwrap();
+    assert!(rows[burst.clone()].iter().all(|r| r.residuals[0] == 0.0));
+    assert!(rows.iter().all(|r| r.residuals.len() == 3));
+
+    let mut replace = tiny_config();
+    replace.features.volatility_estimator = VolatilityEstimator::Parkinson;
+    let (rows, _) = run_engine(&bars, &replace).unwrap();
+    assert!(rows[burst.clone()].iter().any(|r| r.residuals[0] > 0.0));
+
+    let mut channel = replace.clone();
+    channel.features.range_channel = true;
+    channel.validate().unwrap();
+    let (rows, _) = run_engine(&bars, &channel).unwrap();
+    assert!(rows[burst.clone()].iter().any(|r| r.residuals[3] > 0.0));
+    assert!(rows[burst].iter().all(|r| r.residuals[0] == 0.0));
+}
+
+#[test]
+fn test_range_channel_requires_range_estimator() {
+    let mut config = tiny_config();
+    config.features.range_channel = true;
+    assert!(config.validate().is_err());
+}
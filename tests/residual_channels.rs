@@ -0,0 +1,139 @@
+mod common;
+
+use common::{synthetic_bars, tiny_config};
+use rupture_engine::config::{ChannelConfig, Config};
+use rupture_engine::io::write_timeseries_csv;
+use rupture_engine::model::residuals::{ChannelInput, ChannelRegistry, ResidualChannel};
+use rupture_engine::model::{run_engine, RuptureEngine};
+use std::fs;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// Intrabar range relative to close, as a stand-in for a spread channel.
+#[derive(Debug)]
+struct RangeToClose {
+    name: String,
+    theta: f64,
+}
+
+impl ResidualChannel for RangeToClose {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn threshold(&self) -> f64 {
+        self.theta
+    }
+
+    fn activity(&self, input: &ChannelInput) -> f64 {
+        (input.bar.high - input.bar.low) / input.bar.close * 100.0
+    }
+}
+
+#[test]
+fn test_explicit_default_channels_match_implicit() {
+    let bars = synthetic_bars(300);
+    let implicit = tiny_config();
+    let mut explicit = implicit.clone();
+    explicit.residuals.channels = vec![
+        ChannelConfig::new("vol", implicit.residuals.theta_vol),
+        ChannelConfig::new("liq", implicit.residuals.theta_liq),
+        ChannelConfig::new("acc", implicit.residuals.theta_acc),
+    ];
+    explicit.validate().unwrap();
+
+    let (a, _) = run_engine(&bars, &implicit).unwrap();
+    let (b, _) = run_engine(&bars, &explicit).unwrap();
+    for (x, y) in a.iter().zip(&b) {
+        assert_eq!(x.residuals, y.residuals);
+        assert_eq!(x.rho.to_bits(), y.rho.to_bits());
+    }
+}
+
+#[test]
+fn test_channel_list_sets_csv_columns() {
+    let mut config = tiny_config();
+    config.residuals.channels = vec![
+        ChannelConfig::new("acc", 1.0),
+        ChannelConfig {
+            kind: "acc".into(),
+            name: Some("acc_strict".into()),
+            theta: 3.0,
+        },
+        ChannelConfig::new("gap", 0.0),
+    ];
+    config.validate().unwrap();
+
+    let mut engine = RuptureEngine::new(&config).unwrap();
+    let rows: Vec<_> = synthetic_bars(300)
+        .iter()
+        .flat_map(|bar| engine.update(bar))
+        .map(|(row, _)| row)
+        .collect();
+    assert!(rows.iter().all(|r| r.residuals.len() == 3));
+    assert!(rows.iter().all(|r| r.residuals[1] <= r.residuals[0]));
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("ts.csv");
+    write_timeseries_csv(&rows, &engine.channel_names(), &path).unwrap();
+    let header = fs::read_to_string(&path).unwrap();
+    assert!(header
+        .lines()
+        .next()
+        .unwrap()
+        .starts_with("timestamp,close,volume,r_acc,r_acc_strict,r_gap,r_combined,"));
+}
+
+#[test]
+fn test_invalid_channel_lists_are_rejected() {
+    let mut config = tiny_config();
+    config.residuals.channels = vec![ChannelConfig::new("spread", 1.0)];
+    // Not built in: a warning for the config, an error for the engine.
+    config.validate().unwrap();
+    assert_eq!(config.validation_report().warnings().count(), 1);
+    let err = RuptureEngine::new(&config).unwrap_err().to_string();
+    assert!(err.contains("unknown channel kind 'spread'"), "{}", err);
+
+    config.residuals.channels = vec![
+        ChannelConfig::new("vol", 1.0),
+        ChannelConfig::new("vol", 2.0),
+    ];
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_custom_channel_from_registry() {
+    let mut registry = ChannelRegistry::builtin();
+    registry.register("range_to_close", |cfg: &ChannelConfig| {
+        Arc::new(RangeToClose {
+            name: cfg.name().to_string(),
+            theta: cfg.theta,
+        }) as Arc<dyn ResidualChannel>
+    });
+
+    // Custom kinds can be listed in a config file.
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("custom.toml");
+    let tiny = fs::canonicalize("configs/tiny.toml").unwrap();
+    fs::write(
+        &path,
+        format!(
+            "extends = {:?}\n\n[[residuals.channels]]\nkind = \"vol\"\ntheta = 1.25\n\n\
+             [[residuals.channels]]\nkind = \"range_to_close\"\ntheta = 1.0\n",
+            tiny.to_string_lossy()
+        ),
+    )
+    .unwrap();
+    let config = Config::from_file(&path).unwrap();
+    assert!(RuptureEngine::new(&config).is_err());
+
+    let mut engine = RuptureEngine::with_registry(&config, &registry).unwrap();
+    let rows: Vec<_> = synthetic_bars(300)
+        .iter()
+        .flat_map(|bar| engine.update(bar))
+        .map(|(row, _)| row)
+        .collect();
+    // The synthetic burst widens the range well past 1% of close.
+    assert!(rows[150..170].iter().any(|r| r.residuals[1] > 0.0));
+    assert_eq!(engine.channel_names(), vec!["vol", "range_to_close"]);
+}
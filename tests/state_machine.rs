@@ -1,6 +1,7 @@
-use rupture_engine::config::StateMachineConfig;
+use chrono::{Duration, TimeZone, Utc};
+use rupture_engine::config::{Config, ConfirmMode, StateMachineConfig};
 use rupture_engine::model::state_machine::StateMachine;
-use rupture_engine::types::RuptureState;
+use rupture_engine::types::{RuptureState, Timestamp};
 
 fn default_cfg() -> StateMachineConfig {
     StateMachineConfig {
@@ -11,16 +12,17 @@ fn default_cfg() -> StateMachineConfig {
         confirm_m: 2,
         enable_recovery: true,
         recovery_hold: 2,
+        ..Default::default()
     }
 }
 
 #[test]
 fn test_stable_below_threshold() {
     let cfg = default_cfg();
-    let mut sm = StateMachine::new(cfg);
-    let rhos = vec![0.1, 0.2, 0.3, 0.4, 0.5];
+    let mut sm = StateMachine::new(cfg).unwrap();
+    let rhos = [0.1, 0.2, 0.3, 0.4, 0.5];
     for (i, &rho) in rhos.iter().enumerate() {
-        let (state, cand, conf) = sm.update(i, &format!("t{}", i), rho);
+        let (state, cand, conf) = sm.update(i, format!("t{}", i), rho);
         assert_eq!(state, RuptureState::Stable);
         assert!(!cand);
         assert!(!conf);
@@ -30,7 +32,7 @@ fn test_stable_below_threshold() {
 #[test]
 fn test_stressed_state() {
     let cfg = default_cfg();
-    let mut sm = StateMachine::new(cfg);
+    let mut sm = StateMachine::new(cfg).unwrap();
     let (state, _, _) = sm.update(0, "t0", 0.3);
     assert_eq!(state, RuptureState::Stable);
     let (state, _, _) = sm.update(1, "t1", 0.7);
@@ -40,7 +42,7 @@ fn test_stressed_state() {
 #[test]
 fn test_critical_state() {
     let cfg = default_cfg();
-    let mut sm = StateMachine::new(cfg);
+    let mut sm = StateMachine::new(cfg).unwrap();
     let (state, _, _) = sm.update(0, "t0", 0.3);
     assert_eq!(state, RuptureState::Stable);
     let (state, _, _) = sm.update(1, "t1", 0.9);
@@ -51,7 +53,7 @@ fn test_critical_state() {
 fn test_single_spike_no_confirmation() {
     // A single bar above 1.0 then dropping below should not confirm for k=3, m=2.
     let cfg = default_cfg();
-    let mut sm = StateMachine::new(cfg);
+    let mut sm = StateMachine::new(cfg).unwrap();
 
     // Build up to below threshold.
     sm.update(0, "t0", 0.5);
@@ -77,7 +79,7 @@ fn test_single_spike_no_confirmation() {
 fn test_sustained_above_confirms() {
     // Sustained above 1.0 for k bars should confirm with m-of-k.
     let cfg = default_cfg();
-    let mut sm = StateMachine::new(cfg);
+    let mut sm = StateMachine::new(cfg).unwrap();
 
     sm.update(0, "t0", 0.5);
     let (state, cand, _) = sm.update(1, "t1", 1.5);
@@ -99,7 +101,7 @@ fn test_sustained_above_confirms() {
 #[test]
 fn test_recovery_after_confirmed() {
     let cfg = default_cfg();
-    let mut sm = StateMachine::new(cfg);
+    let mut sm = StateMachine::new(cfg).unwrap();
 
     // Enter confirmed rupture.
     sm.update(0, "t0", 0.5);
@@ -122,7 +124,7 @@ fn test_recovery_after_confirmed() {
 fn test_flapping_case() {
     // Rho oscillates around 1.0. With m=2 of k=3, exactly 1-of-3 above should not confirm.
     let cfg = default_cfg();
-    let mut sm = StateMachine::new(cfg);
+    let mut sm = StateMachine::new(cfg).unwrap();
 
     sm.update(0, "t0", 0.5);
     sm.update(1, "t1", 1.1); // candidate
@@ -131,3 +133,256 @@ fn test_flapping_case() {
     assert!(!conf);
     assert_ne!(state, RuptureState::ConfirmedRupture);
 }
+
+#[test]
+fn test_wide_window_confirms_early() {
+    // m=3 of k=10: the third bar above confirms at once with confirm_early,
+    // otherwise only when the tenth bar of the window arrives.
+    let rhos = [0.5, 1.5, 0.9, 1.2, 1.1, 0.9, 0.9, 0.9, 0.9, 0.9, 0.9, 0.9];
+    let confirmed_at = |early: bool| {
+        let mut sm = StateMachine::new(StateMachineConfig {
+            confirm_k: 10,
+            confirm_m: 3,
+            confirm_early: early,
+            ..default_cfg()
+        })
+        .unwrap();
+        rhos.iter()
+            .enumerate()
+            .find(|&(i, &rho)| sm.update(i, format!("t{}", i), rho).2)
+            .map(|(i, _)| i)
+    };
+    assert_eq!(confirmed_at(true), Some(4));
+    assert_eq!(confirmed_at(false), Some(10));
+}
+
+#[test]
+fn test_time_confirmation() {
+    let cfg = StateMachineConfig {
+        confirm_mode: ConfirmMode::Time,
+        confirm_window: Some("10m".into()),
+        confirm_duration: Some("4m".into()),
+        ..default_cfg()
+    };
+    let start = Utc.with_ymd_and_hms(2024, 3, 1, 9, 30, 0).unwrap();
+    let at = |minutes: i64| Timestamp::from(start + Duration::minutes(minutes));
+    let run = |cfg: &StateMachineConfig, bars: &[(i64, f64)]| {
+        let mut sm = StateMachine::new(cfg.clone()).unwrap();
+        sm.update(0, at(-1), 0.5);
+        for (i, &(minute, rho)) in bars.iter().enumerate() {
+            if sm.update(i + 1, at(minute), rho).2 {
+                return Some(minute);
+            }
+        }
+        assert!(sm.events().iter().all(|e| e.confirmed_index.is_none()));
+        None
+    };
+
+    // Above for minutes 0-2 and 3-5 (each bar's rho holds until the next):
+    // four minutes by 09:35, confirmed when the window ends at 09:40.
+    let minutes: Vec<(i64, f64)> = (0..12)
+        .map(|m| (m, if [0, 1, 3, 4].contains(&m) { 1.2 } else { 0.5 }))
+        .collect();
+    assert_eq!(run(&cfg, &minutes), Some(10));
+    let early = StateMachineConfig {
+        confirm_early: true,
+        ..cfg.clone()
+    };
+    assert_eq!(run(&early, &minutes), Some(5));
+
+    // A 30 minute gap after the crossing holds rho above to the window end.
+    assert_eq!(run(&cfg, &[(0, 1.2), (30, 0.5)]), Some(30));
+    // Three minutes above is not enough.
+    assert_eq!(run(&cfg, &[(0, 1.2), (3, 0.5), (12, 0.5)]), None);
+}
+
+#[test]
+fn test_confirmation_config_validation() {
+    let mut config = Config::default();
+    config.state_machine.confirm_k = 20;
+    config.state_machine.confirm_m = 5;
+    config.validate().unwrap();
+
+    config.state_machine.confirm_mode = ConfirmMode::Time;
+    let report = config.validation_report();
+    let fields: Vec<&str> = report.errors().map(|e| e.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "state_machine.confirm_mode",
+            "state_machine.confirm_window",
+            "state_machine.confirm_duration"
+        ]
+    );
+
+    config.io.parse_timestamps = true;
+    config.state_machine.confirm_window = Some("30m".into());
+    config.state_machine.confirm_duration = Some("45m".into());
+    assert!(config.validate().is_err());
+    config.state_machine.confirm_duration = Some("10m".into());
+    config.validate().unwrap();
+}
+
+#[test]
+fn test_hysteresis_stops_flicker() {
+    let rhos = [0.3, 0.62, 0.58, 0.61, 0.57, 0.59, 0.45, 0.9, 0.8, 0.86, 0.7];
+    let states = |cfg: StateMachineConfig| {
+        let mut sm = StateMachine::new(cfg).unwrap();
+        rhos.iter()
+            .enumerate()
+            .map(|(i, &rho)| sm.update(i, format!("t{}", i), rho).0)
+            .collect::<Vec<_>>()
+    };
+    use RuptureState::{Critical, Stable, Stressed};
+
+    assert_eq!(
+        states(default_cfg()),
+        vec![
+            Stable, Stressed, Stable, Stressed, Stable, Stable, Stable, Critical, Stressed,
+            Critical, Stressed
+        ]
+    );
+    let banded = StateMachineConfig {
+        rho_stressed_exit: Some(0.5),
+        rho_critical_exit: Some(0.75),
+        ..default_cfg()
+    };
+    assert_eq!(
+        states(banded),
+        vec![
+            Stable, Stressed, Stressed, Stressed, Stressed, Stressed, Stable, Critical, Critical,
+            Critical, Stressed
+        ]
+    );
+}
+
+#[test]
+fn test_min_dwell_holds_state_but_not_crossings() {
+    let mut sm = StateMachine::new(StateMachineConfig {
+        min_dwell_bars: 3,
+        ..default_cfg()
+    })
+    .unwrap();
+    let states: Vec<RuptureState> = [0.7, 0.3, 0.3, 0.3, 0.9, 1.2]
+        .iter()
+        .enumerate()
+        .map(|(i, &rho)| sm.update(i, format!("t{}", i), rho).0)
+        .collect();
+    assert_eq!(
+        states,
+        vec![
+            RuptureState::Stressed,
+            RuptureState::Stressed,
+            RuptureState::Stressed,
+            RuptureState::Stable,
+            RuptureState::Stable,
+            RuptureState::CandidateRupture,
+        ]
+    );
+
+    let mut config = Config::default();
+    config.state_machine.rho_stressed_exit = Some(0.7);
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_invalid_definition_is_an_error() {
+    let cfg = default_cfg();
+    let declared = StateMachineConfig {
+        states: cfg.state_configs(),
+        transitions: Vec::new(),
+        ..cfg
+    };
+    let err = StateMachine::new(declared).unwrap_err().to_string();
+    assert!(
+        err.contains("needs a confirmed and a rejected transition"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_declared_builtin_definition_matches_default() {
+    let cfg = StateMachineConfig {
+        rho_critical_exit: Some(0.75),
+        min_dwell_bars: 2,
+        recovery_exit_rho: Some(0.5),
+        ..default_cfg()
+    };
+    let declared = StateMachineConfig {
+        states: cfg.state_configs(),
+        transitions: cfg.transition_configs(),
+        ..cfg.clone()
+    };
+    let trace = |cfg: StateMachineConfig| {
+        let mut sm = StateMachine::new(cfg).unwrap();
+        let steps: Vec<_> = (0..400)
+            .map(|i| {
+                let rho = 0.7 + 0.5 * (i as f64 * 0.21).sin() * (i as f64 * 0.047).cos();
+                let step = sm.update(i, format!("t{}", i), rho);
+                (step, sm.state_name().to_string())
+            })
+            .collect();
+        (steps, sm.events().len(), sm.episodes().len())
+    };
+
+    let builtin = trace(cfg);
+    assert!(builtin.2 > 0);
+    assert_eq!(builtin, trace(declared));
+}
+
+#[test]
+fn test_declared_fragile_level_without_recovery() {
+    let config: Config = toml::from_str(
+        r#"
+        [state_machine]
+        states = [
+            { name = "Calm", kind = "stable" },
+            { name = "Fragile", kind = "stressed", enter_rho = 0.45 },
+            { name = "Stressed", kind = "stressed", enter_rho = 0.60 },
+            { name = "Critical", kind = "critical", enter_rho = 0.85 },
+            { name = "Candidate", kind = "candidate" },
+            { name = "Confirmed", kind = "confirmed" },
+        ]
+        transitions = [
+            { from = "Fragile", to = "Candidate", on = "crossing" },
+            { from = "Stressed", to = "Candidate", on = "crossing" },
+            { from = "Critical", to = "Candidate", on = "crossing" },
+            { from = "Candidate", to = "Confirmed", on = "confirmed" },
+            { from = "Candidate", on = "rejected" },
+            { from = "Confirmed", to = "Calm", on = "below", rho = 0.5, bars = 2 },
+        ]
+        "#,
+    )
+    .unwrap();
+    config.validate().unwrap();
+
+    let mut sm = StateMachine::new(config.state_machine).unwrap();
+    let rhos = [0.3, 0.5, 1.1, 1.2, 1.3, 0.9, 0.4, 0.45, 0.2, 1.5];
+    let names: Vec<String> = rhos
+        .iter()
+        .enumerate()
+        .map(|(i, &rho)| {
+            sm.update(i, format!("t{}", i), rho);
+            sm.state_name().to_string()
+        })
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "Calm",
+            "Fragile",
+            "Candidate",
+            "Candidate",
+            "Confirmed",
+            "Confirmed",
+            "Confirmed",
+            "Calm",
+            "Calm",
+            // No crossing transition out of Calm.
+            "Critical",
+        ]
+    );
+    assert_eq!(sm.episodes()[0].end_index, Some(7));
+    assert_eq!(sm.episodes()[0].recovery_index, None);
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use common::{synthetic_bars, tiny_config};
+use rupture_engine::evaluate::{evaluate, LabelInterval, Tolerance};
+use rupture_engine::model::run_engine;
+use rupture_engine::sweep::{run_sweep, SweepLabels, SweepSpec};
+use rupture_engine::types::Timestamp;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_spec_expands_lists_and_ranges() {
+    let spec = SweepSpec::parse(
+        r#"
+[params]
+"residuals.theta_vol" = [1.0, 1.5]
+"memory.alpha" = { start = 0.55, stop = 0.75, step = 0.1 }
+"state_machine.confirm_k" = { start = 2, stop = 3, step = 1 }
+"#,
+    )
+    .unwrap();
+    assert_eq!(spec.len(), 12);
+
+    let alpha = &spec.params[0];
+    assert_eq!(alpha.0, "memory.alpha");
+    let alphas: Vec<f64> = alpha.1.iter().map(|v| v.as_float().unwrap()).collect();
+    assert_eq!(alphas, vec![0.55, 0.65, 0.75]);
+    assert_eq!(spec.params[2].1[1].as_integer(), Some(3));
+
+    let combos = spec.combinations();
+    assert_eq!(combos.len(), 12);
+    assert_eq!(combos[2][1].1.as_float(), Some(1.5));
+
+    let config = tiny_config();
+    spec.check_keys(&config).unwrap();
+    let typo = SweepSpec::parse("[params]\n\"memory.alhpa\" = [0.5]\n").unwrap();
+    assert!(typo.check_keys(&config).is_err());
+    let fixed = SweepSpec::parse("[params]\n\"io.timezone\" = [\"UTC\"]\n").unwrap();
+    assert!(fixed.check_keys(&config).is_err());
+}
+
+#[test]
+fn test_sweep_optional_keys() {
+    let bars = synthetic_bars(400);
+    let base = tiny_config();
+    assert_eq!(base.state_machine.rho_critical_exit, None);
+    let spec = SweepSpec::parse(
+        "[params]\n\"state_machine.rho_critical_exit\" = [0.7, 0.8]\n\
+         \"state_machine.recovery_exit_rho\" = [0.6]\n",
+    )
+    .unwrap();
+    spec.check_keys(&base).unwrap();
+
+    let results = run_sweep(&bars, &base, &spec, None, 2);
+    for (result, exit) in results.iter().zip([0.7, 0.8]) {
+        let mut config = base.clone();
+        config.state_machine.rho_critical_exit = Some(exit);
+        config.state_machine.recovery_exit_rho = Some(0.6);
+        let (_, events) = run_engine(&bars, &config).unwrap();
+        assert_eq!(result.result.as_ref().unwrap().candidates, events.len());
+    }
+}
+
+#[test]
+fn test_sweep_matches_single_runs_and_scores_labels() {
+    let bars = synthetic_bars(400);
+    let base = tiny_config();
+    let spec = SweepSpec::parse(
+        "[params]\n\"memory.alpha\" = [0.5, 0.65, 1.5]\n\"state_machine.rho_rupture\" = [1.0]\n",
+    )
+    .unwrap();
+
+    // Label the interval around the confirmed rupture in the volatility
+    // burst of the base run, and a quiet interval.
+    let (_, events) = run_engine(&bars, &base).unwrap();
+    let hit = events
+        .iter()
+        .find(|e| e.confirmed_index.is_some() && e.candidate_index >= 200)
+        .unwrap();
+    let ts = |i: usize| bars[i].ts.clone();
+    let labels = vec![
+        LabelInterval {
+            name: "burst".into(),
+            start: ts(hit.candidate_index - 2),
+            end: ts(hit.candidate_index + 2),
+        },
+        LabelInterval {
+            name: "quiet".into(),
+            start: ts(20),
+            end: ts(21),
+        },
+    ];
+
+    let results = run_sweep(
+        &bars,
+        &base,
+        &spec,
+        Some(SweepLabels {
+            labels: &labels,
+            tolerance: Tolerance::Bars(0),
+        }),
+        2,
+    );
+    assert_eq!(results.len(), 3);
+    let error = results[2].result.as_ref().unwrap_err();
+    assert!(error.contains("memory.alpha"), "{}", error);
+
+    let metrics = results[1].result.as_ref().unwrap();
+    assert_eq!(metrics.candidates, events.len());
+    assert!((metrics.time_in_state.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    let evaluation = metrics.evaluation.as_ref().unwrap();
+    assert_eq!(evaluation.labels, 2);
+    assert_eq!(evaluation.labels_hit, 1);
+    assert_eq!(evaluation.recall(), 0.5);
+
+    let timestamps: Vec<Timestamp> = bars.iter().map(|b| b.ts.clone()).collect();
+    assert_eq!(
+        evaluation,
+        &evaluate(&events, &timestamps, &labels, Tolerance::Bars(0)).unwrap()
+    );
+}
+
+#[test]
+fn test_sweep_cli_writes_results() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("spec.toml"),
+        "[params]\n\"residuals.theta_vol\" = [1.0, 1.25]\n\"combine.tau\" = [0.35, -1.0]\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("labels.csv"),
+        "start,end,name\n2005-01-10,2005-01-20,early\n",
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("rupture-engine"))
+        .arg("sweep")
+        .args(["--input", "data/fixtures/tiny_ohlcv_60.csv"])
+        .args(["--config", "configs/tiny.toml"])
+        .arg("--spec")
+        .arg(dir.path().join("spec.toml"))
+        .arg("--labels")
+        .arg(dir.path().join("labels.csv"))
+        .arg("--output-dir")
+        .arg(dir.path())
+        .assert()
+        .success();
+
+    let table = fs::read_to_string(dir.path().join("sweep_results.csv")).unwrap();
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with("combination,combine.tau,residuals.theta_vol,status,"));
+    assert!(lines[0].contains(",time_stable,") && lines[0].ends_with(",f1,error"));
+    assert!(lines[1].contains(",ok,"));
+    assert!(lines[3].contains(",failed,"));
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::tiny_config;
+use rupture_engine::error::RuptureError;
+use rupture_engine::io::read_bars_csv;
+use rupture_engine::preprocess::clean_bars;
+use std::fs;
+use tempfile::TempDir;
+
+const US_DATES: &str = "timestamp,open,high,low,close,volume
+1/2/2008,10,11,9,10.5,100
+12/31/2007,10,11,9,10.2,100
+12/1/2007,10,11,9,10.1,100
+";
+
+#[test]
+fn test_parsed_timestamps_sort_chronologically() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("bars.csv");
+    fs::write(&path, US_DATES).unwrap();
+
+    let mut config = tiny_config();
+    config.io.parse_timestamps = true;
+    config.io.timestamp_format = "%m/%d/%Y".into();
+
+    let bars = clean_bars(read_bars_csv(&path, &config.io).unwrap(), true);
+    let order: Vec<String> = bars.iter().map(|b| b.ts.to_string()).collect();
+    assert_eq!(
+        order,
+        vec![
+            "2007-12-01T00:00:00Z",
+            "2007-12-31T00:00:00Z",
+            "2008-01-02T00:00:00Z"
+        ]
+    );
+
+    // Opaque strings keep the old lexicographic order.
+    config.io.parse_timestamps = false;
+    let bars = clean_bars(read_bars_csv(&path, &config.io).unwrap(), true);
+    assert_eq!(bars[0].ts.to_string(), "1/2/2008");
+}
+
+#[test]
+fn test_unparseable_timestamp_is_parse_error() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("bars.csv");
+    fs::write(&path, US_DATES).unwrap();
+
+    let mut config = tiny_config();
+    config.io.parse_timestamps = true;
+    config.io.timestamp_format = "rfc3339".into();
+
+    match read_bars_csv(&path, &config.io) {
+        Err(RuptureError::Parse { line, .. }) => assert_eq!(line, 2),
+        other => panic!("expected parse error, got {:?}", other.map(|b| b.len())),
+    }
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::{synthetic_bars, tiny_config};
+use rupture_engine::config::StateMachineConfig;
+use rupture_engine::model::run_engine_with_episodes;
+use rupture_engine::model::state_machine::StateMachine;
+use rupture_engine::pipeline::write_outputs;
+use rupture_engine::types::{StateTransition, TransitionRule};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_transition_log_records_every_change() {
+    let mut sm = StateMachine::new(StateMachineConfig::default()).unwrap();
+    let rhos = [
+        0.3, 0.7, 0.7, 0.9, 1.2, 1.1, 0.5, 0.4, 0.5, 0.8, 0.2, 1.3, 0.3, 0.3,
+    ];
+    for (i, &rho) in rhos.iter().enumerate() {
+        sm.update(i, format!("t{}", i), rho);
+    }
+
+    let log: Vec<(usize, &str, &str, TransitionRule)> = sm
+        .transitions()
+        .iter()
+        .map(|t| (t.index, t.from.as_str(), t.to.as_str(), t.rule))
+        .collect();
+    use TransitionRule::*;
+    assert_eq!(
+        log,
+        vec![
+            (1, "Stable", "Stressed", Level),
+            (3, "Stressed", "Critical", Level),
+            (4, "Critical", "Candidate rupture", Crossing),
+            (6, "Candidate rupture", "Confirmed rupture", Confirmed),
+            (8, "Confirmed rupture", "Recovery", Below),
+            (9, "Recovery", "Stressed", Below),
+            (10, "Stressed", "Stable", Level),
+            (11, "Stable", "Candidate rupture", Crossing),
+            (13, "Candidate rupture", "Stable", Rejected),
+        ]
+    );
+    let confirmed = &sm.transitions()[3];
+    assert_eq!(confirmed.rho, 0.5);
+    assert_eq!(confirmed.timestamp.to_string(), "t6");
+}
+
+#[test]
+fn test_default_recovery_holds_until_rho_falls_and_allows_new_ruptures() {
+    let mut sm = StateMachine::new(StateMachineConfig::default()).unwrap();
+    let rhos = [
+        0.3, 1.2, 1.1, 1.1, 0.5, 0.5, 0.9, 0.95, 1.2, 1.1, 1.1, 0.4, 0.4, 0.4,
+    ];
+    for (i, &rho) in rhos.iter().enumerate() {
+        sm.update(i, format!("t{}", i), rho);
+    }
+
+    let log: Vec<(usize, &str, &str, TransitionRule)> = sm
+        .transitions()
+        .iter()
+        .map(|t| (t.index, t.from.as_str(), t.to.as_str(), t.rule))
+        .collect();
+    use TransitionRule::*;
+    assert_eq!(
+        log,
+        vec![
+            (1, "Stable", "Candidate rupture", Crossing),
+            (3, "Candidate rupture", "Confirmed rupture", Confirmed),
+            (5, "Confirmed rupture", "Recovery", Below),
+            (8, "Recovery", "Candidate rupture", Crossing),
+            (10, "Candidate rupture", "Confirmed rupture", Confirmed),
+            (12, "Confirmed rupture", "Recovery", Below),
+            (13, "Recovery", "Stable", Below),
+        ]
+    );
+}
+
+#[test]
+fn test_transition_log_matches_state_column_and_is_jsonl() {
+    let config = tiny_config();
+    let run = run_engine_with_episodes(&synthetic_bars(400), &config).unwrap();
+
+    let changes: Vec<(usize, &str, &str)> = run
+        .rows
+        .windows(2)
+        .enumerate()
+        .filter(|(_, w)| w[0].state_label != w[1].state_label)
+        .map(|(i, w)| (i + 1, w[0].state_label.as_str(), w[1].state_label.as_str()))
+        .collect();
+    let logged: Vec<(usize, &str, &str)> = run
+        .transitions
+        .iter()
+        .filter(|t| t.index > 0)
+        .map(|t| (t.index, t.from.as_str(), t.to.as_str()))
+        .collect();
+    assert!(!changes.is_empty());
+    assert_eq!(changes, logged);
+
+    let dir = TempDir::new().unwrap();
+    write_outputs(&run, &config, dir.path()).unwrap();
+    let text = fs::read_to_string(dir.path().join("rupture_transitions.jsonl")).unwrap();
+    let parsed: Vec<StateTransition> = text
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(parsed, run.transitions);
+    assert!(text.contains(r#""rule":"crossing""#));
+}